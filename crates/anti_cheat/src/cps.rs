@@ -0,0 +1,473 @@
+//! Detects autoclickers from the timings of the clicks (arm swings) of the players.
+//!
+//! Humans click with an irregular rhythm, the intervals between their clicks vary a lot and have
+//! a few outliers. Simple autoclickers click faster than humans can, click with almost the same
+//! interval every time or pick random intervals from a fixed range (which has no outliers, so the
+//! kurtosis of the intervals is low).
+
+use std::{collections::VecDeque, time::Instant};
+
+use valence::{
+    event_loop::PacketEvent,
+    prelude::*,
+    protocol::{
+        packets::play::{
+            player_action_c2s::PlayerAction, player_interact_entity_c2s::EntityInteraction,
+            HandSwingC2s, PlayerActionC2s, PlayerInteractEntityC2s,
+        },
+        Hand,
+    },
+};
+
+use crate::{CheatCheck, CheatSuspicionEvent};
+
+/// The excess kurtosis of uniformly distributed intervals.
+const UNIFORM_KURTOSIS: f64 = -1.2;
+/// Humans can click with a low kurtosis by chance, so it alone is never certain.
+const MAX_KURTOSIS_CONFIDENCE: f64 = 0.8;
+
+pub struct CpsPlugin;
+
+impl Plugin for CpsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CpsConfig>()
+            .add_systems(Update, (add_click_timings, record_clicks).chain());
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct CpsConfig {
+    /// How many clicks are analysed.
+    pub window: usize,
+    /// Clicks after a longer pause start a new series, the pause is not part of the analysis.
+    pub max_interval_ms: f64,
+    /// The minimum number of intervals before anything is flagged.
+    pub min_intervals: usize,
+    /// More clicks per second are always flagged.
+    pub max_cps: f64,
+    /// The consistency is only checked from this many clicks per second.
+    pub min_cps_for_consistency: f64,
+    /// A lower standard deviation of the intervals (in milliseconds) is suspicious.
+    pub min_std_dev_ms: f64,
+    /// A lower excess kurtosis of the intervals is suspicious (uniform random intervals have -1.2).
+    /// Only the kurtosis is not enough for a confidence over 0.8.
+    pub min_kurtosis: f64,
+    /// Suspicions with a lower confidence are not reported.
+    pub min_confidence: f32,
+    /// How many clicks have to pass before the same player is reported again.
+    pub report_interval: usize,
+    /// Swings are counted as clicks again if the player did not stop digging for this long, so a
+    /// client that never stops digging can not hide its clicks.
+    pub max_digging_ms: f64,
+}
+
+impl Default for CpsConfig {
+    fn default() -> Self {
+        Self {
+            window: 40,
+            max_interval_ms: 500.0,
+            min_intervals: 20,
+            max_cps: 20.0,
+            min_cps_for_consistency: 8.0,
+            min_std_dev_ms: 12.0,
+            min_kurtosis: -0.8,
+            min_confidence: 0.5,
+            report_interval: 40,
+            max_digging_ms: 60_000.0,
+        }
+    }
+}
+
+/// The statistics of the intervals between clicks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClickStats {
+    pub intervals: usize,
+    pub cps: f64,
+    /// The mean interval in milliseconds.
+    pub mean_ms: f64,
+    pub variance: f64,
+    pub std_dev_ms: f64,
+    /// The excess kurtosis (0 for a normal distribution).
+    pub kurtosis: f64,
+}
+
+impl ClickStats {
+    /// Analyses the intervals between clicks in milliseconds, `None` if there are less than 2.
+    pub fn from_intervals(intervals: &[f64]) -> Option<Self> {
+        if intervals.len() < 2 {
+            return None;
+        }
+
+        let n = intervals.len() as f64;
+        let mean = intervals.iter().sum::<f64>() / n;
+
+        if mean <= 0.0 {
+            return None;
+        }
+
+        let moment = |power: i32| {
+            intervals
+                .iter()
+                .map(|interval| (interval - mean).powi(power))
+                .sum::<f64>()
+                / n
+        };
+
+        let variance = moment(2);
+        let kurtosis = if variance > 0.0 {
+            moment(4) / (variance * variance) - 3.0
+        } else {
+            // All intervals are the same, this is as consistent as it gets.
+            -3.0
+        };
+
+        Some(Self {
+            intervals: intervals.len(),
+            cps: 1000.0 / mean,
+            mean_ms: mean,
+            variance,
+            std_dev_ms: variance.sqrt(),
+            kurtosis,
+        })
+    }
+
+    /// How suspicious the clicks are (0.0 - 1.0) and why.
+    pub fn suspicion(&self, config: &CpsConfig) -> (f32, String) {
+        if self.intervals < config.min_intervals {
+            return (0.0, String::new());
+        }
+
+        if self.cps > config.max_cps {
+            return (1.0, format!("{:.1} cps", self.cps));
+        }
+
+        if self.cps < config.min_cps_for_consistency {
+            return (0.0, String::new());
+        }
+
+        let mut confidence = 0.0_f64;
+        let mut reasons = Vec::new();
+
+        if self.std_dev_ms < config.min_std_dev_ms {
+            confidence = confidence.max(1.0 - self.std_dev_ms / config.min_std_dev_ms);
+            reasons.push(format!("std dev {:.1}ms", self.std_dev_ms));
+        }
+
+        if self.kurtosis < config.min_kurtosis {
+            // Reaches the highest confidence at the kurtosis of uniform random intervals.
+            let range = (config.min_kurtosis - UNIFORM_KURTOSIS).max(f64::EPSILON);
+            let kurtosis_confidence = ((config.min_kurtosis - self.kurtosis) / range).min(1.0);
+
+            confidence = confidence.max(kurtosis_confidence * MAX_KURTOSIS_CONFIDENCE);
+            reasons.push(format!("kurtosis {:.2}", self.kurtosis));
+        }
+
+        // Both signals together are more convincing than each alone.
+        if reasons.len() > 1 {
+            confidence = (confidence * 1.25).min(1.0);
+        }
+
+        (
+            confidence as f32,
+            format!("{:.1} cps, {}", self.cps, reasons.join(", ")),
+        )
+    }
+}
+
+/// The recent clicks of a player, added to all clients.
+#[derive(Component, Debug, Default)]
+pub struct ClickTimings {
+    /// The intervals between the recent clicks in milliseconds.
+    intervals: VecDeque<f64>,
+    last_click: Option<Instant>,
+    /// Mining a block swings the arm continuously, these swings are not clicks. The time when the
+    /// player started digging.
+    digging_since: Option<Instant>,
+    clicks_since_report: usize,
+}
+
+impl ClickTimings {
+    /// Records a click, the oldest intervals are dropped to keep `window` intervals.
+    pub fn record(&mut self, time: Instant, config: &CpsConfig) {
+        if let Some(last_click) = self.last_click {
+            let interval = time.duration_since(last_click).as_secs_f64() * 1000.0;
+
+            if interval <= config.max_interval_ms {
+                self.intervals.push_back(interval);
+
+                while self.intervals.len() > config.window {
+                    self.intervals.pop_front();
+                }
+            }
+        }
+
+        self.last_click = Some(time);
+        self.clicks_since_report += 1;
+    }
+
+    /// The statistics of the recent clicks.
+    pub fn stats(&self) -> Option<ClickStats> {
+        let intervals: Vec<f64> = self.intervals.iter().copied().collect();
+
+        ClickStats::from_intervals(&intervals)
+    }
+
+    /// If the swings at the time are from digging (and not clicks).
+    pub fn is_digging(&self, time: Instant, config: &CpsConfig) -> bool {
+        self.digging_since.is_some_and(|since| {
+            time.saturating_duration_since(since).as_secs_f64() * 1000.0 <= config.max_digging_ms
+        })
+    }
+
+    /// The clicks per second over the recent clicks.
+    pub fn cps(&self) -> f64 {
+        self.stats().map_or(0.0, |stats| stats.cps)
+    }
+}
+
+fn add_click_timings(
+    mut commands: Commands,
+    clients: Query<Entity, (With<Client>, Without<ClickTimings>)>,
+) {
+    for entity in clients.iter() {
+        commands.entity(entity).insert(ClickTimings::default());
+    }
+}
+
+/// The packets are used instead of the swing events for their receive time, the events of a tick
+/// would all have the same time.
+fn record_clicks(
+    mut clients: Query<(&mut ClickTimings, &GameMode)>,
+    mut packets: EventReader<PacketEvent>,
+    mut suspicion_writer: EventWriter<CheatSuspicionEvent>,
+    config: Res<CpsConfig>,
+) {
+    for packet in packets.read() {
+        let Ok((mut timings, game_mode)) = clients.get_mut(packet.client) else {
+            continue;
+        };
+
+        if let Some(action) = packet.decode::<PlayerActionC2s>() {
+            match action.action {
+                // Blocks break instantly in creative, there is no stop action.
+                PlayerAction::StartDestroyBlock if *game_mode != GameMode::Creative => {
+                    timings.digging_since = Some(packet.timestamp)
+                }
+                PlayerAction::StartDestroyBlock
+                | PlayerAction::AbortDestroyBlock
+                | PlayerAction::StopDestroyBlock => timings.digging_since = None,
+                _ => {}
+            }
+
+            continue;
+        }
+
+        // Players can not attack while they dig.
+        if let Some(interact) = packet.decode::<PlayerInteractEntityC2s>() {
+            if interact.interact == EntityInteraction::Attack {
+                timings.digging_since = None;
+            }
+
+            continue;
+        }
+
+        let Some(swing) = packet.decode::<HandSwingC2s>() else {
+            continue;
+        };
+
+        if swing.hand != Hand::Main || timings.is_digging(packet.timestamp, &config) {
+            continue;
+        }
+
+        timings.record(packet.timestamp, &config);
+
+        if timings.clicks_since_report < config.report_interval {
+            continue;
+        }
+
+        let Some(stats) = timings.stats() else {
+            continue;
+        };
+
+        let (confidence, details) = stats.suspicion(&config);
+
+        if confidence < config.min_confidence {
+            continue;
+        }
+
+        timings.clicks_since_report = 0;
+
+        suspicion_writer.send(CheatSuspicionEvent {
+            player: packet.client,
+            check: CheatCheck::Autoclicker,
+            confidence,
+            details,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// A human clicking at about 9.5 cps, with a few slow clicks.
+    const HUMAN_INTERVALS: [f64; 40] = [
+        94.0, 92.0, 108.0, 99.0, 198.0, 136.0, 88.0, 126.0, 81.0, 67.0, 103.0, 80.0, 53.0, 213.0,
+        103.0, 95.0, 75.0, 85.0, 138.0, 78.0, 59.0, 58.0, 179.0, 134.0, 110.0, 114.0, 78.0, 82.0,
+        109.0, 126.0, 89.0, 220.0, 93.0, 125.0, 110.0, 68.0, 68.0, 105.0, 93.0, 76.0,
+    ];
+
+    /// Butterfly clicking at about 16 cps, two fingers alternate so the intervals alternate
+    /// between short and long.
+    const BUTTERFLY_INTERVALS: [f64; 40] = [
+        39.0, 73.0, 46.0, 77.0, 36.0, 95.0, 36.0, 161.0, 33.0, 61.0, 44.0, 67.0, 19.0, 79.0, 44.0,
+        75.0, 30.0, 70.0, 131.0, 67.0, 22.0, 57.0, 27.0, 94.0, 48.0, 84.0, 31.0, 68.0, 47.0, 161.0,
+        37.0, 83.0, 39.0, 90.0, 47.0, 61.0, 26.0, 80.0, 39.0, 65.0,
+    ];
+
+    /// Records a click after every interval (in milliseconds).
+    fn click(intervals: &[f64], config: &CpsConfig) -> ClickTimings {
+        let mut timings = ClickTimings::default();
+        let mut time = Instant::now();
+
+        timings.record(time, config);
+
+        for interval in intervals {
+            time += Duration::from_secs_f64(interval / 1000.0);
+            timings.record(time, config);
+        }
+
+        timings
+    }
+
+    fn suspicion(intervals: &[f64]) -> (ClickStats, f32) {
+        let config = CpsConfig::default();
+        let stats = click(intervals, &config).stats().unwrap();
+        let (confidence, _) = stats.suspicion(&config);
+
+        assert!((0.0..=1.0).contains(&confidence));
+
+        (stats, confidence)
+    }
+
+    fn flagged(confidence: f32) -> bool {
+        confidence >= CpsConfig::default().min_confidence
+    }
+
+    #[test]
+    fn constant_clicks_are_flagged() {
+        let (stats, confidence) = suspicion(&[80.0; 60]);
+
+        // Only the last `window` intervals are analysed.
+        assert_eq!(stats.intervals, CpsConfig::default().window);
+        assert!((stats.cps - 12.5).abs() < 0.01);
+        assert!(stats.std_dev_ms < 0.01);
+        assert!(flagged(confidence));
+        assert!(confidence > 0.99, "confidence {confidence}");
+    }
+
+    #[test]
+    fn jittered_human_clicks_are_not_flagged() {
+        let (stats, confidence) = suspicion(&HUMAN_INTERVALS);
+
+        assert!((9.0..10.0).contains(&stats.cps), "cps {}", stats.cps);
+        assert!(stats.std_dev_ms > CpsConfig::default().min_std_dev_ms);
+        assert!(stats.kurtosis > CpsConfig::default().min_kurtosis);
+        assert!(!flagged(confidence));
+        assert_eq!(confidence, 0.0);
+    }
+
+    #[test]
+    fn butterfly_clicks_are_not_flagged() {
+        let (stats, confidence) = suspicion(&BUTTERFLY_INTERVALS);
+
+        assert!((15.0..17.0).contains(&stats.cps), "cps {}", stats.cps);
+        assert!(!flagged(confidence));
+        assert_eq!(confidence, 0.0);
+    }
+
+    #[test]
+    fn too_fast_butterfly_clicks_are_flagged() {
+        let intervals = BUTTERFLY_INTERVALS.map(|interval| interval / 2.0);
+        let (stats, confidence) = suspicion(&intervals);
+
+        assert!(stats.cps > CpsConfig::default().max_cps);
+        assert!(flagged(confidence));
+        assert_eq!(confidence, 1.0);
+    }
+
+    /// An autoclicker that picks random intervals between `min` and `max`. The intervals are
+    /// spread evenly over the range, so the test does not depend on a random seed.
+    fn uniform_intervals(min: f64, max: f64) -> Vec<f64> {
+        const COUNT: usize = 40;
+
+        // 17 and 40 are coprime, so this visits every step once in a scrambled order.
+        (0..COUNT)
+            .map(|i| (i * 17) % COUNT)
+            .map(|step| min + (max - min) * step as f64 / (COUNT - 1) as f64)
+            .collect()
+    }
+
+    #[test]
+    fn uniform_random_clicks_are_flagged() {
+        let config = CpsConfig::default();
+        let (stats, confidence) = suspicion(&uniform_intervals(70.0, 130.0));
+
+        assert!((stats.cps - 10.0).abs() < 0.01, "cps {}", stats.cps);
+        // The intervals vary like the ones of a human...
+        assert!(stats.std_dev_ms > config.min_std_dev_ms);
+        // ...but they have no outliers.
+        assert!(
+            (stats.kurtosis - UNIFORM_KURTOSIS).abs() < 0.05,
+            "kurtosis {}",
+            stats.kurtosis
+        );
+
+        // The kurtosis alone is never certain.
+        assert!(flagged(confidence));
+        assert!(
+            (f64::from(confidence) - MAX_KURTOSIS_CONFIDENCE).abs() < 1e-6,
+            "confidence {confidence}"
+        );
+    }
+
+    #[test]
+    fn uniform_random_clicks_in_a_small_range_are_certain() {
+        let config = CpsConfig::default();
+        let stats = click(&uniform_intervals(85.0, 115.0), &config)
+            .stats()
+            .unwrap();
+        let (confidence, details) = stats.suspicion(&config);
+
+        // A low standard deviation and a low kurtosis together.
+        assert!(stats.std_dev_ms < config.min_std_dev_ms);
+        assert!(stats.kurtosis < config.min_kurtosis);
+        assert!(details.contains("std dev") && details.contains("kurtosis"));
+        assert_eq!(confidence, 1.0);
+    }
+
+    #[test]
+    fn digging_ends_after_the_timeout() {
+        let config = CpsConfig::default();
+        let start = Instant::now();
+        let timings = ClickTimings {
+            digging_since: Some(start),
+            ..Default::default()
+        };
+        let max_digging = Duration::from_secs_f64(config.max_digging_ms / 1000.0);
+
+        assert!(timings.is_digging(start + Duration::from_secs(1), &config));
+        assert!(!timings.is_digging(start + max_digging + Duration::from_secs(1), &config));
+        assert!(!ClickTimings::default().is_digging(start, &config));
+    }
+
+    #[test]
+    fn pauses_are_not_analysed() {
+        let config = CpsConfig::default();
+        let timings = click(&[100.0, 100.0, 2000.0, 100.0], &config);
+
+        assert_eq!(timings.stats().unwrap().intervals, 3);
+    }
+}
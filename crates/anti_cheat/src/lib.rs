@@ -0,0 +1,40 @@
+pub mod cps;
+pub mod movement;
+
+use valence::prelude::*;
+
+/// The check that detected a [`CheatSuspicionEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheatCheck {
+    /// Too many or too consistent clicks, see [`cps`].
+    Autoclicker,
+    /// Moving faster than allowed, see [`movement`].
+    Speed,
+    /// Moving up in the air.
+    Fly,
+    /// Falling faster than gravity allows or claiming to be on the ground in the air.
+    NoFall,
+}
+
+/// An event that will be fired when a check suspects a player of cheating.
+///
+/// The checks only report suspicions, punishing the player is up to the server.
+#[derive(Event, Debug, Clone)]
+pub struct CheatSuspicionEvent {
+    pub player: Entity,
+    pub check: CheatCheck,
+    /// How sure the check is (0.0 - 1.0).
+    pub confidence: f32,
+    /// A short description for logs and staff notifications.
+    pub details: String,
+}
+
+pub struct AntiCheatPlugin;
+
+impl Plugin for AntiCheatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CheatSuspicionEvent>()
+            .add_plugins(cps::CpsPlugin)
+            .add_plugins(movement::MovementCheckPlugin);
+    }
+}
@@ -0,0 +1,464 @@
+//! Validates the movement of the players against what the vanilla physics allow.
+//!
+//! Every movement packet is one client tick, so the distance of a packet is the velocity of the
+//! player in blocks per tick. The checks allow a bit more than vanilla (see [`MovementConfig`]).
+//! Moves in water, on ladders and other special blocks, moves shortly after taking damage or
+//! after the server set the velocity of the player (knockback) and moves with effects or items
+//! that change the vanilla movement (e.g. levitation or an elytra) are not checked.
+
+use std::time::{Duration, Instant};
+
+use utils::{
+    aabb_full_block_intersections,
+    damage::{DamageEvent, VelocityChangedEvent},
+    enchantments::{Enchantment, ItemStackEnchantmentsExt},
+    is_on_block,
+};
+use valence::{
+    entity::active_status_effects::ActiveStatusEffects, math::Aabb, movement::MovementEvent,
+    prelude::*, status_effects::StatusEffect,
+};
+
+use crate::{CheatCheck, CheatSuspicionEvent};
+
+/// The width of the hitbox of a player.
+const PLAYER_WIDTH: f64 = 0.6;
+/// The height of the hitbox of a player.
+const PLAYER_HEIGHT: f64 = 1.8;
+/// Players walk up blocks up to this height (e.g. slabs) without jumping.
+const STEP_HEIGHT: f64 = 0.6;
+/// The effects that change the vertical movement in ways the checks do not predict.
+const MOVEMENT_EFFECTS: [StatusEffect; 3] = [
+    StatusEffect::Levitation,
+    StatusEffect::SlowFalling,
+    StatusEffect::DolphinsGrace,
+];
+
+pub struct MovementCheckPlugin;
+
+impl Plugin for MovementCheckPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MovementConfig>().add_systems(
+            Update,
+            (
+                add_movement_state,
+                exempt_on_damage,
+                exempt_on_velocity_change,
+                check_movement,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct MovementConfig {
+    /// The horizontal distance per tick that is always allowed (sprint jumping is about 0.62).
+    pub max_horizontal_speed: f64,
+    /// The additional horizontal speed per level of the speed effect (vanilla is 20%).
+    pub speed_effect_multiplier: f64,
+    /// The upward velocity of a jump in blocks per tick.
+    pub jump_velocity: f64,
+    /// The additional jump velocity per level of the jump boost effect.
+    pub jump_boost_velocity: f64,
+    /// The gravity in blocks per tick².
+    pub gravity: f64,
+    /// The vertical velocity is multiplied by this every tick.
+    pub vertical_drag: f64,
+    /// The vertical difference to the predicted movement that is allowed.
+    pub vertical_tolerance: f64,
+    /// How long players are not checked after they took damage.
+    pub damage_exemption: Duration,
+    /// How long players are not checked after the server set their velocity (e.g. knockback).
+    pub velocity_exemption: Duration,
+    /// Each violation adds to the violation level of the player, it decays by this every move.
+    pub violation_decay: f32,
+    /// Violations are reported from this violation level.
+    pub report_violation_level: f32,
+    /// Teleport the player back to the last valid position if a move is invalid.
+    pub setback: bool,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            max_horizontal_speed: 0.75,
+            speed_effect_multiplier: 0.2,
+            jump_velocity: 0.42,
+            jump_boost_velocity: 0.1,
+            gravity: 0.08,
+            vertical_drag: 0.98,
+            vertical_tolerance: 0.05,
+            damage_exemption: Duration::from_millis(1500),
+            velocity_exemption: Duration::from_millis(1500),
+            violation_decay: 0.05,
+            report_violation_level: 3.0,
+            setback: false,
+        }
+    }
+}
+
+/// The movement of a player that the checks compare against, added to all clients.
+#[derive(Component, Debug)]
+pub struct MovementState {
+    /// The last position that passed all checks, used for setbacks.
+    pub last_valid_position: DVec3,
+    /// The vertical velocity of the last move in blocks per tick.
+    vertical_velocity: f64,
+    /// If the server thinks the player stood on a block before the last move.
+    on_ground: bool,
+    exempt_until: Instant,
+    /// The sum of the recent violations, see [`MovementConfig::violation_decay`].
+    pub violation_level: f32,
+}
+
+impl MovementState {
+    fn new(position: DVec3) -> Self {
+        Self {
+            last_valid_position: position,
+            vertical_velocity: 0.0,
+            on_ground: true,
+            exempt_until: Instant::now(),
+            violation_level: 0.0,
+        }
+    }
+
+    /// Skips the checks for a while, e.g. when the player is launched or teleported by a plugin.
+    pub fn exempt(&mut self, duration: Duration) {
+        self.exempt_until = self.exempt_until.max(Instant::now() + duration);
+    }
+}
+
+fn player_hitbox(position: DVec3) -> Aabb {
+    Aabb::new(
+        position - DVec3::new(PLAYER_WIDTH / 2.0, 0.0, PLAYER_WIDTH / 2.0),
+        position + DVec3::new(PLAYER_WIDTH / 2.0, PLAYER_HEIGHT, PLAYER_WIDTH / 2.0),
+    )
+}
+
+/// If the player touches a block that changes the vanilla movement (e.g. water or a ladder).
+fn in_special_block(hitbox: &Aabb, layer: &ChunkLayer) -> bool {
+    // Include the block below the player for bouncy and slippery blocks.
+    let hitbox = Aabb::new(hitbox.min() - DVec3::new(0.0, 0.5, 0.0), hitbox.max());
+
+    aabb_full_block_intersections(&hitbox)
+        .into_iter()
+        .filter_map(|pos| layer.block(pos))
+        .any(|block| {
+            block.state.is_liquid()
+                || block.state.get(PropName::Waterlogged) == Some(PropValue::True)
+                || matches!(
+                    block.state.to_kind(),
+                    BlockKind::Ladder
+                        | BlockKind::Vine
+                        | BlockKind::Scaffolding
+                        | BlockKind::TwistingVines
+                        | BlockKind::TwistingVinesPlant
+                        | BlockKind::WeepingVines
+                        | BlockKind::WeepingVinesPlant
+                        | BlockKind::Cobweb
+                        | BlockKind::BubbleColumn
+                        | BlockKind::SlimeBlock
+                        | BlockKind::HoneyBlock
+                        | BlockKind::PowderSnow
+                        | BlockKind::Ice
+                        | BlockKind::PackedIce
+                        | BlockKind::BlueIce
+                )
+        })
+}
+
+fn add_movement_state(
+    mut commands: Commands,
+    clients: Query<(Entity, &Position), (With<Client>, Without<MovementState>)>,
+) {
+    for (entity, position) in clients.iter() {
+        commands
+            .entity(entity)
+            .insert(MovementState::new(position.0));
+    }
+}
+
+fn exempt_on_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut clients: Query<&mut MovementState>,
+    config: Res<MovementConfig>,
+) {
+    for event in damage_events.read() {
+        if let Ok(mut state) = clients.get_mut(event.victim) {
+            state.exempt(config.damage_exemption);
+        }
+    }
+}
+
+fn exempt_on_velocity_change(
+    mut velocity_events: EventReader<VelocityChangedEvent>,
+    mut clients: Query<&mut MovementState>,
+    config: Res<MovementConfig>,
+) {
+    for event in velocity_events.read() {
+        if let Ok(mut state) = clients.get_mut(event.client) {
+            state.exempt(config.velocity_exemption);
+        }
+    }
+}
+
+/// The horizontal distance per tick that is allowed with the level of the speed effect.
+fn max_horizontal_distance(config: &MovementConfig, speed_level: f64) -> f64 {
+    config.max_horizontal_speed * (1.0 + config.speed_effect_multiplier * speed_level)
+}
+
+/// The highest and lowest vertical distance of a move (`max_up`, `falling`) the vanilla physics
+/// allow, before the [`MovementConfig::vertical_tolerance`].
+///
+/// On the ground the player can jump or step up a block, in the air it falls with the gravity.
+fn vertical_envelope(
+    config: &MovementConfig,
+    was_on_ground: bool,
+    last_vertical_velocity: f64,
+    jump_boost_level: f64,
+) -> (f64, f64) {
+    let previous_velocity = if was_on_ground {
+        0.0
+    } else {
+        last_vertical_velocity
+    };
+    let falling = (previous_velocity - config.gravity) * config.vertical_drag;
+    let max_up = if was_on_ground {
+        let jump = config.jump_velocity + config.jump_boost_velocity * jump_boost_level;
+
+        jump.max(STEP_HEIGHT)
+    } else {
+        falling
+    };
+
+    (max_up, falling)
+}
+
+/// If the player flies with an elytra or can launch itself with a riptide trident.
+fn has_movement_item(equipment: &Equipment) -> bool {
+    let riptide = |stack: &ItemStack| {
+        stack.item == ItemKind::Trident && stack.enchantments().contains_key(&Enchantment::Riptide)
+    };
+
+    equipment.chest().item == ItemKind::Elytra
+        || riptide(equipment.main_hand())
+        || riptide(equipment.off_hand())
+}
+
+fn check_movement(
+    mut movement_events: EventReader<MovementEvent>,
+    mut clients: Query<(
+        &mut MovementState,
+        &mut Position,
+        &GameMode,
+        &VisibleChunkLayer,
+        Option<&ActiveStatusEffects>,
+        Option<&Equipment>,
+    )>,
+    layers: Query<&ChunkLayer>,
+    mut suspicion_writer: EventWriter<CheatSuspicionEvent>,
+    config: Res<MovementConfig>,
+) {
+    for event in movement_events.read() {
+        let Ok((mut state, mut position, game_mode, chunk_layer, effects, equipment)) =
+            clients.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        let Ok(layer) = layers.get(chunk_layer.0) else {
+            continue;
+        };
+
+        // The start of the move is where the server thinks the player is, so teleports by the
+        // server do not count as moves.
+        let start = event.old_position;
+        let end = event.position;
+        let hitbox = player_hitbox(end);
+        let on_ground = is_on_block(&hitbox, layer);
+        let was_on_ground = state.on_ground;
+        let last_vertical_velocity = state.vertical_velocity;
+        let dy = end.y - start.y;
+
+        state.on_ground = on_ground;
+        state.vertical_velocity = dy;
+        state.violation_level = (state.violation_level - config.violation_decay).max(0.0);
+
+        let effect_level = |effect: StatusEffect| {
+            effects
+                .and_then(|effects| effects.get_current_effect(effect))
+                .map_or(0.0, |effect| f64::from(effect.amplifier()) + 1.0)
+        };
+
+        if matches!(game_mode, GameMode::Creative | GameMode::Spectator)
+            || state.exempt_until > Instant::now()
+            || MOVEMENT_EFFECTS
+                .into_iter()
+                .any(|effect| effect_level(effect) > 0.0)
+            || equipment.is_some_and(has_movement_item)
+            || in_special_block(&player_hitbox(start), layer)
+            || in_special_block(&hitbox, layer)
+        {
+            state.last_valid_position = end;
+            continue;
+        }
+
+        let mut violations = Vec::new();
+
+        let horizontal = DVec3::new(end.x - start.x, 0.0, end.z - start.z).length();
+        let max_horizontal = max_horizontal_distance(&config, effect_level(StatusEffect::Speed));
+
+        if horizontal > max_horizontal {
+            violations.push((
+                CheatCheck::Speed,
+                horizontal / max_horizontal - 1.0,
+                format!("moved {horizontal:.2} blocks in a tick (max {max_horizontal:.2})"),
+            ));
+        }
+
+        let (max_up, falling) = vertical_envelope(
+            &config,
+            was_on_ground,
+            last_vertical_velocity,
+            effect_level(StatusEffect::JumpBoost),
+        );
+
+        if dy > max_up + config.vertical_tolerance {
+            violations.push((
+                CheatCheck::Fly,
+                dy - max_up,
+                format!("moved {dy:.2} blocks up (max {max_up:.2})"),
+            ));
+        }
+
+        // Falling faster than gravity allows (e.g. teleporting down to avoid fall damage).
+        if !was_on_ground && dy < falling - config.vertical_tolerance {
+            violations.push((
+                CheatCheck::NoFall,
+                falling - dy,
+                format!("fell {:.2} blocks (max {:.2})", -dy, -falling),
+            ));
+        }
+
+        if event.on_ground && !on_ground {
+            violations.push((
+                CheatCheck::NoFall,
+                1.0,
+                "claimed to be on the ground in the air".to_owned(),
+            ));
+        }
+
+        if violations.is_empty() {
+            state.last_valid_position = end;
+            continue;
+        }
+
+        for (check, excess, details) in violations {
+            state.violation_level += 1.0;
+
+            if state.violation_level >= config.report_violation_level {
+                suspicion_writer.send(CheatSuspicionEvent {
+                    player: event.client,
+                    check,
+                    // Small excesses can be lag, large ones are very unlikely.
+                    confidence: (excess as f32).clamp(0.1, 1.0),
+                    details,
+                });
+            }
+        }
+
+        if config.setback {
+            position.0 = state.last_valid_position;
+            state.vertical_velocity = 0.0;
+            state.on_ground = is_on_block(&player_hitbox(state.last_valid_position), layer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence::nbt::{compound, List};
+
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn jump_from_the_ground() {
+        let config = MovementConfig::default();
+        let (max_up, falling) = vertical_envelope(&config, true, -0.5, 0.0);
+
+        // Stepping up a slab is higher than a jump, the last velocity is reset by the ground.
+        assert_close(max_up, STEP_HEIGHT);
+        assert_close(falling, -0.08 * 0.98);
+    }
+
+    #[test]
+    fn jump_boost() {
+        let config = MovementConfig::default();
+
+        // Jump boost II: 0.42 + 2 * 0.1 is higher than the step height.
+        let (max_up, _) = vertical_envelope(&config, true, 0.0, 2.0);
+        assert_close(max_up, 0.62);
+
+        // Jump boost I is still below it.
+        let (max_up, _) = vertical_envelope(&config, true, 0.0, 1.0);
+        assert_close(max_up, STEP_HEIGHT);
+    }
+
+    #[test]
+    fn fall_in_the_air() {
+        let config = MovementConfig::default();
+
+        // The tick after a jump the player still moves up, but slower.
+        let (max_up, falling) = vertical_envelope(&config, false, 0.42, 0.0);
+        assert_close(max_up, (0.42 - 0.08) * 0.98);
+        assert_close(falling, max_up);
+
+        // Falling gets faster every tick.
+        let (max_up, falling) = vertical_envelope(&config, false, -1.0, 0.0);
+        assert_close(max_up, -1.08 * 0.98);
+        assert_close(falling, -1.08 * 0.98);
+
+        // The jump boost does not help in the air.
+        let (boosted_max_up, _) = vertical_envelope(&config, false, -1.0, 5.0);
+        assert_close(boosted_max_up, max_up);
+    }
+
+    #[test]
+    fn speed_effect() {
+        let config = MovementConfig::default();
+
+        assert_close(max_horizontal_distance(&config, 0.0), 0.75);
+        // Speed II: 20% per level.
+        assert_close(max_horizontal_distance(&config, 2.0), 0.75 * 1.4);
+    }
+
+    #[test]
+    fn movement_items() {
+        let mut equipment = Equipment::default();
+        assert!(!has_movement_item(&equipment));
+
+        equipment.set_main_hand(ItemStack::new(ItemKind::Trident, 1, None));
+        assert!(!has_movement_item(&equipment));
+
+        let riptide = compound! {
+            "Enchantments" => List::Compound(vec![compound! {
+                "id" => Enchantment::Riptide.id(),
+                "lvl" => 3_i64,
+            }]),
+        };
+        equipment.set_main_hand(ItemStack::new(ItemKind::Trident, 1, Some(riptide)));
+        assert!(has_movement_item(&equipment));
+
+        equipment.set_main_hand(ItemStack::EMPTY);
+        equipment.set_chest(ItemStack::new(ItemKind::Elytra, 1, None));
+        assert!(has_movement_item(&equipment));
+    }
+}
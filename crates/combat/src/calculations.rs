@@ -13,28 +13,52 @@ pub fn damage_after_armor(damage: f32, armor_points: f32, toughness: f32) -> f32
     damage * damage_multiplier
 }
 
-/// Calculates a damage multiplier based on the attack cooldown.
+/// Calculates the damage after armor (this is the 1.8 formula, armor toughness did not exist yet).
 /// (java behavior)
-pub fn attack_cooldown_base_damage(weapon_attack_speed: f32, last_attack: Instant) -> f32 {
-    // https://minecraft.fandom.com/wiki/Damage
+pub fn damage_after_armor_legacy(damage: f32, armor_points: f32, _toughness: f32) -> f32 {
+    // https://minecraft.wiki/w/Armor#Damage_protection (Java Edition 1.8)
+    damage * (1.0 - armor_points.clamp(0.0, 20.0) / 25.0)
+}
+
+/// Calculates the damage after the protection enchantments of the armor (this is the java edition formula).
+/// (java behavior)
+pub fn damage_after_enchantments(damage: f32, protection_factor: f32) -> f32 {
+    utils::enchantments::damage_after_enchantments(damage, protection_factor)
+}
+
+/// Calculates how charged an attack is (0.0 - 1.0) based on the attack cooldown.
+/// (java behavior)
+pub fn attack_strength(weapon_attack_speed: f32, last_attack: Instant) -> f32 {
+    // https://minecraft.wiki/w/Damage#Attack_cooldown
     let elapsed_millis = last_attack.elapsed().as_millis();
 
     let elapsed_ticks = elapsed_millis as f32 / 50.0;
     let t = 20.0 / weapon_attack_speed;
 
-    0.2 + ((t + 0.5) / elapsed_ticks).powf(2.0) * 0.8
+    ((elapsed_ticks + 0.5) / t).clamp(0.0, 1.0)
+}
+
+/// Calculates a damage multiplier based on the attack cooldown.
+/// (java behavior)
+pub fn attack_cooldown_base_damage(weapon_attack_speed: f32, last_attack: Instant) -> f32 {
+    // https://minecraft.fandom.com/wiki/Damage
+    0.2 + attack_strength(weapon_attack_speed, last_attack).powf(2.0) * 0.8
 }
 
 /// Calculates a damage multiplier based on the attack cooldown for damage caused by enchantments.
 /// (java behavior)
 pub fn attack_cooldown_enchantment_damage(weapon_attack_speed: f32, last_attack: Instant) -> f32 {
     // https://minecraft.fandom.com/wiki/Damage
-    let elapsed_millis = last_attack.elapsed().as_millis();
+    attack_strength(weapon_attack_speed, last_attack)
+}
 
-    let elapsed_ticks = elapsed_millis as f32 / 50.0;
-    let t = 20.0 / weapon_attack_speed;
+/// Calculates the damage of a sweep attack for the entities next to the victim.
+/// (java behavior)
+pub fn sweep_damage(base_damage: f32, sweeping_level: u32) -> f32 {
+    // https://minecraft.wiki/w/Sword#Sweep_attack
+    let ratio = sweeping_level as f32 / (sweeping_level as f32 + 1.0);
 
-    0.2 + ((t + 0.5) / elapsed_ticks) * 0.8
+    1.0 + base_damage * ratio
 }
 
 /// Calculates the damage for the sharpness enchantment.
@@ -104,6 +128,23 @@ pub fn enchant_fire_aspect(level: u32) -> (Duration, f32) {
     (burn_time, damage_per_second)
 }
 
+/// Calculates the damage of the thorns enchantment of one armor piece, 0 if it does not trigger.
+/// (java behavior)
+///
+/// The rolls are random numbers in `0.0..1.0`.
+pub fn enchant_thorns(level: u32, chance_roll: f32, damage_roll: f32) -> f32 {
+    // https://minecraft.wiki/w/Thorns
+    if level == 0 || chance_roll >= 0.15 * level as f32 {
+        return 0.0;
+    }
+
+    if level > 10 {
+        (level - 10) as f32
+    } else {
+        1.0 + (damage_roll * 4.0).floor()
+    }
+}
+
 /// Calculates the flame burn time and damage per second.
 /// (mostly java behavior)
 pub fn enchant_flame(level: u32) -> (Duration, f32) {
@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+use utils::damage::DeathEvent;
+use valence::prelude::*;
+
+use crate::{CombatHitEvent, CombatState};
+
+/// Counts the combos of the entities, used by the feedback and the stats.
+pub(crate) struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ComboConfig>()
+            .add_systems(Update, (add_combo_counter, update_combo_counter).chain());
+    }
+}
+
+#[derive(Resource)]
+pub struct ComboConfig {
+    /// The combo ends if the entity does not hit for this long, `None` to keep it until the
+    /// entity is hit.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for ComboConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(3)),
+        }
+    }
+}
+
+/// The hits of an entity without being hit back, added to all entities with a [`CombatState`].
+#[derive(Component, Debug, Default)]
+pub struct ComboCounter {
+    /// The hits since the entity was last hit (or died).
+    pub combo: u32,
+    /// The longest combo of the entity (its best hit streak).
+    pub longest_combo: u32,
+    last_hit: Option<Instant>,
+}
+
+fn add_combo_counter(
+    mut commands: Commands,
+    query: Query<Entity, (With<CombatState>, Without<ComboCounter>)>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(ComboCounter::default());
+    }
+}
+
+pub(crate) fn update_combo_counter(
+    mut hit_events: EventReader<CombatHitEvent>,
+    mut death_events: EventReader<DeathEvent>,
+    mut query: Query<&mut ComboCounter>,
+    config: Res<ComboConfig>,
+) {
+    if let Some(timeout) = config.timeout {
+        for mut counter in query.iter_mut() {
+            if counter.combo > 0 && counter.last_hit.is_some_and(|hit| hit.elapsed() > timeout) {
+                counter.combo = 0;
+            }
+        }
+    }
+
+    for event in hit_events.read() {
+        // A sweep attack hits several entities, but it is only one hit of the combo.
+        if !event.sweep {
+            if let Ok(mut counter) = query.get_mut(event.attacker) {
+                counter.combo += 1;
+                counter.longest_combo = counter.longest_combo.max(counter.combo);
+                counter.last_hit = Some(Instant::now());
+            }
+        }
+
+        // Being hit ends the combo, even if the entity hit back in the same tick.
+        if let Ok(mut counter) = query.get_mut(event.victim) {
+            counter.combo = 0;
+        }
+    }
+
+    for event in death_events.read() {
+        if let Ok(mut counter) = query.get_mut(event.victim) {
+            counter.combo = 0;
+        }
+    }
+}
@@ -0,0 +1,724 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use bevy_time::{Time, Timer, TimerMode};
+use serde::{Deserialize, Serialize};
+use utils::item_values::CombatSystem;
+use valence::prelude::*;
+
+use crate::{
+    calculations,
+    formula::{
+        ArmorFormula, AttackCooldownFormula, BurnFormula, DamageFormula, Expression,
+        ExpressionError, Formula, KnockbackFormula, ProtectionFormula, ThornsFormula,
+    },
+    BlockingConfig, CombatConfigs, PlayerCombatConfig, PlayerStateDependantValue,
+    SweepAttackConfig, DEFAULT_COMBAT_CONFIG,
+};
+
+/// The name that disables an optional formula in a config file.
+const DISABLED_FORMULA: &str = "none";
+
+/// The formulas that can be referenced by name in a [`CombatConfigFile`].
+///
+/// The built-in formulas are registered with the name of their function in [`calculations`],
+/// e.g. `damage_after_armor`. Custom formulas can be registered before the config is loaded.
+#[derive(Resource, Clone)]
+pub struct CombatFormulas {
+    /// The parameters are: `damage`, `armor_points`, `toughness`.
+    pub armor: HashMap<String, fn(f32, f32, f32) -> f32>,
+    /// The parameters are: `damage`, `protection_factor`.
+    pub protection: HashMap<String, fn(f32, f32) -> f32>,
+    /// The parameters are: `weapon_attack_speed`, `last_attack`.
+    pub attack_cooldown: HashMap<String, fn(f32, Instant) -> f32>,
+    /// Sharpness, power and sweep damage.
+    ///
+    /// The parameters are: `damage`, `level`.
+    pub damage: HashMap<String, fn(f32, u32) -> f32>,
+    /// Knockback and punch.
+    ///
+    /// The parameters are: `base_knockback_vector`, `level`.
+    pub knockback: HashMap<String, fn(Vec3, u32) -> Vec3>,
+    /// Fire aspect and flame.
+    ///
+    /// The parameters are: `level`.
+    pub burn: HashMap<String, fn(u32) -> (Duration, f32)>,
+    /// The parameters are: `thorns_level`, `chance_roll`, `damage_roll`.
+    pub thorns: HashMap<String, fn(u32, f32, f32) -> f32>,
+}
+
+impl Default for CombatFormulas {
+    fn default() -> Self {
+        let mut formulas = Self {
+            armor: HashMap::new(),
+            protection: HashMap::new(),
+            attack_cooldown: HashMap::new(),
+            damage: HashMap::new(),
+            knockback: HashMap::new(),
+            burn: HashMap::new(),
+            thorns: HashMap::new(),
+        };
+
+        formulas.armor.insert(
+            "damage_after_armor".to_owned(),
+            calculations::damage_after_armor,
+        );
+        formulas.armor.insert(
+            "damage_after_armor_legacy".to_owned(),
+            calculations::damage_after_armor_legacy,
+        );
+        formulas.protection.insert(
+            "damage_after_enchantments".to_owned(),
+            calculations::damage_after_enchantments,
+        );
+        formulas.attack_cooldown.insert(
+            "attack_cooldown_base_damage".to_owned(),
+            calculations::attack_cooldown_base_damage,
+        );
+        formulas.attack_cooldown.insert(
+            "attack_cooldown_enchantment_damage".to_owned(),
+            calculations::attack_cooldown_enchantment_damage,
+        );
+        formulas.damage.insert(
+            "enchant_sharpness_damage".to_owned(),
+            calculations::enchant_sharpness_damage,
+        );
+        formulas.damage.insert(
+            "enchant_power_damage".to_owned(),
+            calculations::enchant_power_damage,
+        );
+        formulas
+            .damage
+            .insert("sweep_damage".to_owned(), calculations::sweep_damage);
+        formulas.knockback.insert(
+            "enchant_knockback".to_owned(),
+            calculations::enchant_knockback,
+        );
+        formulas
+            .knockback
+            .insert("enchant_punch".to_owned(), calculations::enchant_punch);
+        formulas.burn.insert(
+            "enchant_fire_aspect".to_owned(),
+            calculations::enchant_fire_aspect,
+        );
+        formulas
+            .burn
+            .insert("enchant_flame".to_owned(), calculations::enchant_flame);
+        formulas
+            .thorns
+            .insert("enchant_thorns".to_owned(), calculations::enchant_thorns);
+
+        formulas
+    }
+}
+
+/// A formula in a config file, the name of a formula in [`CombatFormulas`] or an [`Expression`].
+///
+/// In TOML a named formula is a string (`armor_formula = "damage_after_armor"`) and an expression
+/// is a table with optional parameters, which can be used by name in the expression:
+///
+/// ```toml
+/// [enchantments.sharpness]
+/// expression = "damage + per_level * level"
+/// parameters = { per_level = 1.25 }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FormulaFile {
+    Name(String),
+    Expression {
+        expression: String,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        parameters: HashMap<String, f32>,
+    },
+}
+
+impl From<&str> for FormulaFile {
+    fn from(name: &str) -> Self {
+        FormulaFile::Name(name.to_owned())
+    }
+}
+
+/// Looks up a named formula or parses the expression.
+///
+/// `variables` are the variables an expression can use, e.g. [`ArmorFormula::VARIABLES`].
+fn formula<F: Copy>(
+    formulas: &HashMap<String, F>,
+    file: &FormulaFile,
+    variables: &[&str],
+) -> Result<Formula<F>, CombatConfigError> {
+    match file {
+        FormulaFile::Name(name) => formulas
+            .get(name)
+            .copied()
+            .map(Formula::Function)
+            .ok_or_else(|| CombatConfigError::UnknownFormula(name.clone())),
+        FormulaFile::Expression {
+            expression,
+            parameters,
+        } => Expression::parse(expression, variables, parameters)
+            .map(|parsed| Formula::Expression(Arc::new(parsed)))
+            .map_err(|error| CombatConfigError::InvalidExpression {
+                expression: expression.clone(),
+                error,
+            }),
+    }
+}
+
+/// Like [`formula`], but the name [`DISABLED_FORMULA`] disables the formula.
+fn optional_formula<F: Copy>(
+    formulas: &HashMap<String, F>,
+    file: &FormulaFile,
+    variables: &[&str],
+) -> Result<Option<Formula<F>>, CombatConfigError> {
+    if matches!(file, FormulaFile::Name(name) if name == DISABLED_FORMULA) {
+        return Ok(None);
+    }
+
+    formula(formulas, file, variables).map(Some)
+}
+
+/// The preset a [`CombatConfigFile`] is based on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CombatPreset {
+    /// [`PlayerCombatConfig::legacy_1_8`]
+    #[default]
+    #[serde(rename = "legacy_1_8")]
+    Legacy1_8,
+    /// [`PlayerCombatConfig::modern`]
+    #[serde(rename = "modern")]
+    Modern,
+    /// [`PlayerCombatConfig::old_combat_mechanics`]
+    #[serde(rename = "old_combat_mechanics")]
+    OldCombatMechanics,
+}
+
+impl CombatPreset {
+    /// The combat config of the preset.
+    pub fn config(&self) -> PlayerCombatConfig {
+        match self {
+            CombatPreset::Legacy1_8 => PlayerCombatConfig::legacy_1_8(),
+            CombatPreset::Modern => PlayerCombatConfig::modern(),
+            CombatPreset::OldCombatMechanics => PlayerCombatConfig::old_combat_mechanics(),
+        }
+    }
+}
+
+/// An optional part of the config that can be turned on or off (`true`/`false`) or configured.
+///
+/// Turning it on keeps the values of the preset or uses the default values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Toggle<T> {
+    Enabled(bool),
+    Config(T),
+}
+
+/// The attack cooldown of a [`CombatConfigFile`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttackCooldownFile {
+    /// See [`PlayerCombatConfig::attack_cooldown_multiplier`].
+    pub multiplier: f32,
+    /// See [`PlayerCombatConfig::damage_cooldown_formula_base_damage`].
+    pub base_damage_formula: FormulaFile,
+    /// See [`PlayerCombatConfig::damage_cooldown_enchantment_formula`].
+    pub enchantment_damage_formula: FormulaFile,
+}
+
+impl Default for AttackCooldownFile {
+    fn default() -> Self {
+        Self {
+            multiplier: 1.0,
+            base_damage_formula: "attack_cooldown_base_damage".into(),
+            enchantment_damage_formula: "attack_cooldown_enchantment_damage".into(),
+        }
+    }
+}
+
+/// The sweep attack of a [`CombatConfigFile`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SweepAttackFile {
+    pub damage_formula: FormulaFile,
+    pub radius: f64,
+    pub reach: f64,
+    pub knockback: f32,
+}
+
+impl Default for SweepAttackFile {
+    fn default() -> Self {
+        let sweep = SweepAttackConfig::default();
+
+        Self {
+            damage_formula: "sweep_damage".into(),
+            radius: sweep.radius,
+            reach: sweep.reach,
+            knockback: sweep.knockback,
+        }
+    }
+}
+
+/// The enchantment formulas of a [`CombatConfigFile`], missing formulas keep the preset value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnchantmentFormulasFile {
+    pub sharpness: Option<FormulaFile>,
+    pub knockback: Option<FormulaFile>,
+    pub fire_aspect: Option<FormulaFile>,
+    pub flame: Option<FormulaFile>,
+    pub power: Option<FormulaFile>,
+    pub punch: Option<FormulaFile>,
+    pub thorns: Option<FormulaFile>,
+}
+
+/// The serializable form of a [`PlayerCombatConfig`].
+///
+/// The config starts from the [`CombatPreset`] and every value that is set in the file replaces
+/// the value of the preset. The field names are the same as in [`PlayerCombatConfig`].
+///
+/// Formulas are referenced by their name in [`CombatFormulas`] or written as expressions (see
+/// [`FormulaFile`]), optional formulas can be disabled with `"none"`. Unknown keys (e.g. typos)
+/// are rejected instead of silently keeping the preset value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CombatConfigFile {
+    pub preset: CombatPreset,
+    pub combat_system: Option<CombatSystem>,
+    pub item_value_table: Option<String>,
+    pub arrows_stick: Option<u8>,
+    pub friendly_teams: Option<HashSet<u16>>,
+    pub hit_cooldown_millis: Option<u64>,
+    pub attack_cooldown: Option<Toggle<AttackCooldownFile>>,
+
+    pub armor_points_multiplier: Option<f32>,
+    pub armor_toughness_multiplier: Option<f32>,
+    pub armor_knockback_resistance_multiplier: Option<f32>,
+
+    pub horizontal_knockback: Option<PlayerStateDependantValue>,
+    pub vertical_knockback: Option<PlayerStateDependantValue>,
+    pub horizontal_knockback_received_multiplier: Option<PlayerStateDependantValue>,
+    pub vertical_knockback_received_multiplier: Option<PlayerStateDependantValue>,
+
+    pub random_critical_hit_chance: Option<PlayerStateDependantValue>,
+    pub critical_hit_chance_falling: Option<f32>,
+    pub critical_hit_damage_multiplier: Option<f32>,
+    pub critical_hit_while_sprinting: Option<bool>,
+
+    pub damage_multiplier: Option<PlayerStateDependantValue>,
+    pub fire_damage_multiplier: Option<PlayerStateDependantValue>,
+    pub fire_duration_multiplier: Option<PlayerStateDependantValue>,
+    pub damage_taken_multiplier: Option<PlayerStateDependantValue>,
+
+    pub friendly_fire_damage_multiplier: Option<f32>,
+    pub friendly_fire_damage_taken_multiplier: Option<f32>,
+
+    pub armor_formula: Option<FormulaFile>,
+    pub protection_formula: Option<FormulaFile>,
+    pub enchantments: EnchantmentFormulasFile,
+
+    pub weapon_durability: Option<bool>,
+    pub armor_durability: Option<bool>,
+
+    pub sweep_attack: Option<Toggle<SweepAttackFile>>,
+    pub blocking: Option<Toggle<BlockingConfig>>,
+}
+
+/// Replaces the value if the file (or override) sets it.
+pub(crate) fn set<T>(value: &mut T, file_value: &Option<T>)
+where
+    T: Clone,
+{
+    if let Some(file_value) = file_value {
+        *value = file_value.clone();
+    }
+}
+
+impl CombatConfigFile {
+    /// Parse a config from JSON.
+    pub fn from_json(json: &str) -> Result<Self, CombatConfigError> {
+        serde_json::from_str(json).map_err(CombatConfigError::Json)
+    }
+
+    /// Parse a config from TOML.
+    pub fn from_toml(toml: &str) -> Result<Self, CombatConfigError> {
+        toml::from_str(toml).map_err(CombatConfigError::Toml)
+    }
+
+    /// Load a config from a `.json` or `.toml` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CombatConfigError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(CombatConfigError::Io)?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::from_json(&content),
+            Some("toml") => Self::from_toml(&content),
+            _ => Err(CombatConfigError::UnknownFormat),
+        }
+    }
+
+    /// Serialize the config to pretty printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("combat configs can always be serialized")
+    }
+
+    /// Serialize the config to TOML.
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("combat configs can always be serialized")
+    }
+
+    /// Build the combat config, the formulas are looked up in `formulas`.
+    pub fn to_config(
+        &self,
+        formulas: &CombatFormulas,
+    ) -> Result<PlayerCombatConfig, CombatConfigError> {
+        let mut config = self.preset.config();
+
+        set(&mut config.combat_system, &self.combat_system);
+        if let Some(item_value_table) = &self.item_value_table {
+            config.item_value_table = Some(item_value_table.clone());
+        }
+        set(&mut config.arrows_stick, &self.arrows_stick);
+        set(&mut config.friendly_teams, &self.friendly_teams);
+        if let Some(hit_cooldown) = self.hit_cooldown_millis {
+            config.hit_cooldown = Duration::from_millis(hit_cooldown);
+        }
+
+        match &self.attack_cooldown {
+            None => {}
+            Some(Toggle::Enabled(false)) => config.attack_cooldown_multiplier = None,
+            Some(Toggle::Enabled(true)) => {
+                config.attack_cooldown_multiplier =
+                    Some(config.attack_cooldown_multiplier.unwrap_or(1.0));
+            }
+            Some(Toggle::Config(cooldown)) => {
+                config.attack_cooldown_multiplier = Some(cooldown.multiplier);
+                config.damage_cooldown_formula_base_damage = formula(
+                    &formulas.attack_cooldown,
+                    &cooldown.base_damage_formula,
+                    AttackCooldownFormula::VARIABLES,
+                )?;
+                config.damage_cooldown_enchantment_formula = formula(
+                    &formulas.attack_cooldown,
+                    &cooldown.enchantment_damage_formula,
+                    AttackCooldownFormula::VARIABLES,
+                )?;
+            }
+        }
+
+        set(
+            &mut config.armor_points_multiplier,
+            &self.armor_points_multiplier,
+        );
+        set(
+            &mut config.armor_toughness_multiplier,
+            &self.armor_toughness_multiplier,
+        );
+        set(
+            &mut config.armor_knockback_resistance_multiplier,
+            &self.armor_knockback_resistance_multiplier,
+        );
+
+        set(&mut config.horizontal_knockback, &self.horizontal_knockback);
+        set(&mut config.vertical_knockback, &self.vertical_knockback);
+        set(
+            &mut config.horizontal_knockback_received_multiplier,
+            &self.horizontal_knockback_received_multiplier,
+        );
+        set(
+            &mut config.vertical_knockback_received_multiplier,
+            &self.vertical_knockback_received_multiplier,
+        );
+
+        set(
+            &mut config.random_critical_hit_chance,
+            &self.random_critical_hit_chance,
+        );
+        set(
+            &mut config.critical_hit_chance_falling,
+            &self.critical_hit_chance_falling,
+        );
+        set(
+            &mut config.critical_hit_damage_multiplier,
+            &self.critical_hit_damage_multiplier,
+        );
+        set(
+            &mut config.critical_hit_while_sprinting,
+            &self.critical_hit_while_sprinting,
+        );
+
+        set(&mut config.damage_multiplier, &self.damage_multiplier);
+        set(
+            &mut config.fire_damage_multiplier,
+            &self.fire_damage_multiplier,
+        );
+        set(
+            &mut config.fire_duration_multiplier,
+            &self.fire_duration_multiplier,
+        );
+        set(
+            &mut config.damage_taken_multiplier,
+            &self.damage_taken_multiplier,
+        );
+
+        set(
+            &mut config.friendly_fire_damage_multiplier,
+            &self.friendly_fire_damage_multiplier,
+        );
+        set(
+            &mut config.friendly_fire_damage_taken_multiplier,
+            &self.friendly_fire_damage_taken_multiplier,
+        );
+
+        if let Some(file) = &self.armor_formula {
+            config.armor_formula = formula(&formulas.armor, file, ArmorFormula::VARIABLES)?;
+        }
+        if let Some(file) = &self.protection_formula {
+            config.protection_formula =
+                optional_formula(&formulas.protection, file, ProtectionFormula::VARIABLES)?;
+        }
+
+        let enchantments = &self.enchantments;
+        let enchantment_config = &mut config.enchantment_config;
+        if let Some(file) = &enchantments.sharpness {
+            enchantment_config.sharpness_formula =
+                optional_formula(&formulas.damage, file, DamageFormula::VARIABLES)?;
+        }
+        if let Some(file) = &enchantments.knockback {
+            enchantment_config.knockback_formula =
+                optional_formula(&formulas.knockback, file, KnockbackFormula::VARIABLES)?;
+        }
+        if let Some(file) = &enchantments.fire_aspect {
+            enchantment_config.fire_aspect_formula =
+                optional_formula(&formulas.burn, file, BurnFormula::VARIABLES)?;
+        }
+        if let Some(file) = &enchantments.flame {
+            enchantment_config.flame_formula =
+                optional_formula(&formulas.burn, file, BurnFormula::VARIABLES)?;
+        }
+        if let Some(file) = &enchantments.power {
+            enchantment_config.power_formula =
+                optional_formula(&formulas.damage, file, DamageFormula::VARIABLES)?;
+        }
+        if let Some(file) = &enchantments.punch {
+            enchantment_config.punch_formula =
+                optional_formula(&formulas.knockback, file, KnockbackFormula::VARIABLES)?;
+        }
+        if let Some(file) = &enchantments.thorns {
+            enchantment_config.thorns_formula =
+                optional_formula(&formulas.thorns, file, ThornsFormula::VARIABLES)?;
+        }
+
+        set(&mut config.weapon_durability, &self.weapon_durability);
+        set(&mut config.armor_durability, &self.armor_durability);
+
+        match &self.sweep_attack {
+            None => {}
+            Some(Toggle::Enabled(false)) => config.sweep_attack = None,
+            Some(Toggle::Enabled(true)) => {
+                config.sweep_attack = Some(config.sweep_attack.take().unwrap_or_default());
+            }
+            Some(Toggle::Config(sweep)) => {
+                config.sweep_attack = Some(SweepAttackConfig {
+                    damage_formula: formula(
+                        &formulas.damage,
+                        &sweep.damage_formula,
+                        DamageFormula::VARIABLES,
+                    )?,
+                    radius: sweep.radius,
+                    reach: sweep.reach,
+                    knockback: sweep.knockback,
+                });
+            }
+        }
+
+        match &self.blocking {
+            None => {}
+            Some(Toggle::Enabled(false)) => config.blocking = None,
+            Some(Toggle::Enabled(true)) => {
+                config.blocking = Some(
+                    config
+                        .blocking
+                        .take()
+                        .unwrap_or_else(BlockingConfig::shield),
+                );
+            }
+            Some(Toggle::Config(blocking)) => config.blocking = Some(blocking.clone()),
+        }
+
+        Ok(config)
+    }
+}
+
+#[derive(Debug)]
+pub enum CombatConfigError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    /// The formula is not registered in the [`CombatFormulas`].
+    UnknownFormula(String),
+    /// The formula expression can not be parsed.
+    InvalidExpression {
+        expression: String,
+        error: ExpressionError,
+    },
+    /// The file extension is neither `.json` nor `.toml`.
+    UnknownFormat,
+}
+
+impl fmt::Display for CombatConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CombatConfigError::Io(err) => write!(f, "failed to read combat config: {err}"),
+            CombatConfigError::Json(err) => write!(f, "invalid combat config: {err}"),
+            CombatConfigError::Toml(err) => write!(f, "invalid combat config: {err}"),
+            CombatConfigError::UnknownFormula(name) => write!(f, "unknown formula `{name}`"),
+            CombatConfigError::InvalidExpression { expression, error } => {
+                write!(f, "invalid formula `{expression}`: {error}")
+            }
+            CombatConfigError::UnknownFormat => {
+                write!(f, "combat configs must be .json or .toml files")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CombatConfigError {}
+
+/// Reloads a combat config from a file while the server runs.
+///
+/// When the file changes, the config in [`CombatConfigs`] is replaced, so every player using it
+/// gets the new rules. If the file can not be loaded, the current config is kept.
+#[derive(Resource)]
+pub struct CombatConfigReloader {
+    path: PathBuf,
+    config_id: u64,
+    timer: Timer,
+    last_modified: Option<SystemTime>,
+}
+
+impl CombatConfigReloader {
+    /// Checks the file for changes every `interval`, the file replaces the default config.
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self {
+            path: path.into(),
+            config_id: DEFAULT_COMBAT_CONFIG,
+            timer: Timer::new(interval, TimerMode::Repeating),
+            last_modified: None,
+        }
+    }
+
+    /// Replace the config with the id instead of the default config.
+    pub fn with_config_id(mut self, config_id: u64) -> Self {
+        self.config_id = config_id;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// An event that will be fired after a combat config was reloaded.
+#[derive(Event)]
+pub struct CombatConfigReloadedEvent {
+    pub config_id: u64,
+}
+
+pub(crate) fn reload_combat_config(
+    reloader: Option<ResMut<CombatConfigReloader>>,
+    formulas: Res<CombatFormulas>,
+    time: Res<Time>,
+    mut configs: ResMut<CombatConfigs>,
+    mut reloaded_writer: EventWriter<CombatConfigReloadedEvent>,
+) {
+    let Some(mut reloader) = reloader else {
+        return;
+    };
+
+    if !reloader.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let Ok(modified) = std::fs::metadata(&reloader.path).and_then(|meta| meta.modified()) else {
+        return;
+    };
+
+    if reloader.last_modified == Some(modified) {
+        return;
+    }
+
+    reloader.last_modified = Some(modified);
+
+    let config =
+        match CombatConfigFile::load(&reloader.path).and_then(|file| file.to_config(&formulas)) {
+            Ok(config) => config,
+            Err(err) => {
+                tracing::warn!("{}: {err}", reloader.path.display());
+                return;
+            }
+        };
+
+    configs.insert(reloader.config_id, config);
+
+    tracing::info!("reloaded combat config from {}", reloader.path.display());
+    reloaded_writer.send(CombatConfigReloadedEvent {
+        config_id: reloader.config_id,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_keys_are_loaded() {
+        let file = CombatConfigFile::from_toml(
+            r#"
+            preset = "modern"
+            hit_cooldown_millis = 400
+            armor_formula = "damage_after_armor_legacy"
+
+            [enchantments.sharpness]
+            expression = "damage + per_level * level"
+            parameters = { per_level = 1.25 }
+
+            [sweep_attack]
+            radius = 2.0
+            "#,
+        )
+        .unwrap();
+
+        let config = file.to_config(&CombatFormulas::default()).unwrap();
+
+        assert_eq!(config.hit_cooldown, Duration::from_millis(400));
+        assert_eq!(config.sweep_attack.map(|sweep| sweep.radius), Some(2.0));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(matches!(
+            CombatConfigFile::from_toml("hit_cooldown_milis = 400"),
+            Err(CombatConfigError::Toml(_))
+        ));
+        assert!(matches!(
+            CombatConfigFile::from_toml("[enchantments]\nsharpnes = \"enchant_sharpness_damage\""),
+            Err(CombatConfigError::Toml(_))
+        ));
+        assert!(matches!(
+            CombatConfigFile::from_json(r#"{ "sweep_attack": { "radius": 2.0, "reech": 3.0 } }"#),
+            Err(CombatConfigError::Json(_))
+        ));
+        assert!(matches!(
+            CombatConfigFile::from_json(
+                r#"{ "damage_multiplier": { "base": 1.0, "sprinting": 1.0, "sneaking": 1.0, "in_air": 1.0, "falling": 2.0 } }"#
+            ),
+            Err(CombatConfigError::Json(_))
+        ));
+    }
+}
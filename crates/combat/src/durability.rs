@@ -0,0 +1,199 @@
+use utils::{
+    enchantments::{Enchantment, ItemStackEnchantmentsExt},
+    random::GameRng,
+};
+use valence::{nbt::Value, prelude::*};
+
+/// The slots of the armor in the player inventory (head, chest, legs, feet).
+const PLAYER_ARMOR_SLOTS: [u16; 4] = [5, 6, 7, 8];
+
+/// The durability an armor piece loses when its thorns enchantment hurts an attacker.
+/// (java behavior)
+pub const THORNS_WEAR: i32 = 2;
+
+/// The result of wearing down an item.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ItemWear {
+    /// The item is not damageable or unbreaking prevented the damage.
+    Unchanged,
+    /// The durability of the item was reduced.
+    Damaged,
+    /// The item broke and was replaced with an empty stack.
+    Broken,
+}
+
+/// The current damage of the item (the `Damage` NBT tag).
+pub fn item_damage(stack: &ItemStack) -> i32 {
+    match stack.nbt.as_ref().and_then(|nbt| nbt.get("Damage")) {
+        Some(Value::Int(damage)) => *damage,
+        _ => 0,
+    }
+}
+
+/// Wears down the item by `amount` durability points.
+///
+/// Every point can be ignored based on the unbreaking level of the item (java behavior).
+pub fn damage_item(
+    stack: &mut ItemStack,
+    amount: i32,
+    is_armor: bool,
+    rng: &mut GameRng,
+) -> ItemWear {
+    let max_durability = stack.item.max_durability() as i32;
+
+    if stack.is_empty() || max_durability == 0 || amount <= 0 {
+        return ItemWear::Unchanged;
+    }
+
+    let unbreaking = stack
+        .enchantments()
+        .get(&Enchantment::Unbreaking)
+        .copied()
+        .unwrap_or(0);
+
+    let amount = (0..amount)
+        .filter(|_| !unbreaking_prevents_damage(unbreaking, is_armor, rng))
+        .count() as i32;
+
+    if amount == 0 {
+        return ItemWear::Unchanged;
+    }
+
+    let damage = item_damage(stack) + amount;
+
+    if damage >= max_durability {
+        *stack = ItemStack::EMPTY;
+        return ItemWear::Broken;
+    }
+
+    stack
+        .nbt
+        .get_or_insert_with(Default::default)
+        .insert("Damage", Value::Int(damage));
+
+    ItemWear::Damaged
+}
+
+/// Returns true if the unbreaking enchantment prevents one point of damage.
+/// (java behavior)
+fn unbreaking_prevents_damage(level: u32, is_armor: bool, rng: &mut GameRng) -> bool {
+    // https://minecraft.wiki/w/Unbreaking
+    if level == 0 {
+        return false;
+    }
+
+    if is_armor && rng.chance(0.6) {
+        return false;
+    }
+
+    !rng.chance(1.0 / (level as f32 + 1.0))
+}
+
+/// The durability a weapon loses when it is used to attack an entity.
+/// (java behavior)
+pub fn weapon_wear(item: ItemKind) -> i32 {
+    match item {
+        ItemKind::WoodenSword
+        | ItemKind::StoneSword
+        | ItemKind::IronSword
+        | ItemKind::GoldenSword
+        | ItemKind::DiamondSword
+        | ItemKind::NetheriteSword
+        | ItemKind::Trident => 1,
+
+        ItemKind::WoodenAxe
+        | ItemKind::StoneAxe
+        | ItemKind::IronAxe
+        | ItemKind::GoldenAxe
+        | ItemKind::DiamondAxe
+        | ItemKind::NetheriteAxe
+        | ItemKind::WoodenPickaxe
+        | ItemKind::StonePickaxe
+        | ItemKind::IronPickaxe
+        | ItemKind::GoldenPickaxe
+        | ItemKind::DiamondPickaxe
+        | ItemKind::NetheritePickaxe
+        | ItemKind::WoodenShovel
+        | ItemKind::StoneShovel
+        | ItemKind::IronShovel
+        | ItemKind::GoldenShovel
+        | ItemKind::DiamondShovel
+        | ItemKind::NetheriteShovel
+        | ItemKind::WoodenHoe
+        | ItemKind::StoneHoe
+        | ItemKind::IronHoe
+        | ItemKind::GoldenHoe
+        | ItemKind::DiamondHoe
+        | ItemKind::NetheriteHoe => 2,
+
+        _ => 0,
+    }
+}
+
+/// The durability every armor piece loses when the entity takes `damage` (before armor).
+/// (java behavior)
+pub fn armor_wear(damage: f32) -> i32 {
+    // https://minecraft.wiki/w/Armor#Durability
+    (damage / 4.0).max(1.0) as i32
+}
+
+/// Wears down every armor piece of the entity.
+///
+/// If the entity has an inventory (players), the armor slots of the inventory are changed,
+/// otherwise the equipment is changed directly.
+///
+/// Returns true if any armor piece broke.
+pub(crate) fn wear_armor(
+    equipment: &mut Equipment,
+    inventory: Option<&mut Inventory>,
+    damage: f32,
+    rng: &mut GameRng,
+) -> bool {
+    wear_armor_pieces(equipment, inventory, [armor_wear(damage); 4], rng)
+}
+
+/// Wears down the armor pieces (head, chest, legs, feet) by different amounts, see [`wear_armor`].
+pub(crate) fn wear_armor_pieces(
+    equipment: &mut Equipment,
+    inventory: Option<&mut Inventory>,
+    amounts: [i32; 4],
+    rng: &mut GameRng,
+) -> bool {
+    let mut broken = false;
+
+    let mut wear = |stack: &ItemStack, amount: i32| {
+        let mut stack = stack.clone();
+
+        match damage_item(&mut stack, amount, true, rng) {
+            ItemWear::Unchanged => None,
+            ItemWear::Damaged => Some(stack),
+            ItemWear::Broken => {
+                broken = true;
+                Some(stack)
+            }
+        }
+    };
+
+    if let Some(inventory) = inventory {
+        for (slot, amount) in PLAYER_ARMOR_SLOTS.into_iter().zip(amounts) {
+            if let Some(stack) = wear(inventory.slot(slot), amount) {
+                inventory.set_slot(slot, stack);
+            }
+        }
+    } else {
+        if let Some(stack) = wear(equipment.head(), amounts[0]) {
+            equipment.set_head(stack);
+        }
+        if let Some(stack) = wear(equipment.chest(), amounts[1]) {
+            equipment.set_chest(stack);
+        }
+        if let Some(stack) = wear(equipment.legs(), amounts[2]) {
+            equipment.set_legs(stack);
+        }
+        if let Some(stack) = wear(equipment.feet(), amounts[3]) {
+            equipment.set_feet(stack);
+        }
+    }
+
+    broken
+}
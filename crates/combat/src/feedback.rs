@@ -0,0 +1,150 @@
+use std::time::{Duration, Instant};
+
+use utils::damage::DamageTakenEvent;
+use valence::{
+    entity::{
+        display::Billboard,
+        text_display::{self, TextDisplayEntityBundle},
+    },
+    prelude::*,
+    title::SetTitle,
+};
+
+use crate::combo::ComboCounter;
+
+/// Shows the combo of the players in the action bar and the dealt damage as holograms.
+///
+/// This is not added by [`CombatPlugin`](crate::CombatPlugin), add it if you want the feedback.
+pub struct CombatFeedbackPlugin;
+
+impl Plugin for CombatFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CombatFeedbackConfig>().add_systems(
+            Update,
+            (
+                add_shown_combo,
+                show_combo,
+                spawn_damage_indicators,
+                update_damage_indicators,
+            ),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct CombatFeedbackConfig {
+    /// The combo is shown in the action bar from this many hits, `None` to not show it.
+    pub action_bar_min_combo: Option<u32>,
+    /// Spawn a hologram with the damage when an entity damages another entity.
+    pub damage_indicators: bool,
+    /// How long the damage holograms are shown.
+    pub damage_indicator_duration: Duration,
+    /// How fast the damage holograms rise in blocks per second.
+    pub damage_indicator_speed: f64,
+}
+
+impl Default for CombatFeedbackConfig {
+    fn default() -> Self {
+        Self {
+            action_bar_min_combo: Some(2),
+            damage_indicators: true,
+            damage_indicator_duration: Duration::from_millis(1000),
+            damage_indicator_speed: 0.5,
+        }
+    }
+}
+
+/// The combo that is shown in the action bar of the player.
+#[derive(Component, Default)]
+struct ShownCombo(u32);
+
+/// A hologram that shows the dealt damage, it rises and is despawned after a short time.
+#[derive(Component)]
+pub struct DamageIndicator {
+    spawned: Instant,
+}
+
+fn add_shown_combo(
+    mut commands: Commands,
+    query: Query<Entity, (With<ComboCounter>, With<Client>, Without<ShownCombo>)>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(ShownCombo::default());
+    }
+}
+
+fn show_combo(
+    mut query: Query<(&ComboCounter, &mut ShownCombo, &mut Client), Changed<ComboCounter>>,
+    config: Res<CombatFeedbackConfig>,
+) {
+    let Some(min_combo) = config.action_bar_min_combo else {
+        return;
+    };
+
+    for (counter, mut shown, mut client) in query.iter_mut() {
+        let old_combo = shown.0;
+
+        if counter.combo == old_combo {
+            continue;
+        }
+
+        shown.0 = counter.combo;
+
+        if counter.combo >= min_combo {
+            client.set_action_bar(format!("{} Combo", counter.combo).color(Color::GOLD));
+        } else if old_combo >= min_combo {
+            client.set_action_bar("");
+        }
+    }
+}
+
+fn spawn_damage_indicators(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageTakenEvent>,
+    victims: Query<(&Position, &EntityLayerId, Option<&Hitbox>)>,
+    config: Res<CombatFeedbackConfig>,
+) {
+    for event in damage_events.read() {
+        if !config.damage_indicators || event.attacker.is_none() || event.damage <= 0.0 {
+            continue;
+        }
+
+        let Ok((position, layer, hitbox)) = victims.get(event.victim) else {
+            continue;
+        };
+
+        let height = hitbox.map_or(1.8, |hitbox| hitbox.get().max().y - position.0.y);
+
+        commands
+            .spawn(TextDisplayEntityBundle {
+                position: Position(position.0 + DVec3::new(0.0, height + 0.3, 0.0)),
+                layer: *layer,
+                text_display_text: text_display::Text(
+                    format!("-{:.1}", event.damage).color(Color::RED),
+                ),
+                // Always face the player.
+                display_billboard: Billboard::Center,
+
+                ..Default::default()
+            })
+            .insert(DamageIndicator {
+                spawned: Instant::now(),
+            });
+    }
+}
+
+fn update_damage_indicators(
+    mut commands: Commands,
+    mut query: Query<(Entity, &DamageIndicator, &mut Position)>,
+    config: Res<CombatFeedbackConfig>,
+) {
+    for (entity, indicator, mut position) in query.iter_mut() {
+        if indicator.spawned.elapsed() > config.damage_indicator_duration {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        // Update is run 20 times per second.
+        position.0.y += config.damage_indicator_speed / 20.0;
+    }
+}
@@ -0,0 +1,760 @@
+//! The formulas of a [`PlayerCombatConfig`](crate::PlayerCombatConfig).
+//!
+//! A formula is either a function (like the ones in [`calculations`](crate::calculations)) or an
+//! [`Expression`] that was loaded from a config file, e.g. `damage + 0.5 * level + 0.5`.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use valence::math::Vec3;
+
+use crate::calculations;
+
+/// A formula of the combat config, a function or an [`Expression`].
+#[derive(Clone)]
+pub enum Formula<F> {
+    Function(F),
+    Expression(Arc<Expression>),
+}
+
+impl<F> From<F> for Formula<F> {
+    fn from(function: F) -> Self {
+        Formula::Function(function)
+    }
+}
+
+/// The armor formula.
+pub type ArmorFormula = Formula<fn(f32, f32, f32) -> f32>;
+/// The protection enchantment formula.
+pub type ProtectionFormula = Formula<fn(f32, f32) -> f32>;
+/// The attack cooldown damage multiplier formula.
+pub type AttackCooldownFormula = Formula<fn(f32, Instant) -> f32>;
+/// Sharpness, power and sweep damage.
+pub type DamageFormula = Formula<fn(f32, u32) -> f32>;
+/// Knockback and punch.
+pub type KnockbackFormula = Formula<fn(Vec3, u32) -> Vec3>;
+/// Fire aspect and flame.
+pub type BurnFormula = Formula<fn(u32) -> (Duration, f32)>;
+/// The thorns enchantment formula.
+pub type ThornsFormula = Formula<fn(u32, f32, f32) -> f32>;
+
+impl ArmorFormula {
+    /// The variables of an expression.
+    pub const VARIABLES: &'static [&'static str] = &["damage", "armor_points", "toughness"];
+
+    pub fn call(&self, damage: f32, armor_points: f32, toughness: f32) -> f32 {
+        match self {
+            Formula::Function(function) => function(damage, armor_points, toughness),
+            Formula::Expression(expression) => {
+                expression.eval(&[damage as f64, armor_points as f64, toughness as f64]) as f32
+            }
+        }
+    }
+}
+
+impl ProtectionFormula {
+    /// The variables of an expression.
+    pub const VARIABLES: &'static [&'static str] = &["damage", "protection_factor"];
+
+    pub fn call(&self, damage: f32, protection_factor: f32) -> f32 {
+        match self {
+            Formula::Function(function) => function(damage, protection_factor),
+            Formula::Expression(expression) => {
+                expression.eval(&[damage as f64, protection_factor as f64]) as f32
+            }
+        }
+    }
+}
+
+impl AttackCooldownFormula {
+    /// The variables of an expression.
+    ///
+    /// `attack_strength` is how charged the attack is (0.0 - 1.0), see
+    /// [`calculations::attack_strength`].
+    pub const VARIABLES: &'static [&'static str] =
+        &["attack_speed", "elapsed_ticks", "attack_strength"];
+
+    pub fn call(&self, weapon_attack_speed: f32, last_attack: Instant) -> f32 {
+        match self {
+            Formula::Function(function) => function(weapon_attack_speed, last_attack),
+            Formula::Expression(expression) => {
+                let elapsed_ticks = last_attack.elapsed().as_millis() as f64 / 50.0;
+                let attack_strength =
+                    calculations::attack_strength(weapon_attack_speed, last_attack);
+
+                expression.eval(&[
+                    weapon_attack_speed as f64,
+                    elapsed_ticks,
+                    attack_strength as f64,
+                ]) as f32
+            }
+        }
+    }
+}
+
+impl DamageFormula {
+    /// The variables of an expression.
+    pub const VARIABLES: &'static [&'static str] = &["damage", "level"];
+
+    pub fn call(&self, damage: f32, level: u32) -> f32 {
+        match self {
+            Formula::Function(function) => function(damage, level),
+            Formula::Expression(expression) => {
+                expression.eval(&[damage as f64, level as f64]) as f32
+            }
+        }
+    }
+}
+
+impl KnockbackFormula {
+    /// The variables of an expression.
+    ///
+    /// The expression calculates the new strength of the knockback from the strength of the base
+    /// knockback (`knockback`), the direction stays the same.
+    pub const VARIABLES: &'static [&'static str] = &["knockback", "level"];
+
+    pub fn call(&self, base_knockback: Vec3, level: u32) -> Vec3 {
+        match self {
+            Formula::Function(function) => function(base_knockback, level),
+            Formula::Expression(expression) => {
+                let strength = expression.eval(&[base_knockback.length() as f64, level as f64]);
+
+                base_knockback.normalize_or_zero() * strength as f32
+            }
+        }
+    }
+}
+
+impl BurnFormula {
+    /// The variables of an expression.
+    ///
+    /// The expression calculates the burn time in seconds, the burn damage per second is 1 like
+    /// in vanilla (it can be changed with the fire damage multiplier).
+    pub const VARIABLES: &'static [&'static str] = &["level"];
+
+    pub fn call(&self, level: u32) -> (Duration, f32) {
+        match self {
+            Formula::Function(function) => function(level),
+            Formula::Expression(expression) => {
+                let seconds = expression.eval(&[level as f64]).max(0.0);
+
+                (Duration::from_secs_f64(seconds), 1.0)
+            }
+        }
+    }
+}
+
+impl ThornsFormula {
+    /// The variables of an expression.
+    pub const VARIABLES: &'static [&'static str] = &["level", "chance_roll", "damage_roll"];
+
+    pub fn call(&self, level: u32, chance_roll: f32, damage_roll: f32) -> f32 {
+        match self {
+            Formula::Function(function) => function(level, chance_roll, damage_roll),
+            Formula::Expression(expression) => {
+                expression.eval(&[level as f64, chance_roll as f64, damage_roll as f64]) as f32
+            }
+        }
+    }
+}
+
+/// An arithmetic expression over the variables of a formula.
+///
+/// Supported are numbers, the variables and parameters, `+ - * / ^`, the comparisons
+/// `< <= > >= == !=` (1 if true, 0 if false), parentheses and the functions `min(a, b)`,
+/// `max(a, b)`, `clamp(x, min, max)`, `if(condition, then, else)`, `floor(x)`, `ceil(x)`,
+/// `round(x)`, `abs(x)` and `sqrt(x)`.
+///
+/// e.g. the vanilla thorns formula:
+/// `if(chance_roll < 0.15 * level, if(level > 10, level - 10, 1 + floor(damage_roll * 4)), 0)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    source: String,
+    node: Node,
+}
+
+impl Expression {
+    /// Parses an expression.
+    ///
+    /// # Arguments
+    /// - `variables`: The variables of the formula, their values are passed to [`Self::eval`] in
+    ///   the same order.
+    /// - `parameters`: Constants that can be used by name in the expression.
+    pub fn parse(
+        source: &str,
+        variables: &[&str],
+        parameters: &HashMap<String, f32>,
+    ) -> Result<Self, ExpressionError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            variables,
+            parameters,
+        };
+
+        let node = parser.comparison()?;
+
+        if let Some(token) = parser.peek() {
+            return Err(ExpressionError::UnexpectedToken(token.to_string()));
+        }
+
+        Ok(Self {
+            source: source.to_owned(),
+            node,
+        })
+    }
+
+    /// The expression as it was written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluates the expression, `values` are the values of the variables given to
+    /// [`Self::parse`].
+    pub fn eval(&self, values: &[f64]) -> f64 {
+        self.node.eval(values)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    Variable(usize),
+    Negate(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+impl Node {
+    fn eval(&self, values: &[f64]) -> f64 {
+        match self {
+            Node::Number(number) => *number,
+            Node::Variable(index) => values[*index],
+            Node::Negate(node) => -node.eval(values),
+            Node::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(values), rhs.eval(values));
+
+                match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Sub => lhs - rhs,
+                    BinaryOp::Mul => lhs * rhs,
+                    BinaryOp::Div => lhs / rhs,
+                    BinaryOp::Pow => lhs.powf(rhs),
+                    BinaryOp::Lt => bool_value(lhs < rhs),
+                    BinaryOp::Le => bool_value(lhs <= rhs),
+                    BinaryOp::Gt => bool_value(lhs > rhs),
+                    BinaryOp::Ge => bool_value(lhs >= rhs),
+                    BinaryOp::Eq => bool_value(lhs == rhs),
+                    BinaryOp::Ne => bool_value(lhs != rhs),
+                }
+            }
+            Node::Call(function, args) => {
+                let arg = |index: usize| args[index].eval(values);
+
+                match function {
+                    Function::Min => arg(0).min(arg(1)),
+                    Function::Max => arg(0).max(arg(1)),
+                    Function::Clamp => arg(0).max(arg(1)).min(arg(2)),
+                    // Only the chosen branch is evaluated.
+                    Function::If if arg(0) != 0.0 => arg(1),
+                    Function::If => arg(2),
+                    Function::Floor => arg(0).floor(),
+                    Function::Ceil => arg(0).ceil(),
+                    Function::Round => arg(0).round(),
+                    Function::Abs => arg(0).abs(),
+                    Function::Sqrt => arg(0).sqrt(),
+                }
+            }
+        }
+    }
+}
+
+fn bool_value(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Min,
+    Max,
+    Clamp,
+    If,
+    Floor,
+    Ceil,
+    Round,
+    Abs,
+    Sqrt,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            "clamp" => Some(Function::Clamp),
+            "if" => Some(Function::If),
+            "floor" => Some(Function::Floor),
+            "ceil" => Some(Function::Ceil),
+            "round" => Some(Function::Round),
+            "abs" => Some(Function::Abs),
+            "sqrt" => Some(Function::Sqrt),
+            _ => None,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Function::Min | Function::Max => 2,
+            Function::Clamp | Function::If => 3,
+            Function::Floor | Function::Ceil | Function::Round | Function::Abs | Function::Sqrt => {
+                1
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+    OpenParen,
+    CloseParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "{number}"),
+            Token::Ident(ident) => write!(f, "{ident}"),
+            Token::Op(op) => write!(f, "{op}"),
+            Token::OpenParen => write!(f, "("),
+            Token::CloseParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+const OPERATORS: [&str; 11] = ["<=", ">=", "==", "!=", "<", ">", "+", "-", "*", "/", "^"];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ExpressionError> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit() || c == '.' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..len]
+                .parse()
+                .map_err(|_| ExpressionError::UnexpectedToken(rest[..len].to_owned()))?;
+
+            tokens.push(Token::Number(number));
+            len
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+
+            tokens.push(Token::Ident(rest[..len].to_owned()));
+            len
+        } else if c == '(' {
+            tokens.push(Token::OpenParen);
+            1
+        } else if c == ')' {
+            tokens.push(Token::CloseParen);
+            1
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            1
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(*op));
+            op.len()
+        } else {
+            return Err(ExpressionError::UnexpectedToken(c.to_string()));
+        };
+
+        rest = rest[len..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// A recursive descent parser, from the lowest to the highest precedence:
+/// comparisons, `+ -`, `* /`, unary `-`, `^` (right associative), numbers, variables, calls and
+/// parentheses.
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    variables: &'a [&'a str],
+    parameters: &'a HashMap<String, f32>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, ExpressionError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or(ExpressionError::UnexpectedEnd)?;
+        self.position += 1;
+
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ExpressionError> {
+        let token = self.next()?;
+
+        if token != expected {
+            return Err(ExpressionError::UnexpectedToken(token.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Parses a left associative chain of the binary operators.
+    fn binary(
+        &mut self,
+        operators: &[(&str, BinaryOp)],
+        operand: fn(&mut Self) -> Result<Node, ExpressionError>,
+    ) -> Result<Node, ExpressionError> {
+        let mut node = operand(self)?;
+
+        while let Some(Token::Op(op)) = self.peek() {
+            let Some((_, binary_op)) = operators.iter().find(|(name, _)| name == op) else {
+                break;
+            };
+            let binary_op = *binary_op;
+
+            self.position += 1;
+            node = Node::Binary(binary_op, Box::new(node), Box::new(operand(self)?));
+        }
+
+        Ok(node)
+    }
+
+    fn comparison(&mut self) -> Result<Node, ExpressionError> {
+        self.binary(
+            &[
+                ("<", BinaryOp::Lt),
+                ("<=", BinaryOp::Le),
+                (">", BinaryOp::Gt),
+                (">=", BinaryOp::Ge),
+                ("==", BinaryOp::Eq),
+                ("!=", BinaryOp::Ne),
+            ],
+            Self::sum,
+        )
+    }
+
+    fn sum(&mut self) -> Result<Node, ExpressionError> {
+        self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Node, ExpressionError> {
+        self.binary(&[("*", BinaryOp::Mul), ("/", BinaryOp::Div)], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Node, ExpressionError> {
+        if self.peek() == Some(&Token::Op("-")) {
+            self.position += 1;
+            return Ok(Node::Negate(Box::new(self.unary()?)));
+        }
+
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Node, ExpressionError> {
+        let base = self.primary()?;
+
+        if self.peek() == Some(&Token::Op("^")) {
+            self.position += 1;
+            // -2 ^ 2 is -(2 ^ 2), but 2 ^ -2 is allowed.
+            return Ok(Node::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ));
+        }
+
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Node, ExpressionError> {
+        match self.next()? {
+            Token::Number(number) => Ok(Node::Number(number)),
+            Token::OpenParen => {
+                let node = self.comparison()?;
+                self.expect(Token::CloseParen)?;
+
+                Ok(node)
+            }
+            Token::Ident(name) if self.peek() == Some(&Token::OpenParen) => {
+                let function = Function::from_name(&name)
+                    .ok_or_else(|| ExpressionError::UnknownFunction(name.clone()))?;
+                self.position += 1;
+
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::CloseParen) {
+                    args.push(self.comparison()?);
+
+                    while self.peek() == Some(&Token::Comma) {
+                        self.position += 1;
+                        args.push(self.comparison()?);
+                    }
+                }
+                self.expect(Token::CloseParen)?;
+
+                if args.len() != function.arity() {
+                    return Err(ExpressionError::WrongArgumentCount {
+                        function: name,
+                        expected: function.arity(),
+                        found: args.len(),
+                    });
+                }
+
+                Ok(Node::Call(function, args))
+            }
+            Token::Ident(name) => {
+                if let Some(index) = self.variables.iter().position(|variable| *variable == name) {
+                    Ok(Node::Variable(index))
+                } else if let Some(value) = self.parameters.get(&name) {
+                    Ok(Node::Number(*value as f64))
+                } else {
+                    Err(ExpressionError::UnknownVariable(name))
+                }
+            }
+            token => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    /// The expression ended in the middle, e.g. `damage *`.
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    /// The name is neither a variable of the formula nor a parameter.
+    UnknownVariable(String),
+    UnknownFunction(String),
+    WrongArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionError::UnexpectedEnd => write!(f, "unexpected end of the expression"),
+            ExpressionError::UnexpectedToken(token) => write!(f, "unexpected `{token}`"),
+            ExpressionError::UnknownVariable(name) => write!(f, "unknown variable `{name}`"),
+            ExpressionError::UnknownFunction(name) => write!(f, "unknown function `{name}`"),
+            ExpressionError::WrongArgumentCount {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{function}` takes {expected} arguments but {found} were given"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARIABLES: &[&str] = &["damage", "level"];
+
+    fn parse(source: &str) -> Result<Expression, ExpressionError> {
+        Expression::parse(source, VARIABLES, &HashMap::new())
+    }
+
+    /// Evaluates the expression with `damage = 4` and `level = 3`.
+    fn eval(source: &str) -> f64 {
+        parse(source).unwrap().eval(&[4.0, 3.0])
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7.0);
+        assert_eq!(eval("(1 + 2) * 3"), 9.0);
+        assert_eq!(eval("2 * 3 ^ 2"), 18.0);
+        assert_eq!(eval("1 + 2 < 2 * 2"), 1.0);
+        assert_eq!(eval("8 / 4 / 2"), 1.0);
+        assert_eq!(eval("8 - 4 - 2"), 2.0);
+    }
+
+    #[test]
+    fn power_is_right_associative() {
+        assert_eq!(eval("2 ^ 3 ^ 2"), 512.0);
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-damage"), -4.0);
+        assert_eq!(eval("--damage"), 4.0);
+        assert_eq!(eval("1 - -1"), 2.0);
+        // The power binds stronger than the minus in front of it, but not the one behind it.
+        assert_eq!(eval("-2 ^ 2"), -4.0);
+        assert_eq!(eval("2 ^ -1"), 0.5);
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(eval("level <= 3"), 1.0);
+        assert_eq!(eval("level < 3"), 0.0);
+        assert_eq!(eval("level >= 4"), 0.0);
+        assert_eq!(eval("level > 2"), 1.0);
+        assert_eq!(eval("level == 3"), 1.0);
+        assert_eq!(eval("level != 3"), 0.0);
+    }
+
+    #[test]
+    fn function_calls() {
+        assert_eq!(eval("min(damage, level)"), 3.0);
+        assert_eq!(eval("max(damage, level)"), 4.0);
+        assert_eq!(eval("clamp(damage * 10, 0, 20)"), 20.0);
+        assert_eq!(eval("if(level > 2, damage, 0)"), 4.0);
+        assert_eq!(eval("if(level > 5, damage, 0)"), 0.0);
+        assert_eq!(eval("floor(2.7) + ceil(2.2) + round(2.5)"), 8.0);
+        assert_eq!(eval("abs(-damage) + sqrt(16)"), 8.0);
+        assert_eq!(eval("max(min(1, 2), abs(-3))"), 3.0);
+    }
+
+    #[test]
+    fn vanilla_thorns() {
+        let expression = Expression::parse(
+            "if(chance_roll < 0.15 * level, if(level > 10, level - 10, 1 + floor(damage_roll * 4)), 0)",
+            ThornsFormula::VARIABLES,
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(expression.eval(&[3.0, 0.2, 0.9]), 4.0);
+        assert_eq!(expression.eval(&[3.0, 0.5, 0.9]), 0.0);
+        assert_eq!(expression.eval(&[12.0, 0.0, 0.9]), 2.0);
+    }
+
+    #[test]
+    fn parameters() {
+        let parameters =
+            HashMap::from([("per_level".to_owned(), 1.25), ("damage".to_owned(), 100.0)]);
+        let expression =
+            Expression::parse("damage + per_level * level", VARIABLES, &parameters).unwrap();
+
+        // The variables take precedence over parameters with the same name.
+        assert_eq!(expression.eval(&[4.0, 2.0]), 6.5);
+        assert_eq!(expression.source(), "damage + per_level * level");
+    }
+
+    #[test]
+    fn formula_expressions() {
+        let formula = DamageFormula::Expression(Arc::new(
+            Expression::parse("damage * level", DamageFormula::VARIABLES, &HashMap::new()).unwrap(),
+        ));
+
+        assert_eq!(formula.call(2.5, 2), 5.0);
+    }
+
+    #[test]
+    fn unknown_identifiers() {
+        assert_eq!(
+            parse("damage * strength"),
+            Err(ExpressionError::UnknownVariable("strength".to_owned()))
+        );
+        assert_eq!(
+            parse("pow(damage, 2)"),
+            Err(ExpressionError::UnknownFunction("pow".to_owned()))
+        );
+    }
+
+    #[test]
+    fn unbalanced_parentheses() {
+        assert_eq!(parse("(damage + 1"), Err(ExpressionError::UnexpectedEnd));
+        assert_eq!(
+            parse("damage + 1)"),
+            Err(ExpressionError::UnexpectedToken(")".to_owned()))
+        );
+        assert_eq!(parse("min(damage, 1"), Err(ExpressionError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn trailing_tokens() {
+        assert_eq!(
+            parse("damage level"),
+            Err(ExpressionError::UnexpectedToken("level".to_owned()))
+        );
+        assert_eq!(
+            parse("damage 2"),
+            Err(ExpressionError::UnexpectedToken("2".to_owned()))
+        );
+        assert_eq!(parse("damage *"), Err(ExpressionError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn invalid_tokens() {
+        assert_eq!(
+            parse("damage % 2"),
+            Err(ExpressionError::UnexpectedToken("%".to_owned()))
+        );
+        assert_eq!(
+            parse("1.2.3"),
+            Err(ExpressionError::UnexpectedToken("1.2.3".to_owned()))
+        );
+    }
+
+    #[test]
+    fn wrong_argument_count() {
+        assert_eq!(
+            parse("min(damage)"),
+            Err(ExpressionError::WrongArgumentCount {
+                function: "min".to_owned(),
+                expected: 2,
+                found: 1,
+            })
+        );
+        assert_eq!(
+            parse("abs()"),
+            Err(ExpressionError::WrongArgumentCount {
+                function: "abs".to_owned(),
+                expected: 1,
+                found: 0,
+            })
+        );
+    }
+}
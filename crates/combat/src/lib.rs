@@ -1,16 +1,30 @@
 use std::{
+    borrow::Cow,
     collections::{HashMap, HashSet},
     time::{Duration, Instant},
 };
 
 use bevy_ecs::query::QueryData;
-use calculations::damage_after_armor;
+use combo::ComboPlugin;
+use config_file::{reload_combat_config, set, CombatConfigReloadedEvent, CombatFormulas};
+use durability::ItemWear;
 use fall_damage::FallingState;
+use formula::{
+    ArmorFormula, AttackCooldownFormula, BurnFormula, DamageFormula, Formula, KnockbackFormula,
+    ProtectionFormula, ThornsFormula,
+};
+use projectile::{projectile_system, ProjectileHitEvent};
+use serde::{Deserialize, Serialize};
+pub use team::Team;
+use team::TeamPlugin;
 use utils::{
-    damage::{DamageEvent, StartBurningEvent},
-    enchantments::{Enchantment, ItemStackEnchantmentsExt},
-    item_values::{CombatSystem, EquipmentExt},
-    ItemKindExt,
+    attributes::entity_attribute_bonus,
+    damage::{DamageEvent, DamageType, StartBurningEvent, VelocityChangedEvent},
+    enchantments::{Enchantment, EnchantmentProtection, ItemStackEnchantmentsExt},
+    item_values::{CombatSystem, EquipmentExt, ItemStackExt, ItemValueTable, ItemValueTables},
+    random::GameRng,
+    region::Regions,
+    spawn_protection::{ProtectedAttacks, SpawnProtection},
 };
 use valence::{
     entity::{
@@ -18,13 +32,34 @@ use valence::{
         living::StuckArrowCount,
         EntityId, EntityStatuses, Velocity,
     },
+    event_loop::PacketEvent,
+    hand_swing::HandSwingEvent,
+    interact_item::InteractItemEvent,
     inventory::{HeldItem, UpdateSelectedSlotEvent},
     prelude::*,
+    protocol::{
+        packets::play::{player_action_c2s::PlayerAction, PlayerActionC2s},
+        sound::SoundCategory,
+        Hand, Sound,
+    },
+    Layer,
 };
 
 pub mod calculations;
+pub mod combo;
+pub mod config_file;
+pub mod durability;
+pub mod feedback;
+pub mod formula;
+pub mod projectile;
+pub mod stats;
+pub mod team;
 
 const BASE_HIT_COOLDOWN: Duration = Duration::from_millis(500);
+/// The attack strength above which an attack counts as charged (java behavior).
+const FULL_ATTACK_STRENGTH: f32 = 0.9;
+/// The vertical distance from the victim in which entities are hit by a sweep attack.
+const SWEEP_VERTICAL_RANGE: f64 = 0.25;
 
 /// Attached to every player that participates in combat.
 #[derive(Component)]
@@ -39,10 +74,16 @@ pub struct CombatState {
     pub sprinting: bool,
     /// The player is sneaking.
     pub sneaking: bool,
-    /// The combat config for the player.
-    pub combat_config: PlayerCombatConfig,
-    /// The player is currently blocking with a shield.
+    /// The id of the shared combat config of the player in [`CombatConfigs`].
+    pub combat_config: u64,
+    /// Changes to the config only for this player, they are applied over the shared config (or
+    /// the config of the region).
+    pub config_override: Option<Box<PlayerCombatConfigOverride>>,
+    /// The player is currently blocking (see [`PlayerCombatConfig::blocking`]).
     pub blocking: bool,
+    /// The id of the combat config of the region the player is in, it is used instead of the
+    /// shared config. This is updated from the [`Regions`] of the layer every tick.
+    pub region_config: Option<u64>,
 }
 
 impl Default for CombatState {
@@ -53,14 +94,126 @@ impl Default for CombatState {
             last_attack: Instant::now(),
             sprinting: false,
             sneaking: false,
-            combat_config: PlayerCombatConfig::default(),
+            combat_config: DEFAULT_COMBAT_CONFIG,
+            config_override: None,
             blocking: false,
+            region_config: None,
+        }
+    }
+}
+
+impl CombatState {
+    /// Uses the shared combat config with the given id.
+    pub fn with_config(combat_config: u64) -> Self {
+        Self {
+            combat_config,
+            ..Default::default()
+        }
+    }
+
+    /// Uses the default config with changes only for this player.
+    pub fn with_override(config_override: PlayerCombatConfigOverride) -> Self {
+        Self {
+            config_override: Some(Box::new(config_override)),
+            ..Default::default()
+        }
+    }
+
+    /// The combat config of the player.
+    ///
+    /// This is the config of the region the player is in or the shared config, with the override
+    /// of the player applied if it has one.
+    /// If the shared config does not exist, the default config is used.
+    pub fn config<'a>(&'a self, configs: &'a CombatConfigs) -> Cow<'a, PlayerCombatConfig> {
+        let config = configs.get(self.region_config.unwrap_or(self.combat_config));
+
+        match &self.config_override {
+            Some(config_override) => {
+                let mut config = config.clone();
+                config_override.apply(&mut config);
+
+                Cow::Owned(config)
+            }
+            None => Cow::Borrowed(config),
+        }
+    }
+
+    /// Resets the timers and movement flags, keeping the combat config.
+    ///
+    /// This should be called when the entity respawns so stale state does not leak into the next life.
+    pub fn reset(&mut self) {
+        let now = Instant::now();
+
+        self.last_hit = now;
+        self.last_got_hit = now;
+        self.last_attack = now;
+        self.sprinting = false;
+        self.sneaking = false;
+        self.blocking = false;
+    }
+}
+
+/// The id of the combat config that is used if no other config is set.
+pub const DEFAULT_COMBAT_CONFIG: u64 = 0;
+
+/// The combat configs shared by the players (e.g. per team or per arena).
+///
+/// The players reference a config by its id in [`CombatState::combat_config`], so changing a
+/// config here changes the rules for every player that uses it.
+#[derive(Resource)]
+pub struct CombatConfigs {
+    configs: HashMap<u64, PlayerCombatConfig>,
+    default: PlayerCombatConfig,
+}
+
+impl Default for CombatConfigs {
+    fn default() -> Self {
+        Self::new(PlayerCombatConfig::default())
+    }
+}
+
+impl CombatConfigs {
+    /// Create the configs with the config for [`DEFAULT_COMBAT_CONFIG`].
+    pub fn new(default: PlayerCombatConfig) -> Self {
+        Self {
+            configs: HashMap::new(),
+            default,
+        }
+    }
+
+    /// Add or replace a config.
+    pub fn insert(&mut self, id: u64, config: PlayerCombatConfig) {
+        if id == DEFAULT_COMBAT_CONFIG {
+            self.default = config;
+        } else {
+            self.configs.insert(id, config);
+        }
+    }
+
+    /// Remove a config, players using it will use the default config.
+    /// The default config can not be removed.
+    pub fn remove(&mut self, id: u64) -> Option<PlayerCombatConfig> {
+        self.configs.remove(&id)
+    }
+
+    /// Get the config with the id or the default config if it does not exist.
+    pub fn get(&self, id: u64) -> &PlayerCombatConfig {
+        self.configs.get(&id).unwrap_or(&self.default)
+    }
+
+    /// Get the config with the id to change it.
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut PlayerCombatConfig> {
+        if id == DEFAULT_COMBAT_CONFIG {
+            Some(&mut self.default)
+        } else {
+            self.configs.get_mut(&id)
         }
     }
 }
 
 /// Contains configuration options mostly multipliers for the player.
 /// They will usually not be changed during the game.
+#[derive(Clone)]
 pub struct PlayerCombatConfig {
     /// The combat system that will be used to determine the weapon damage.
     /// This only affects the damage, not the actual cooldown, change [`Self::attack_cooldown_multiplier`] for that.
@@ -69,9 +222,16 @@ pub struct PlayerCombatConfig {
     ///
     /// [`CombatSystem::New`] is the 1.9+ combat system.
     pub combat_system: CombatSystem,
+    /// The name of the [`ItemValueTable`] in the [`ItemValueTables`] resource that is used for the
+    /// weapon and armor values of the player.
+    ///
+    /// If `None`, the table of [`Self::combat_system`] will be used.
+    pub item_value_table: Option<String>,
     /// How many arrows can be in the player at once.
     pub arrows_stick: u8,
-    /// Teams considered friendly.
+    /// Teams considered friendly (allies) besides the own [`Team`].
+    ///
+    /// Damage dealt to their members uses the friendly fire multipliers.
     pub friendly_teams: HashSet<u16>,
     /// The minimum time between two attacks. (This is not the attack cooldown, but the minimum time before another attack can be registered).
     pub hit_cooldown: Duration,
@@ -105,6 +265,8 @@ pub struct PlayerCombatConfig {
     pub critical_hit_chance_falling: f32,
     /// The damage multiplier of a critical hit.
     pub critical_hit_damage_multiplier: f32,
+    /// If the player can land critical hits while sprinting (1.8), since 1.9 sprinting prevents them.
+    pub critical_hit_while_sprinting: bool,
 
     /// The damage multiplier of the player.
     pub damage_multiplier: PlayerStateDependantValue,
@@ -124,23 +286,286 @@ pub struct PlayerCombatConfig {
     /// The formula that should be used to calculate the received damage after armor.
     ///
     /// The parameters are: `damage`, `armor_points`, `toughness`.
-    pub armor_formula: fn(f32, f32, f32) -> f32,
+    pub armor_formula: ArmorFormula,
+
+    /// The formula that should be used to calculate the received damage after the protection enchantments
+    /// of the armor (applied after [`Self::armor_formula`]).
+    ///
+    /// The parameters are: `damage`, `protection_factor`.
+    ///
+    /// If this is `None`, protection enchantments will not reduce the damage the player takes.
+    ///
+    /// It is also used for the damage that is not dealt by the combat (e.g. fall damage), see
+    /// [`EnchantmentProtection`].
+    pub protection_formula: Option<ProtectionFormula>,
 
     /// Attack cooldown damage multiplier for weapon damage formula
     ///
     /// The parameters are: `weapon_attack_speed`, `last_attack`.
-    pub damage_cooldown_formula_base_damage: fn(f32, Instant) -> f32,
+    pub damage_cooldown_formula_base_damage: AttackCooldownFormula,
 
     /// Attack cooldown damage multiplier for enchantments formula
     ///
     /// The parameters are: `weapon_attack_speed`, `last_attack`.
-    pub damage_cooldown_enchantment_formula: fn(f32, Instant) -> f32,
+    pub damage_cooldown_enchantment_formula: AttackCooldownFormula,
 
     /// The configuration of combat relevant enchantments.
     pub enchantment_config: CombatEnchantmentConfig,
+
+    /// If the weapon of the player loses durability when attacking.
+    pub weapon_durability: bool,
+    /// If the armor of the player loses durability when getting hit.
+    pub armor_durability: bool,
+
+    /// The sweep attack of swords (1.9+).
+    ///
+    /// If `None`, the attacks of the player only hit the attacked entity.
+    pub sweep_attack: Option<SweepAttackConfig>,
+    /// How the player can block attacks.
+    ///
+    /// If `None`, the player can not block.
+    pub blocking: Option<BlockingConfig>,
+}
+
+impl PlayerCombatConfig {
+    /// The 1.7/1.8 combat: no attack cooldown, sword blocking, critical hits while sprinting
+    /// and no sweep attacks.
+    pub fn legacy_1_8() -> Self {
+        Self {
+            combat_system: CombatSystem::Old,
+            item_value_table: None,
+            arrows_stick: 0,
+            friendly_teams: HashSet::new(),
+            hit_cooldown: BASE_HIT_COOLDOWN,
+            attack_cooldown_multiplier: None,
+            armor_points_multiplier: 1.0,
+            armor_toughness_multiplier: 1.0,
+            armor_knockback_resistance_multiplier: 1.0,
+            horizontal_knockback: PlayerStateDependantValue {
+                base: 0.4,
+                sprinting: 0.8,
+                sneaking: 0.4,
+                in_air: 0.4,
+            },
+            vertical_knockback: PlayerStateDependantValue {
+                base: 0.36,
+                sprinting: 0.42,
+                sneaking: 0.36,
+                in_air: 0.36,
+            },
+            horizontal_knockback_received_multiplier: PlayerStateDependantValue {
+                base: 1.0,
+                sprinting: 1.0,
+                sneaking: 1.0,
+                in_air: 0.6,
+            },
+            vertical_knockback_received_multiplier: PlayerStateDependantValue {
+                base: 1.0,
+                sprinting: 1.0,
+                sneaking: 1.0,
+                in_air: 0.8,
+            },
+            random_critical_hit_chance: PlayerStateDependantValue::always(0.0),
+            critical_hit_chance_falling: 1.0,
+            critical_hit_damage_multiplier: 1.5,
+            critical_hit_while_sprinting: true,
+            damage_multiplier: PlayerStateDependantValue::always(1.0),
+            damage_taken_multiplier: PlayerStateDependantValue::always(1.0),
+            fire_damage_multiplier: PlayerStateDependantValue::always(1.0),
+            fire_duration_multiplier: PlayerStateDependantValue::always(1.0),
+            friendly_fire_damage_multiplier: 0.0,
+            friendly_fire_damage_taken_multiplier: 0.0,
+            armor_formula: Formula::Function(calculations::damage_after_armor_legacy),
+            protection_formula: Some(Formula::Function(calculations::damage_after_enchantments)),
+            enchantment_config: CombatEnchantmentConfig {
+                sharpness_formula: Some(Formula::Function(calculations::enchant_sharpness_damage)),
+                knockback_formula: Some(Formula::Function(calculations::enchant_knockback)),
+                fire_aspect_formula: Some(Formula::Function(calculations::enchant_fire_aspect)),
+                flame_formula: Some(Formula::Function(calculations::enchant_flame)),
+                power_formula: Some(Formula::Function(calculations::enchant_power_damage)),
+                punch_formula: Some(Formula::Function(calculations::enchant_punch)),
+                thorns_formula: Some(Formula::Function(calculations::enchant_thorns)),
+            },
+            damage_cooldown_formula_base_damage: Formula::Function(
+                calculations::attack_cooldown_base_damage,
+            ),
+            damage_cooldown_enchantment_formula: Formula::Function(
+                calculations::attack_cooldown_enchantment_damage,
+            ),
+            weapon_durability: true,
+            armor_durability: true,
+            sweep_attack: None,
+            blocking: Some(BlockingConfig::sword()),
+        }
+    }
+
+    /// The 1.9+ combat: attack cooldown, shields, sweep attacks and no critical hits while sprinting.
+    pub fn modern() -> Self {
+        Self {
+            combat_system: CombatSystem::New,
+            attack_cooldown_multiplier: Some(1.0),
+            critical_hit_while_sprinting: false,
+            armor_formula: Formula::Function(calculations::damage_after_armor),
+            sweep_attack: Some(SweepAttackConfig::default()),
+            blocking: Some(BlockingConfig::shield()),
+            ..Self::legacy_1_8()
+        }
+    }
+
+    /// The 1.8 combat on a modern client, like the OldCombatMechanics plugin does it.
+    ///
+    /// There is no attack cooldown and no sweep attack, but shields only block half of the damage
+    /// (the same as a sword block in 1.8), since modern clients can not block with swords.
+    pub fn old_combat_mechanics() -> Self {
+        Self {
+            blocking: Some(BlockingConfig {
+                sword_blocking: false,
+                shield_blocking: true,
+                damage_multiplier: 0.5,
+                knockback_multiplier: 1.0,
+                front_only: false,
+            }),
+            ..Self::legacy_1_8()
+        }
+    }
+
+    /// The item value table used for the player.
+    pub fn item_values<'a>(&self, tables: &'a ItemValueTables) -> &'a ItemValueTable {
+        tables.get(self.item_value_table.as_deref(), &self.combat_system)
+    }
+}
+
+/// Changes to the combat config for a single player (see [`CombatState::config_override`]).
+///
+/// Every value that is set replaces the value of the config the player would use otherwise, the
+/// field names are the same as in [`PlayerCombatConfig`].
+#[derive(Clone, Default)]
+pub struct PlayerCombatConfigOverride {
+    pub combat_system: Option<CombatSystem>,
+    pub item_value_table: Option<Option<String>>,
+    pub arrows_stick: Option<u8>,
+    pub friendly_teams: Option<HashSet<u16>>,
+    pub hit_cooldown: Option<Duration>,
+    pub attack_cooldown_multiplier: Option<Option<f32>>,
+    pub armor_points_multiplier: Option<f32>,
+    pub armor_toughness_multiplier: Option<f32>,
+    pub armor_knockback_resistance_multiplier: Option<f32>,
+    pub horizontal_knockback: Option<PlayerStateDependantValue>,
+    pub vertical_knockback: Option<PlayerStateDependantValue>,
+    pub horizontal_knockback_received_multiplier: Option<PlayerStateDependantValue>,
+    pub vertical_knockback_received_multiplier: Option<PlayerStateDependantValue>,
+    pub random_critical_hit_chance: Option<PlayerStateDependantValue>,
+    pub critical_hit_chance_falling: Option<f32>,
+    pub critical_hit_damage_multiplier: Option<f32>,
+    pub critical_hit_while_sprinting: Option<bool>,
+    pub damage_multiplier: Option<PlayerStateDependantValue>,
+    pub fire_damage_multiplier: Option<PlayerStateDependantValue>,
+    pub fire_duration_multiplier: Option<PlayerStateDependantValue>,
+    pub damage_taken_multiplier: Option<PlayerStateDependantValue>,
+    pub friendly_fire_damage_multiplier: Option<f32>,
+    pub friendly_fire_damage_taken_multiplier: Option<f32>,
+    pub armor_formula: Option<ArmorFormula>,
+    pub protection_formula: Option<Option<ProtectionFormula>>,
+    pub damage_cooldown_formula_base_damage: Option<AttackCooldownFormula>,
+    pub damage_cooldown_enchantment_formula: Option<AttackCooldownFormula>,
+    pub enchantment_config: Option<CombatEnchantmentConfig>,
+    pub weapon_durability: Option<bool>,
+    pub armor_durability: Option<bool>,
+    pub sweep_attack: Option<Option<SweepAttackConfig>>,
+    pub blocking: Option<Option<BlockingConfig>>,
+}
+
+impl PlayerCombatConfigOverride {
+    /// Replaces the values of the config that are set in the override.
+    pub fn apply(&self, config: &mut PlayerCombatConfig) {
+        set(&mut config.combat_system, &self.combat_system);
+        set(&mut config.item_value_table, &self.item_value_table);
+        set(&mut config.arrows_stick, &self.arrows_stick);
+        set(&mut config.friendly_teams, &self.friendly_teams);
+        set(&mut config.hit_cooldown, &self.hit_cooldown);
+        set(
+            &mut config.attack_cooldown_multiplier,
+            &self.attack_cooldown_multiplier,
+        );
+        set(
+            &mut config.armor_points_multiplier,
+            &self.armor_points_multiplier,
+        );
+        set(
+            &mut config.armor_toughness_multiplier,
+            &self.armor_toughness_multiplier,
+        );
+        set(
+            &mut config.armor_knockback_resistance_multiplier,
+            &self.armor_knockback_resistance_multiplier,
+        );
+        set(&mut config.horizontal_knockback, &self.horizontal_knockback);
+        set(&mut config.vertical_knockback, &self.vertical_knockback);
+        set(
+            &mut config.horizontal_knockback_received_multiplier,
+            &self.horizontal_knockback_received_multiplier,
+        );
+        set(
+            &mut config.vertical_knockback_received_multiplier,
+            &self.vertical_knockback_received_multiplier,
+        );
+        set(
+            &mut config.random_critical_hit_chance,
+            &self.random_critical_hit_chance,
+        );
+        set(
+            &mut config.critical_hit_chance_falling,
+            &self.critical_hit_chance_falling,
+        );
+        set(
+            &mut config.critical_hit_damage_multiplier,
+            &self.critical_hit_damage_multiplier,
+        );
+        set(
+            &mut config.critical_hit_while_sprinting,
+            &self.critical_hit_while_sprinting,
+        );
+        set(&mut config.damage_multiplier, &self.damage_multiplier);
+        set(
+            &mut config.fire_damage_multiplier,
+            &self.fire_damage_multiplier,
+        );
+        set(
+            &mut config.fire_duration_multiplier,
+            &self.fire_duration_multiplier,
+        );
+        set(
+            &mut config.damage_taken_multiplier,
+            &self.damage_taken_multiplier,
+        );
+        set(
+            &mut config.friendly_fire_damage_multiplier,
+            &self.friendly_fire_damage_multiplier,
+        );
+        set(
+            &mut config.friendly_fire_damage_taken_multiplier,
+            &self.friendly_fire_damage_taken_multiplier,
+        );
+        set(&mut config.armor_formula, &self.armor_formula);
+        set(&mut config.protection_formula, &self.protection_formula);
+        set(
+            &mut config.damage_cooldown_formula_base_damage,
+            &self.damage_cooldown_formula_base_damage,
+        );
+        set(
+            &mut config.damage_cooldown_enchantment_formula,
+            &self.damage_cooldown_enchantment_formula,
+        );
+        set(&mut config.enchantment_config, &self.enchantment_config);
+        set(&mut config.weapon_durability, &self.weapon_durability);
+        set(&mut config.armor_durability, &self.armor_durability);
+        set(&mut config.sweep_attack, &self.sweep_attack);
+        set(&mut config.blocking, &self.blocking);
+    }
 }
 
 /// The current state of the player's movement.
+#[derive(Clone, Copy)]
 enum PlayerMovementState {
     Sprinting,
     Sneaking,
@@ -149,6 +574,8 @@ enum PlayerMovementState {
 }
 
 /// Values that depend on the current state of the player.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PlayerStateDependantValue {
     pub base: f32,
     pub sprinting: f32,
@@ -177,102 +604,148 @@ impl PlayerStateDependantValue {
     }
 }
 
+/// The sweep attack of swords (1.9+).
+///
+/// A sweep attack happens if the player attacks with a sword while on the ground, not sprinting
+/// and with a fully charged attack.
+#[derive(Clone)]
+pub struct SweepAttackConfig {
+    /// The formula to calculate the damage dealt to the entities next to the victim.
+    ///
+    /// The parameters are: `base_damage`, `sweeping_edge_level`.
+    pub damage_formula: DamageFormula,
+    /// The horizontal distance from the victim in which other entities are hit.
+    pub radius: f64,
+    /// The maximum distance from the attacker of the entities that are hit.
+    pub reach: f64,
+    /// The horizontal knockback the hit entities take.
+    pub knockback: f32,
+}
+
+impl Default for SweepAttackConfig {
+    fn default() -> Self {
+        Self {
+            damage_formula: Formula::Function(calculations::sweep_damage),
+            radius: 1.0,
+            reach: 3.0,
+            knockback: 0.4,
+        }
+    }
+}
+
+/// How a player can block attacks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockingConfig {
+    /// If the player can block by using a sword (1.8).
+    ///
+    /// Modern clients do not tell the server when they stop using a sword, so the block lasts
+    /// until the player swings or switches the item.
+    pub sword_blocking: bool,
+    /// If the player can block by using a shield (1.9+).
+    pub shield_blocking: bool,
+    /// The damage multiplier while blocking.
+    pub damage_multiplier: f32,
+    /// The knockback multiplier while blocking.
+    pub knockback_multiplier: f32,
+    /// If only attacks from the front are blocked.
+    pub front_only: bool,
+}
+
+impl BlockingConfig {
+    /// Blocking with a sword halves the damage (1.8).
+    /// (java behavior)
+    pub fn sword() -> Self {
+        Self {
+            sword_blocking: true,
+            shield_blocking: false,
+            damage_multiplier: 0.5,
+            knockback_multiplier: 1.0,
+            front_only: false,
+        }
+    }
+
+    /// Blocking with a shield blocks all damage from the front (1.9+).
+    /// (java behavior)
+    pub fn shield() -> Self {
+        Self {
+            sword_blocking: false,
+            shield_blocking: true,
+            damage_multiplier: 0.0,
+            knockback_multiplier: 0.0,
+            front_only: true,
+        }
+    }
+
+    /// If the item can be used to block.
+    pub fn can_block_with(&self, item: ItemKind) -> bool {
+        (self.sword_blocking && is_sword(item))
+            || (self.shield_blocking && item == ItemKind::Shield)
+    }
+}
+
+fn is_sword(item: ItemKind) -> bool {
+    matches!(
+        item,
+        ItemKind::WoodenSword
+            | ItemKind::StoneSword
+            | ItemKind::IronSword
+            | ItemKind::GoldenSword
+            | ItemKind::DiamondSword
+            | ItemKind::NetheriteSword
+    )
+}
+
+#[derive(Clone)]
 pub struct CombatEnchantmentConfig {
     /// The formula to calculate the damage after applying the sharpness enchantment.
     ///
     /// The parameters are: `weapon_base_damage`, `sharpness_level`.
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub sharpness_formula: Option<fn(f32, u32) -> f32>,
+    pub sharpness_formula: Option<DamageFormula>,
     /// The formula to calculate the knockback after applying the knockback enchantment.
     ///
     /// The parameters are: `base_knockback_vector`, `knockback_level`.
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub knockback_formula: Option<fn(Vec3, u32) -> Vec3>,
+    pub knockback_formula: Option<KnockbackFormula>,
     /// The formula to calculate the burn time and damage per second after applying the fire aspect enchantment.
     ///
     /// The parameters are: `fire_aspect_level`.
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub fire_aspect_formula: Option<fn(u32) -> (Duration, f32)>,
+    pub fire_aspect_formula: Option<BurnFormula>,
     /// The formula to calculate the burn time and damage per second after applying the flame enchantment.
     ///
     /// The parameters are: `fire_aspect_level`.
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub flame_formula: Option<fn(u32) -> (Duration, f32)>,
+    pub flame_formula: Option<BurnFormula>,
     /// The formula to calculate the damage after applying the power enchantment.
     ///
     /// The parameters are: `base_arrow_damage`, `power_level`.
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub power_formula: Option<fn(f32, u32) -> f32>,
+    pub power_formula: Option<DamageFormula>,
     /// The formula to calculate the knockback after applying the punch enchantment.
     ///
     /// The parameters are: `base_knockback_vector`, `punch_level`.
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub punch_formula: Option<fn(Vec3, u32) -> Vec3>,
-    // TODO: thorns,
+    pub punch_formula: Option<KnockbackFormula>,
+    /// The formula to calculate the damage an attacker takes from the thorns enchantment of one
+    /// armor piece of the victim (0 if it does not trigger).
+    ///
+    /// The parameters are: `thorns_level`, `chance_roll`, `damage_roll` (random numbers in `0.0..1.0`).
+    ///
+    /// If this is `None`, the enchantment will not be usable by the player.
+    pub thorns_formula: Option<ThornsFormula>,
 }
 
 impl Default for PlayerCombatConfig {
     fn default() -> Self {
-        Self {
-            combat_system: CombatSystem::Old,
-            arrows_stick: 0,
-            friendly_teams: HashSet::new(),
-            hit_cooldown: BASE_HIT_COOLDOWN,
-            attack_cooldown_multiplier: None,
-            armor_points_multiplier: 1.0,
-            armor_toughness_multiplier: 1.0,
-            armor_knockback_resistance_multiplier: 1.0,
-            horizontal_knockback: PlayerStateDependantValue {
-                base: 0.4,
-                sprinting: 0.8,
-                sneaking: 0.4,
-                in_air: 0.4,
-            },
-            vertical_knockback: PlayerStateDependantValue {
-                base: 0.36,
-                sprinting: 0.42,
-                sneaking: 0.36,
-                in_air: 0.36,
-            },
-            horizontal_knockback_received_multiplier: PlayerStateDependantValue {
-                base: 1.0,
-                sprinting: 1.0,
-                sneaking: 1.0,
-                in_air: 0.6,
-            },
-            vertical_knockback_received_multiplier: PlayerStateDependantValue {
-                base: 1.0,
-                sprinting: 1.0,
-                sneaking: 1.0,
-                in_air: 0.8,
-            },
-            random_critical_hit_chance: PlayerStateDependantValue::always(0.0),
-            critical_hit_chance_falling: 1.0,
-            critical_hit_damage_multiplier: 1.5,
-            damage_multiplier: PlayerStateDependantValue::always(1.0),
-            damage_taken_multiplier: PlayerStateDependantValue::always(1.0),
-            fire_damage_multiplier: PlayerStateDependantValue::always(1.0),
-            fire_duration_multiplier: PlayerStateDependantValue::always(1.0),
-            friendly_fire_damage_multiplier: 0.0,
-            friendly_fire_damage_taken_multiplier: 0.0,
-            armor_formula: calculations::damage_after_armor,
-            enchantment_config: CombatEnchantmentConfig {
-                sharpness_formula: Some(calculations::enchant_sharpness_damage),
-                knockback_formula: Some(calculations::enchant_knockback),
-                fire_aspect_formula: Some(calculations::enchant_fire_aspect),
-                flame_formula: Some(calculations::enchant_flame),
-                power_formula: Some(calculations::enchant_power_damage),
-                punch_formula: Some(calculations::enchant_punch),
-            },
-            damage_cooldown_formula_base_damage: calculations::attack_cooldown_base_damage,
-            damage_cooldown_enchantment_formula: calculations::attack_cooldown_enchantment_damage,
-        }
+        Self::legacy_1_8()
     }
 }
 
@@ -296,32 +769,32 @@ fn apply_enchantments(
         match enchant {
             Enchantment::Sharpness => {
                 if let Some(formula) = &enchantment_config.sharpness_formula {
-                    base_damage = formula(base_damage, level);
+                    base_damage = formula.call(base_damage, level);
                 }
             }
             Enchantment::Knockback => {
                 if let Some(formula) = &enchantment_config.knockback_formula {
-                    base_knockback = formula(base_knockback, level);
+                    base_knockback = formula.call(base_knockback, level);
                 }
             }
             Enchantment::FireAspect => {
                 if let Some(formula) = &enchantment_config.fire_aspect_formula {
-                    burn = Some(formula(level));
+                    burn = Some(formula.call(level));
                 }
             }
             Enchantment::Flame => {
                 if let Some(formula) = &enchantment_config.flame_formula {
-                    burn = Some(formula(level));
+                    burn = Some(formula.call(level));
                 }
             }
             Enchantment::Power => {
                 if let Some(formula) = &enchantment_config.power_formula {
-                    base_damage = formula(base_damage, level);
+                    base_damage = formula.call(base_damage, level);
                 }
             }
             Enchantment::Punch => {
                 if let Some(formula) = &enchantment_config.punch_formula {
-                    base_knockback = formula(base_knockback, level);
+                    base_knockback = formula.call(base_knockback, level);
                 }
             }
             _ => {}
@@ -335,46 +808,128 @@ fn apply_enchantments(
     }
 }
 
-/// A Team component that is attached to entities that are part of a team.
-#[derive(Component, PartialEq, Eq, Hash, Clone, Copy)]
-pub struct Team(pub u16);
-
 #[derive(QueryData)]
 #[query_data(mutable)]
 struct CombatQuery {
+    entity: Entity,
     client: Option<&'static mut Client>,
     entity_id: &'static EntityId,
     position: &'static Position,
+    look: &'static Look,
+    layer: &'static EntityLayerId,
     velocity: &'static mut Velocity,
     state: &'static mut CombatState,
     statuses: &'static mut EntityStatuses,
     // To retrieve the weapon used.
-    inventory: Option<&'static Inventory>,
+    inventory: Option<&'static mut Inventory>,
     // Held item is optional so we can add the CombatQuery to NPCs as well.
     held_item: Option<&'static HeldItem>,
     falling_state: &'static FallingState,
-    equipment: &'static Equipment,
+    equipment: &'static mut Equipment,
     team: Option<&'static Team>,
     stuck_arrow_count: Option<&'static mut StuckArrowCount>,
     // Used for the attack cooldown
     attributes: &'static mut EntityAttributes,
+    spawn_protection: Option<&'static mut SpawnProtection>,
+}
+
+/// An attack of an entity on another entity.
+///
+/// The attacks of clients are sent automatically, NPCs and mobs can send this event to attack
+/// with the same rules. Both entities need the components of a combat participant
+/// (e.g. [`CombatState`], [`FallingState`], [`Equipment`] and [`EntityStatuses`]).
+#[derive(Event, Clone)]
+pub struct AttackEvent {
+    pub attacker: Entity,
+    pub victim: Entity,
+    pub weapon: AttackWeapon,
+}
+
+/// An event that will be fired when an attack hits an entity (also for every entity hit by a
+/// sweep attack), after the [`DamageEvent`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct CombatHitEvent {
+    pub attacker: Entity,
+    pub victim: Entity,
+    /// The damage after armor and multipliers.
+    pub damage: f32,
+    pub critical: bool,
+    /// If the victim was hit by the sweep of the attack instead of the attack itself.
+    pub sweep: bool,
+    /// The knockback velocity the victim got.
+    pub knockback: Vec3,
+}
+
+/// What an entity attacks with.
+#[derive(Clone)]
+pub enum AttackWeapon {
+    /// The held item of the inventory or the main hand of the equipment.
+    HeldItem,
+    /// The item stack, e.g. the weapon of a mob that does not have equipment.
+    Item(ItemStack),
+    /// A fixed base damage, e.g. for mobs without weapons.
+    Damage(f32),
 }
 
 pub struct CombatPlugin;
 
 impl Plugin for CombatPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (combat_system, update_last_attack_on_item_switch));
+        app.add_plugins((TeamPlugin, ComboPlugin))
+            .init_resource::<ItemValueTables>()
+            .init_resource::<CombatConfigs>()
+            .init_resource::<CombatFormulas>()
+            .init_resource::<GameRng>()
+            .add_event::<CombatConfigReloadedEvent>()
+            .add_event::<AttackEvent>()
+            .add_event::<CombatHitEvent>()
+            .add_event::<ProjectileHitEvent>()
+            .add_systems(
+                Update,
+                (
+                    send_client_attacks.before(combat_system),
+                    update_region_configs.before(combat_system),
+                    sync_enchantment_protection.after(update_region_configs),
+                    combat_system,
+                    update_last_attack_on_item_switch,
+                    update_blocking,
+                    reload_combat_config,
+                    projectile_system,
+                ),
+            );
+    }
+}
+
+fn send_client_attacks(
+    mut interact_entity_events: EventReader<InteractEntityEvent>,
+    mut attack_writer: EventWriter<AttackEvent>,
+) {
+    for event in interact_entity_events.read() {
+        if matches!(event.interact, EntityInteraction::Attack) {
+            attack_writer.send(AttackEvent {
+                attacker: event.client,
+                victim: event.entity,
+                weapon: AttackWeapon::HeldItem,
+            });
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn combat_system(
     mut query: Query<CombatQuery>,
     mut damage_event_writer: EventWriter<DamageEvent>,
     mut start_burn_event_writer: EventWriter<StartBurningEvent>,
     mut sprinting_events: EventReader<SprintEvent>,
     mut sneaking_events: EventReader<SneakEvent>,
-    mut interact_entity_events: EventReader<InteractEntityEvent>,
+    mut attack_events: EventReader<AttackEvent>,
+    mut hit_writer: EventWriter<CombatHitEvent>,
+    mut velocity_writer: EventWriter<VelocityChangedEvent>,
+    mut rng: ResMut<GameRng>,
+    mut layers: Query<&mut ChunkLayer>,
+    regions: Query<&Regions>,
+    item_value_tables: Res<ItemValueTables>,
+    configs: Res<CombatConfigs>,
 ) {
     for &SprintEvent { client, state } in sprinting_events.read() {
         if let Ok(mut client) = query.get_mut(client) {
@@ -388,16 +943,13 @@ fn combat_system(
         }
     }
 
-    for &InteractEntityEvent {
-        client: attacker_ent,
-        entity: victim_ent,
-        interact,
-        ..
-    } in interact_entity_events.read()
+    for AttackEvent {
+        attacker: attacker_ent,
+        victim: victim_ent,
+        weapon: attack_weapon,
+    } in attack_events.read()
     {
-        if !matches!(interact, EntityInteraction::Attack) {
-            continue;
-        }
+        let (attacker_ent, victim_ent) = (*attacker_ent, *victim_ent);
 
         if attacker_ent == victim_ent {
             continue;
@@ -407,54 +959,85 @@ fn combat_system(
             continue;
         };
 
-        if attacker.state.last_hit.elapsed() < attacker.state.combat_config.hit_cooldown {
+        if !pvp_allowed(&attacker, &regions) || !pvp_allowed(&victim, &regions) {
+            continue;
+        }
+
+        if is_spawn_protected(&victim) {
             continue;
         }
 
+        if attacker.state.last_hit.elapsed() < attacker.state.config(&configs).hit_cooldown {
+            continue;
+        }
+
+        // Only an attack that hits ends the protection.
+        if let Some(protection) = attacker
+            .spawn_protection
+            .as_mut()
+            .filter(|protection| protection.is_active())
+        {
+            match protection.attacks {
+                ProtectedAttacks::Cancel => protection.cancel(),
+                ProtectedAttacks::Deny => continue,
+                ProtectedAttacks::Allow => {}
+            }
+        }
+
         let now = Instant::now();
+        // The attack cooldown depends on the time since the previous attack, not this one.
+        let last_attack = attacker.state.last_attack;
 
         attacker.state.last_hit = now;
         attacker.state.last_attack = now;
         victim.state.last_got_hit = now;
 
-        let attacker_config = &attacker.state.combat_config;
-        let victim_config = &victim.state.combat_config;
-
-        let attacker_state = match (
-            attacker.state.sprinting,
-            attacker.state.sneaking,
-            attacker.falling_state.falling,
-        ) {
-            (true, _, _) => PlayerMovementState::Sprinting,
-            (_, true, _) => PlayerMovementState::Sneaking,
-            (_, _, true) => PlayerMovementState::InAir,
-            _ => PlayerMovementState::None,
-        };
+        let attacker_config = attacker.state.config(&configs);
+        let victim_config = victim.state.config(&configs);
 
-        let victim_state = match (
-            victim.state.sprinting,
-            victim.state.sneaking,
-            victim.falling_state.falling,
-        ) {
-            (true, _, _) => PlayerMovementState::Sprinting,
-            (_, true, _) => PlayerMovementState::Sneaking,
-            (_, _, true) => PlayerMovementState::InAir,
-            _ => PlayerMovementState::None,
-        };
+        let attacker_item_values = attacker_config.item_values(&item_value_tables);
+        let victim_item_values = victim_config.item_values(&item_value_tables);
+
+        let attacker_state = movement_state(&attacker.state, attacker.falling_state);
+        let victim_state = movement_state(&victim.state, victim.falling_state);
 
         let direction = (victim.position.0 - attacker.position.0)
             .normalize()
             .as_vec3();
 
-        let weapon = match (attacker.held_item, attacker.inventory) {
-            (Some(held_item), Some(inventory)) => inventory.slot(held_item.slot()),
-            _ => return,
+        let weapon = match attack_weapon {
+            AttackWeapon::HeldItem => match (attacker.held_item, attacker.inventory.as_deref()) {
+                (Some(held_item), Some(inventory)) => inventory.slot(held_item.slot()).clone(),
+                _ => attacker.equipment.main_hand().clone(),
+            },
+            AttackWeapon::Item(stack) => stack.clone(),
+            AttackWeapon::Damage(_) => ItemStack::EMPTY,
+        };
+        let weapon_damage = match attack_weapon {
+            AttackWeapon::Damage(damage) => *damage,
+            _ => weapon.attack_damage(attacker_item_values),
+        };
+
+        // Without an attack cooldown every attack is fully charged.
+        let attack_speed = attacker_config
+            .attack_cooldown_multiplier
+            .map(|cooldown_multiplier| {
+                weapon.attack_speed(attacker_item_values) * cooldown_multiplier
+            });
+        let fully_charged = attack_speed.map_or(true, |attack_speed| {
+            calculations::attack_strength(attack_speed, last_attack) > FULL_ATTACK_STRENGTH
+        });
+
+        // Since 1.9 the sprint knockback is only dealt by charged attacks.
+        let knockback_state = match attacker_state {
+            PlayerMovementState::Sprinting if !fully_charged => PlayerMovementState::None,
+            state => state,
         };
 
         let knockback_xz = attacker_config
             .horizontal_knockback
-            .current(&attacker_state);
-        let knockback_y = attacker_config.vertical_knockback.current(&attacker_state);
+            .current(&knockback_state);
+        let knockback_y = attacker_config.vertical_knockback.current(&knockback_state);
 
         // TODO: set based on tick rate
         // TODO: this is not accurate
@@ -465,19 +1048,16 @@ fn combat_system(
         );
 
         let weapon_echants = weapon.enchantments();
-        let mut base_damage = weapon.item.attack_damage(&attacker_config.combat_system);
-
-        if let Some(cooldown_multiplier) = &attacker_config.attack_cooldown_multiplier {
-            base_damage = base_damage
-                * (attacker_config.damage_cooldown_formula_base_damage)(
-                    base_damage,
-                    attacker.state.last_attack,
-                )
-                * cooldown_multiplier;
-        }
+        let sweeping_level = weapon_echants
+            .get(&Enchantment::SweepingEdge)
+            .copied()
+            .unwrap_or(0);
+        let mut base_damage = weapon_damage
+            + entity_attribute_bonus(&attacker.attributes, EntityAttribute::GenericAttackDamage)
+                as f32;
 
         let EnchantmentValues {
-            mut damage,
+            damage,
             mut knockback,
             burn,
         } = apply_enchantments(
@@ -487,6 +1067,17 @@ fn combat_system(
             &attacker_config.enchantment_config,
         );
 
+        let mut enchantment_damage = damage - base_damage;
+
+        if let Some(attack_speed) = attack_speed {
+            base_damage *= attacker_config
+                .damage_cooldown_formula_base_damage
+                .call(attack_speed, last_attack);
+            enchantment_damage *= attacker_config
+                .damage_cooldown_enchantment_formula
+                .call(attack_speed, last_attack);
+        }
+
         if let Some((burn_time, burn_dps)) = burn {
             let burn_event = StartBurningEvent {
                 victim: victim_ent,
@@ -505,50 +1096,82 @@ fn combat_system(
             start_burn_event_writer.send(burn_event);
         }
 
-        let enchantment_extra_dmg = damage - base_damage;
+        let critical_hit = fully_charged
+            && (attacker_config.critical_hit_while_sprinting || !attacker.state.sprinting)
+            && attacker_config
+                .random_critical_hit_chance
+                .current(&attacker_state)
+                + if attacker.falling_state.falling {
+                    attacker_config.critical_hit_chance_falling
+                } else {
+                    0.0
+                }
+                > rng.f32();
 
-        // TODO: im not sure if this is actually applied after the base damage.
-        if let Some(cooldown_multiplier) = &attacker_config.attack_cooldown_multiplier {
-            damage = damage
-                * (attacker_config.damage_cooldown_enchantment_formula)(
-                    enchantment_extra_dmg,
-                    attacker.state.last_attack,
-                )
-                * cooldown_multiplier;
+        // The critical hit multiplier only applies to the weapon damage, not to enchantments.
+        if critical_hit {
+            base_damage *= attacker_config.critical_hit_damage_multiplier;
         }
 
-        damage *= attacker_config.damage_multiplier.current(&attacker_state);
+        // The sweep attack needs a charged sword attack on the ground without sprinting.
+        let sweep = attacker_config
+            .sweep_attack
+            .as_ref()
+            .filter(|_| {
+                fully_charged
+                    && !critical_hit
+                    && !attacker.state.sprinting
+                    && !attacker.falling_state.falling
+                    && is_sword(weapon.item)
+            })
+            .map(|sweep| {
+                (
+                    sweep.damage_formula.call(base_damage, sweeping_level),
+                    sweep.radius,
+                    sweep.reach,
+                    sweep.knockback,
+                )
+            });
 
-        damage = damage_after_armor(
-            damage,
-            victim.equipment.armor_points() * victim_config.armor_points_multiplier,
-            victim.equipment.armor_toughness() * victim_config.armor_toughness_multiplier,
-        );
+        let mut damage = (base_damage + enchantment_damage)
+            * attacker_config.damage_multiplier.current(&attacker_state);
 
-        damage *= victim_config.damage_taken_multiplier.current(&victim_state);
+        let block = victim_config.blocking.as_ref().filter(|blocking| {
+            victim.state.blocking
+                && (!blocking.front_only
+                    || is_in_front(victim.look, victim.position.0, attacker.position.0))
+        });
 
-        if let (Some(attacker_team), Some(victim_team)) = (attacker.team, victim.team) {
-            if attacker_team == victim_team {
-                damage *= attacker_config.friendly_fire_damage_multiplier;
-                damage *= victim_config.friendly_fire_damage_taken_multiplier;
-            }
+        if let Some(block) = block {
+            damage *= block.damage_multiplier;
+            knockback *= block.knockback_multiplier;
         }
 
-        if attacker_config
-            .random_critical_hit_chance
-            .current(&attacker_state)
-            + if attacker.falling_state.falling {
-                attacker_config.critical_hit_chance_falling
+        let friendly_fire_multiplier =
+            if team::are_friendly(&attacker_config, attacker.team, victim.team) {
+                attacker_config.friendly_fire_damage_multiplier
+                    * victim_config.friendly_fire_damage_taken_multiplier
             } else {
-                0.0
-            }
-            > rand::random::<f32>()
-        {
-            damage *= attacker_config.critical_hit_damage_multiplier;
-        }
+                1.0
+            };
+        // Teammates that can not hurt each other do not wear down their gear or trigger thorns.
+        let harmless_friendly_hit = friendly_fire_multiplier <= 0.0;
+
+        // The armor is worn down by the damage the friendly fire lets through.
+        let damage_before_armor = damage * friendly_fire_multiplier;
 
-        let knockback_resistance = victim.equipment.knockback_resistance()
-            * victim_config.armor_knockback_resistance_multiplier;
+        damage = damage_after_defense(
+            &victim,
+            &victim_config,
+            victim_item_values,
+            DamageType::EntityAttack,
+            damage,
+        );
+        damage *= victim_config.damage_taken_multiplier.current(&victim_state);
+        damage *= friendly_fire_multiplier;
+
+        let knockback_resistance =
+            knockback_resistance(&victim, &victim_config, victim_item_values);
 
         knockback.x *= 1.0 - knockback_resistance;
         knockback.z *= 1.0 - knockback_resistance;
@@ -567,40 +1190,389 @@ fn combat_system(
         knockback.z *= knockback_received_xz_mult;
         knockback.y *= knockback_received_y_mult;
 
-        if let Some(mut client) = victim.client {
-            client.set_velocity(knockback);
-        } else {
-            victim.velocity.0 += knockback;
+        apply_knockback(&mut victim, knockback, &mut velocity_writer);
+
+        // Only the held item is worn down, explicit weapons are not part of the entity.
+        if attacker_config.weapon_durability
+            && !harmless_friendly_hit
+            && matches!(attack_weapon, AttackWeapon::HeldItem)
+        {
+            let mut weapon = weapon;
+            let wear_amount = durability::weapon_wear(weapon.item);
+
+            match durability::damage_item(&mut weapon, wear_amount, false, &mut rng) {
+                ItemWear::Unchanged => {}
+                wear => {
+                    match (attacker.held_item, attacker.inventory.as_mut()) {
+                        (Some(held_item), Some(inventory)) => {
+                            inventory.set_slot(held_item.slot(), weapon)
+                        }
+                        _ => attacker.equipment.set_main_hand(weapon),
+                    }
+
+                    if wear == ItemWear::Broken {
+                        if let Ok(mut layer) = layers.get_mut(attacker.layer.0) {
+                            play_item_break_sound(&mut layer, attacker.position.0);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Fully blocked attacks do not wear down the armor.
+        if victim.state.config(&configs).armor_durability
+            && damage_before_armor > 0.0
+            && durability::wear_armor(
+                &mut victim.equipment,
+                victim.inventory.as_deref_mut(),
+                damage_before_armor,
+                &mut rng,
+            )
+        {
+            if let Ok(mut layer) = layers.get_mut(victim.layer.0) {
+                play_item_break_sound(&mut layer, victim.position.0);
+            }
         }
 
         damage_event_writer.send(DamageEvent {
             victim: victim_ent,
             attacker: Some(attacker_ent),
             damage,
+            damage_type: DamageType::EntityAttack,
+        });
+
+        hit_writer.send(CombatHitEvent {
+            attacker: attacker_ent,
+            victim: victim_ent,
+            damage,
+            critical: critical_hit,
+            sweep: false,
+            knockback,
+        });
+
+        // Every armor piece with thorns can hurt the attacker, but like with the invulnerability
+        // ticks only the highest damage counts. (java behavior)
+        if let Some(thorns_formula) = victim
+            .state
+            .config(&configs)
+            .enchantment_config
+            .thorns_formula
+            .clone()
+            .filter(|_| !harmless_friendly_hit)
+        {
+            let armor = [
+                victim.equipment.head(),
+                victim.equipment.chest(),
+                victim.equipment.legs(),
+                victim.equipment.feet(),
+            ];
+            let mut thorns_wear = [0; 4];
+            let mut thorns_damage = 0.0_f32;
+
+            for (wear, stack) in thorns_wear.iter_mut().zip(armor) {
+                let level = stack
+                    .enchantments()
+                    .get(&Enchantment::Thorns)
+                    .copied()
+                    .unwrap_or(0);
+
+                if level == 0 {
+                    continue;
+                }
+
+                let damage = thorns_formula.call(level, rng.f32(), rng.f32());
+
+                if damage > 0.0 {
+                    *wear = durability::THORNS_WEAR;
+                    thorns_damage = thorns_damage.max(damage);
+                }
+            }
+
+            if victim.state.config(&configs).armor_durability
+                && durability::wear_armor_pieces(
+                    &mut victim.equipment,
+                    victim.inventory.as_deref_mut(),
+                    thorns_wear,
+                    &mut rng,
+                )
+            {
+                if let Ok(mut layer) = layers.get_mut(victim.layer.0) {
+                    play_item_break_sound(&mut layer, victim.position.0);
+                }
+            }
+
+            if thorns_damage > 0.0 {
+                damage_event_writer.send(DamageEvent {
+                    victim: attacker_ent,
+                    attacker: Some(victim_ent),
+                    damage: damage_after_defense(
+                        &attacker,
+                        &attacker_config,
+                        attacker_item_values,
+                        DamageType::Thorns,
+                        thorns_damage,
+                    ),
+                    damage_type: DamageType::Thorns,
+                });
+            }
+        }
+
+        let Some((sweep_damage, sweep_radius, sweep_reach, sweep_knockback)) = sweep else {
+            continue;
+        };
+
+        let attacker_position = attacker.position.0;
+        let attacker_layer = attacker.layer.0;
+        let attacker_team = attacker.team.copied();
+        // The attacker can not be borrowed while the targets are iterated.
+        let attacker_config = attacker.state.config(&configs).into_owned();
+        let victim_position = victim.position.0;
+
+        for mut target in query.iter_mut() {
+            if target.entity == attacker_ent
+                || target.entity == victim_ent
+                || !pvp_allowed(&target, &regions)
+                || is_spawn_protected(&target)
+            {
+                continue;
+            }
+
+            let offset = target.position.0 - victim_position;
+
+            if offset.x.abs() > sweep_radius
+                || offset.z.abs() > sweep_radius
+                || offset.y.abs() > SWEEP_VERTICAL_RANGE
+                || target.position.0.distance(attacker_position) > sweep_reach
+            {
+                continue;
+            }
+
+            target.state.last_got_hit = now;
+
+            let target_config = target.state.config(&configs);
+            let target_item_values = target_config.item_values(&item_value_tables);
+            let target_state = movement_state(&target.state, target.falling_state);
+
+            let mut damage = damage_after_defense(
+                &target,
+                &target_config,
+                target_item_values,
+                DamageType::EntityAttack,
+                sweep_damage,
+            );
+            damage *= target_config.damage_taken_multiplier.current(&target_state);
+
+            if team::are_friendly(&attacker_config, attacker_team.as_ref(), target.team) {
+                damage *= attacker_config.friendly_fire_damage_multiplier;
+                damage *= target_config.friendly_fire_damage_taken_multiplier;
+            }
+
+            let direction = (target.position.0 - attacker_position)
+                .normalize()
+                .as_vec3();
+            let knockback = Vec3::new(direction.x, 1.0, direction.z)
+                * sweep_knockback
+                * 20.0
+                * (1.0 - knockback_resistance(&target, &target_config, target_item_values));
+
+            apply_knockback(&mut target, knockback, &mut velocity_writer);
+
+            damage_event_writer.send(DamageEvent {
+                victim: target.entity,
+                attacker: Some(attacker_ent),
+                damage,
+                damage_type: DamageType::EntityAttack,
+            });
+
+            hit_writer.send(CombatHitEvent {
+                attacker: attacker_ent,
+                victim: target.entity,
+                damage,
+                critical: false,
+                sweep: true,
+                knockback,
+            });
+        }
+
+        if let Ok(mut layer) = layers.get_mut(attacker_layer) {
+            layer.play_sound(
+                Sound::EntityPlayerAttackSweep,
+                SoundCategory::Player,
+                attacker_position,
+                1.0,
+                1.0,
+            );
+        }
+    }
+}
+
+/// If the entity is in a region of its layer where it can attack and be attacked.
+fn pvp_allowed(entity: &CombatQueryItem, regions: &Query<&Regions>) -> bool {
+    pvp_allowed_at(entity.layer, entity.position.0, regions)
+}
+
+/// If an entity at the position can attack and be attacked, see [`pvp_allowed`].
+fn pvp_allowed_at(layer: &EntityLayerId, position: DVec3, regions: &Query<&Regions>) -> bool {
+    regions
+        .get(layer.0)
+        .map_or(true, |regions| regions.flags_at(position).pvp())
+}
+
+fn is_spawn_protected(entity: &CombatQueryItem) -> bool {
+    entity
+        .spawn_protection
+        .as_ref()
+        .is_some_and(|protection| protection.is_active())
+}
+
+fn update_region_configs(
+    mut query: Query<(&mut CombatState, &Position, &EntityLayerId)>,
+    regions: Query<&Regions>,
+) {
+    for (mut state, position, layer) in query.iter_mut() {
+        let region_config = regions
+            .get(layer.0)
+            .ok()
+            .and_then(|regions| regions.flags_at(position.0).combat_config);
+
+        // Only write on changes to keep the change detection of the state useful.
+        if state.region_config != region_config {
+            state.region_config = region_config;
+        }
+    }
+}
+
+/// Keeps the [`EnchantmentProtection`] of the entities in sync with the protection formula of
+/// their combat config.
+fn sync_enchantment_protection(
+    mut commands: Commands,
+    query: Query<(Entity, Ref<CombatState>, Has<EnchantmentProtection>)>,
+    configs: Res<CombatConfigs>,
+) {
+    for (entity, state, has_protection) in query.iter() {
+        if has_protection && !state.is_changed() && !configs.is_changed() {
+            continue;
+        }
+
+        let protection = match state.config(&configs).protection_formula.clone() {
+            Some(formula) => EnchantmentProtection::new(move |damage, protection_factor| {
+                formula.call(damage, protection_factor)
+            }),
+            None => EnchantmentProtection::none(),
+        };
+
+        commands.entity(entity).insert(protection);
+    }
+}
+
+/// The movement state of the entity that decides which [`PlayerStateDependantValue`] is used.
+fn movement_state(state: &CombatState, falling_state: &FallingState) -> PlayerMovementState {
+    match (state.sprinting, state.sneaking, falling_state.falling) {
+        (true, _, _) => PlayerMovementState::Sprinting,
+        (_, true, _) => PlayerMovementState::Sneaking,
+        (_, _, true) => PlayerMovementState::InAir,
+        _ => PlayerMovementState::None,
+    }
+}
+
+/// Reduces the damage by the armor and the protection enchantments of the victim.
+fn damage_after_defense(
+    victim: &CombatQueryItem,
+    config: &PlayerCombatConfig,
+    item_values: &ItemValueTable,
+    damage_type: DamageType,
+    damage: f32,
+) -> f32 {
+    let armor_points = victim.equipment.armor_points(item_values)
+        + entity_attribute_bonus(&victim.attributes, EntityAttribute::GenericArmor) as f32;
+    let armor_toughness = victim.equipment.armor_toughness(item_values)
+        + entity_attribute_bonus(&victim.attributes, EntityAttribute::GenericArmorToughness) as f32;
+
+    let mut damage = config.armor_formula.call(
+        damage,
+        armor_points * config.armor_points_multiplier,
+        armor_toughness * config.armor_toughness_multiplier,
+    );
+
+    if let Some(protection_formula) = &config.protection_formula {
+        let protection_factor = victim.equipment.protection_factor(damage_type);
+
+        damage = protection_formula.call(damage, protection_factor as f32);
+    }
+
+    damage
+}
+
+/// The knockback resistance of the victim (armor and attributes).
+fn knockback_resistance(
+    victim: &CombatQueryItem,
+    config: &PlayerCombatConfig,
+    item_values: &ItemValueTable,
+) -> f32 {
+    (victim.equipment.knockback_resistance(item_values)
+        + entity_attribute_bonus(
+            &victim.attributes,
+            EntityAttribute::GenericKnockbackResistance,
+        ) as f32)
+        * config.armor_knockback_resistance_multiplier
+}
+
+fn apply_knockback(
+    entity: &mut CombatQueryItem,
+    knockback: Vec3,
+    velocity_writer: &mut EventWriter<VelocityChangedEvent>,
+) {
+    if let Some(client) = entity.client.as_mut() {
+        client.set_velocity(knockback);
+        velocity_writer.send(VelocityChangedEvent {
+            client: entity.entity,
+            velocity: knockback,
         });
+    } else {
+        entity.velocity.0 += knockback;
     }
 }
 
-// TODO: new combat system is has not been tested i think
+/// If `other` is in front of the entity at `position` looking in the direction of `look`.
+fn is_in_front(look: &Look, position: DVec3, other: DVec3) -> bool {
+    let yaw = look.yaw.to_radians();
+    let offset = other - position;
+
+    -yaw.sin() as f64 * offset.x + yaw.cos() as f64 * offset.z > 0.0
+}
+
+/// The pitch is only cosmetic, so it does not use the [`GameRng`] of the combat.
+fn play_item_break_sound(layer: &mut ChunkLayer, position: DVec3) {
+    layer.play_sound(
+        Sound::EntityItemBreak,
+        SoundCategory::Player,
+        position,
+        0.8,
+        0.8 + rand::random::<f32>() * 0.4,
+    );
+}
 
 // If the player changes their hotbar slot, update the last attack time,
 // this is the vanilla behavior.
 fn update_last_attack_on_item_switch(
     mut query: Query<CombatQuery>,
     mut events: EventReader<UpdateSelectedSlotEvent>,
+    item_value_tables: Res<ItemValueTables>,
+    configs: Res<CombatConfigs>,
 ) {
     for event in events.read() {
         if let Ok(mut combat_query) = query.get_mut(event.client) {
             combat_query.state.last_attack = Instant::now();
 
-            if let Some(cooldown_multiplier) =
-                &combat_query.state.combat_config.attack_cooldown_multiplier
-            {
+            let config = combat_query.state.config(&configs);
+
+            if let Some(cooldown_multiplier) = config.attack_cooldown_multiplier {
                 if let (Some(held_item), Some(inventory)) =
-                    (combat_query.held_item, combat_query.inventory)
+                    (combat_query.held_item, combat_query.inventory.as_deref())
                 {
                     let held_item = inventory.slot(held_item.slot());
-                    let attack_speed = held_item.item.attack_speed() * cooldown_multiplier;
+                    let item_values = config.item_values(&item_value_tables);
+                    let attack_speed = held_item.attack_speed(item_values) * cooldown_multiplier;
 
                     combat_query
                         .attributes
@@ -611,17 +1583,18 @@ fn update_last_attack_on_item_switch(
     }
 
     for mut state in query.iter_mut() {
-        if let (Some(held_item), Some(inventory)) = (state.held_item, state.inventory) {
+        if let (Some(held_item), Some(inventory)) = (state.held_item, state.inventory.as_deref()) {
             let held_item_slot = held_item.slot();
 
             if inventory.changed & (1 << held_item_slot) != 0 {
                 state.state.last_attack = Instant::now();
 
-                if let Some(cooldown_multiplier) =
-                    &state.state.combat_config.attack_cooldown_multiplier
-                {
+                let config = state.state.config(&configs);
+
+                if let Some(cooldown_multiplier) = config.attack_cooldown_multiplier {
                     let held_item = inventory.slot(held_item.slot());
-                    let attack_speed = held_item.item.attack_speed() * cooldown_multiplier;
+                    let item_values = config.item_values(&item_value_tables);
+                    let attack_speed = held_item.attack_speed(item_values) * cooldown_multiplier;
 
                     state
                         .attributes
@@ -631,3 +1604,63 @@ fn update_last_attack_on_item_switch(
         }
     }
 }
+
+/// The off hand slot of the player inventory.
+const OFF_HAND_SLOT: u16 = 45;
+
+// Players block while they use an item that can block and stop when they release it,
+// swing or switch the held item.
+fn update_blocking(
+    mut query: Query<CombatQuery>,
+    mut interact_item_events: EventReader<InteractItemEvent>,
+    mut hand_swing_events: EventReader<HandSwingEvent>,
+    mut slot_events: EventReader<UpdateSelectedSlotEvent>,
+    mut packets: EventReader<PacketEvent>,
+    configs: Res<CombatConfigs>,
+) {
+    for &InteractItemEvent { client, hand, .. } in interact_item_events.read() {
+        let Ok(mut player) = query.get_mut(client) else {
+            continue;
+        };
+
+        let config = player.state.config(&configs);
+        let Some(blocking) = &config.blocking else {
+            continue;
+        };
+
+        let item = match (hand, player.held_item, player.inventory.as_deref()) {
+            (Hand::Main, Some(held_item), Some(inventory)) => inventory.slot(held_item.slot()).item,
+            (Hand::Off, _, Some(inventory)) => inventory.slot(OFF_HAND_SLOT).item,
+            (Hand::Main, _, _) => player.equipment.main_hand().item,
+            (Hand::Off, _, _) => player.equipment.off_hand().item,
+        };
+
+        if blocking.can_block_with(item) {
+            player.state.blocking = true;
+        }
+    }
+
+    for event in hand_swing_events.read() {
+        if let Ok(mut player) = query.get_mut(event.client) {
+            player.state.blocking = false;
+        }
+    }
+
+    for event in slot_events.read() {
+        if let Ok(mut player) = query.get_mut(event.client) {
+            player.state.blocking = false;
+        }
+    }
+
+    for packet in packets.read() {
+        let Some(action) = packet.decode::<PlayerActionC2s>() else {
+            continue;
+        };
+
+        if matches!(action.action, PlayerAction::ReleaseUseItem) {
+            if let Ok(mut player) = query.get_mut(packet.client) {
+                player.state.blocking = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::time::{Duration, Instant};
+
+use utils::{
+    aaab::AabbExt,
+    damage::{DamageEvent, DamageType, StartBurningEvent, VelocityChangedEvent},
+    item_values::ItemValueTables,
+    region::Regions,
+    spawn_protection::{ProtectedAttacks, SpawnProtection},
+};
+use valence::{entity::Velocity, prelude::*};
+
+use crate::{
+    apply_knockback, damage_after_defense, is_in_front, knockback_resistance, movement_state,
+    pvp_allowed_at, team::are_friendly, CombatConfigs, CombatQuery,
+};
+
+/// A projectile that damages the first entity it hits (e.g. an arrow).
+///
+/// The projectile is moved by its [`Velocity`] (e.g. with the physics plugin) and despawned
+/// when it hits an entity or stops moving (e.g. stuck in a block).
+#[derive(Component)]
+pub struct Projectile {
+    /// The entity that shot the projectile, it can not be hit by its own projectile.
+    pub shooter: Option<Entity>,
+    /// The damage before armor.
+    pub damage: f32,
+    /// The knockback the hit entity takes.
+    pub knockback: f32,
+    /// The burn time and damage per second (e.g. flame arrows).
+    pub burn: Option<(Duration, f32)>,
+}
+
+/// An event that will be fired when a projectile hits an entity or stops moving.
+#[derive(Event)]
+pub struct ProjectileHitEvent {
+    pub projectile: Entity,
+    pub shooter: Option<Entity>,
+    /// The entity that was hit, `None` if the projectile stopped (e.g. in a block).
+    pub victim: Option<Entity>,
+    pub position: DVec3,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn projectile_system(
+    mut commands: Commands,
+    projectiles: Query<(Entity, &Projectile, &Position, &OldPosition, &Velocity)>,
+    mut victims: Query<(CombatQuery, &Hitbox), Without<Projectile>>,
+    mut damage_event_writer: EventWriter<DamageEvent>,
+    mut start_burn_event_writer: EventWriter<StartBurningEvent>,
+    mut hit_writer: EventWriter<ProjectileHitEvent>,
+    mut velocity_writer: EventWriter<VelocityChangedEvent>,
+    regions: Query<&Regions>,
+    item_value_tables: Res<ItemValueTables>,
+    configs: Res<CombatConfigs>,
+) {
+    for (projectile_ent, projectile, position, old_position, velocity) in projectiles.iter() {
+        if velocity.0.length_squared() < f32::EPSILON {
+            hit_writer.send(ProjectileHitEvent {
+                projectile: projectile_ent,
+                shooter: projectile.shooter,
+                victim: None,
+                position: position.0,
+            });
+            commands.entity(projectile_ent).despawn();
+            continue;
+        }
+
+        // Projectiles of shooters that can not attack (in a region without pvp or denied by the
+        // spawn protection) fly through all entities, like projectiles fly through the entities
+        // that can not be attacked.
+        let shooter_can_attack = projectile
+            .shooter
+            .and_then(|shooter| victims.get(shooter).ok())
+            .map_or(true, |(shooter, _)| {
+                pvp_allowed_at(shooter.layer, shooter.position.0, &regions)
+                    && !shooter.spawn_protection.is_some_and(|protection| {
+                        protection.is_active() && protection.attacks == ProtectedAttacks::Deny
+                    })
+            });
+
+        if !shooter_can_attack {
+            continue;
+        }
+
+        let start = old_position.get();
+        let end = position.0;
+
+        let Some((victim_ent, _)) = victims
+            .iter()
+            .filter(|(victim, _)| Some(victim.entity) != projectile.shooter)
+            .filter(|(victim, _)| pvp_allowed_at(victim.layer, victim.position.0, &regions))
+            .filter(|(victim, _)| {
+                !victim
+                    .spawn_protection
+                    .is_some_and(SpawnProtection::is_active)
+            })
+            .filter_map(|(victim, hitbox)| {
+                let entry = hitbox.get().segment_intersection(start, end)?;
+                Some((victim.entity, entry))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            continue;
+        };
+
+        // Only a projectile that hits ends the protection of the shooter.
+        if let Some(Ok((mut shooter, _))) =
+            projectile.shooter.map(|shooter| victims.get_mut(shooter))
+        {
+            if let Some(protection) = shooter
+                .spawn_protection
+                .as_mut()
+                .filter(|protection| protection.attacks == ProtectedAttacks::Cancel)
+            {
+                protection.cancel();
+            }
+        }
+
+        // The friendly fire rules of the shooter, if it still exists.
+        let shooter = projectile
+            .shooter
+            .and_then(|shooter| victims.get(shooter).ok())
+            .map(|(shooter, _)| {
+                (
+                    shooter.team.copied(),
+                    shooter.state.config(&configs).into_owned(),
+                )
+            });
+
+        let Ok((mut victim, _)) = victims.get_mut(victim_ent) else {
+            continue;
+        };
+
+        let victim_config = victim.state.config(&configs);
+        let victim_item_values = victim_config.item_values(&item_value_tables);
+        let victim_state = movement_state(&victim.state, victim.falling_state);
+
+        let mut damage = projectile.damage;
+        let mut knockback = velocity.0.normalize_or_zero();
+        knockback = Vec3::new(knockback.x, 1.0, knockback.z) * projectile.knockback * 20.0;
+
+        let block = victim_config.blocking.as_ref().filter(|blocking| {
+            victim.state.blocking
+                && (!blocking.front_only || is_in_front(victim.look, victim.position.0, start))
+        });
+
+        if let Some(block) = block {
+            damage *= block.damage_multiplier;
+            knockback *= block.knockback_multiplier;
+        }
+
+        damage = damage_after_defense(
+            &victim,
+            &victim_config,
+            victim_item_values,
+            DamageType::Projectile,
+            damage,
+        );
+        damage *= victim_config.damage_taken_multiplier.current(&victim_state);
+
+        if let Some((shooter_team, shooter_config)) = &shooter {
+            if are_friendly(shooter_config, shooter_team.as_ref(), victim.team) {
+                damage *= shooter_config.friendly_fire_damage_multiplier;
+                damage *= victim_config.friendly_fire_damage_taken_multiplier;
+            }
+        }
+
+        knockback *= 1.0 - knockback_resistance(&victim, &victim_config, victim_item_values);
+
+        let arrows_stick = victim_config.arrows_stick as i32;
+
+        if let Some(stuck_arrow_count) = victim.stuck_arrow_count.as_mut() {
+            if stuck_arrow_count.0 < arrows_stick {
+                stuck_arrow_count.0 += 1;
+            }
+        }
+
+        victim.state.last_got_hit = Instant::now();
+        apply_knockback(&mut victim, knockback, &mut velocity_writer);
+
+        if let Some((duration, damage_per_second)) = projectile.burn {
+            start_burn_event_writer.send(StartBurningEvent {
+                victim: victim_ent,
+                attacker: projectile.shooter,
+                duration,
+                damage_per_second,
+            });
+        }
+
+        damage_event_writer.send(DamageEvent {
+            victim: victim_ent,
+            attacker: projectile.shooter,
+            damage,
+            damage_type: DamageType::Projectile,
+        });
+
+        hit_writer.send(ProjectileHitEvent {
+            projectile: projectile_ent,
+            shooter: projectile.shooter,
+            victim: Some(victim_ent),
+            position: end,
+        });
+
+        commands.entity(projectile_ent).despawn();
+    }
+}
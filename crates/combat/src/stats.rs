@@ -0,0 +1,339 @@
+use std::{
+    fmt,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use utils::damage::{DamageTakenEvent, DeathEvent};
+use valence::prelude::*;
+
+use crate::{
+    combo::{update_combo_counter, ComboCounter},
+    projectile::{Projectile, ProjectileHitEvent},
+    CombatHitEvent, CombatState,
+};
+
+/// Collects the [`CombatStats`] of the players and exports the [`MatchSummary`] of every match.
+///
+/// This is not added by [`CombatPlugin`](crate::CombatPlugin), add it if you want the stats.
+pub struct CombatStatsPlugin;
+
+impl Plugin for CombatStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MatchEndEvent>()
+            .add_event::<MatchSummaryEvent>()
+            .add_systems(
+                Update,
+                (
+                    add_combat_stats,
+                    count_hits,
+                    count_combos.after(update_combo_counter),
+                    count_damage,
+                    count_deaths,
+                    count_arrows,
+                    end_match
+                        .after(count_hits)
+                        .after(count_combos)
+                        .after(count_damage)
+                        .after(count_deaths)
+                        .after(count_arrows),
+                    export_match_summaries.after(end_match),
+                ),
+            );
+    }
+}
+
+/// The combat stats of an entity, added to all entities with a [`CombatState`].
+#[derive(Component, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CombatStats {
+    pub hits_landed: u32,
+    pub hits_taken: u32,
+    pub damage_dealt: f32,
+    pub damage_received: f32,
+    pub critical_hits: u32,
+    /// The most hits without being hit back (see [`ComboCounter`]).
+    pub longest_combo: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub arrows_shot: u32,
+    pub arrows_hit: u32,
+}
+
+/// Send this event when a match ends to get a [`MatchSummaryEvent`] with the stats of the players.
+///
+/// The stats of the players are reset for the next match.
+#[derive(Event)]
+pub struct MatchEndEvent {
+    /// Identifies the match in the exported summaries (e.g. the arena and a counter).
+    pub match_id: String,
+    pub players: Vec<Entity>,
+}
+
+/// An event that will be fired after a [`MatchEndEvent`].
+#[derive(Event)]
+pub struct MatchSummaryEvent(pub MatchSummary);
+
+/// The stats of all players of a match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchSummary {
+    pub match_id: String,
+    /// The unix timestamp of the end of the match in seconds.
+    pub ended_at: u64,
+    pub players: Vec<PlayerStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub uuid: String,
+    pub username: String,
+    #[serde(flatten)]
+    pub stats: CombatStats,
+}
+
+const CSV_HEADER: &str = "match_id,ended_at,uuid,username,hits_landed,hits_taken,damage_dealt,\
+    damage_received,critical_hits,longest_combo,kills,deaths,arrows_shot,arrows_hit";
+
+impl MatchSummary {
+    /// Serialize the summary to a single line of JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("match summaries can always be serialized")
+    }
+
+    /// Serialize the summary to CSV rows (one per player) without a header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+
+        for player in &self.players {
+            let stats = &player.stats;
+
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                csv_field(&self.match_id),
+                self.ended_at,
+                player.uuid,
+                csv_field(&player.username),
+                stats.hits_landed,
+                stats.hits_taken,
+                stats.damage_dealt,
+                stats.damage_received,
+                stats.critical_hits,
+                stats.longest_combo,
+                stats.kills,
+                stats.deaths,
+                stats.arrows_shot,
+                stats.arrows_hit,
+            ));
+        }
+
+        csv
+    }
+
+    /// Append the summary to a file, see [`StatsFormat`].
+    pub fn export(&self, path: impl AsRef<Path>, format: StatsFormat) -> Result<(), StatsError> {
+        let path = path.as_ref();
+        let new_file = !path.exists();
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(StatsError::Io)?;
+
+        let content = match format {
+            StatsFormat::Json => format!("{}\n", self.to_json()),
+            StatsFormat::Csv if new_file => format!("{CSV_HEADER}\n{}", self.to_csv()),
+            StatsFormat::Csv => self.to_csv(),
+        };
+
+        file.write_all(content.as_bytes()).map_err(StatsError::Io)
+    }
+}
+
+/// Quotes a CSV field if needed.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// The file format of exported [`MatchSummary`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsFormat {
+    /// One JSON object per line (JSON Lines).
+    Json,
+    /// One row per player, the header is written when the file is created.
+    Csv,
+}
+
+#[derive(Debug)]
+pub enum StatsError {
+    Io(std::io::Error),
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatsError::Io(err) => write!(f, "failed to export combat stats: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for StatsError {}
+
+/// Insert this resource to append every [`MatchSummary`] to a file.
+#[derive(Resource)]
+pub struct StatsExporter {
+    pub path: PathBuf,
+    pub format: StatsFormat,
+}
+
+impl StatsExporter {
+    pub fn new(path: impl Into<PathBuf>, format: StatsFormat) -> Self {
+        Self {
+            path: path.into(),
+            format,
+        }
+    }
+}
+
+fn add_combat_stats(
+    mut commands: Commands,
+    query: Query<Entity, (With<CombatState>, Without<CombatStats>)>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(CombatStats::default());
+    }
+}
+
+fn count_hits(mut hit_events: EventReader<CombatHitEvent>, mut query: Query<&mut CombatStats>) {
+    for event in hit_events.read() {
+        if let Ok(mut stats) = query.get_mut(event.attacker) {
+            stats.hits_landed += 1;
+            stats.critical_hits += u32::from(event.critical);
+        }
+
+        if let Ok(mut stats) = query.get_mut(event.victim) {
+            stats.hits_taken += 1;
+        }
+    }
+}
+
+fn count_combos(mut query: Query<(&ComboCounter, &mut CombatStats), Changed<ComboCounter>>) {
+    for (counter, mut stats) in query.iter_mut() {
+        if counter.combo > stats.longest_combo {
+            stats.longest_combo = counter.combo;
+        }
+    }
+}
+
+fn count_damage(
+    mut damage_events: EventReader<DamageTakenEvent>,
+    mut query: Query<&mut CombatStats>,
+) {
+    for event in damage_events.read() {
+        if let Some(mut stats) = event
+            .attacker
+            .and_then(|attacker| query.get_mut(attacker).ok())
+        {
+            stats.damage_dealt += event.damage;
+        }
+
+        if let Ok(mut stats) = query.get_mut(event.victim) {
+            stats.damage_received += event.damage;
+        }
+    }
+}
+
+fn count_deaths(mut death_events: EventReader<DeathEvent>, mut query: Query<&mut CombatStats>) {
+    for event in death_events.read() {
+        if let Some(mut stats) = event
+            .attacker
+            .and_then(|attacker| query.get_mut(attacker).ok())
+        {
+            stats.kills += 1;
+        }
+
+        if let Ok(mut stats) = query.get_mut(event.victim) {
+            stats.deaths += 1;
+        }
+    }
+}
+
+fn count_arrows(
+    projectiles: Query<&Projectile, Added<Projectile>>,
+    mut projectile_hit_events: EventReader<ProjectileHitEvent>,
+    mut query: Query<&mut CombatStats>,
+) {
+    for projectile in projectiles.iter() {
+        if let Some(mut stats) = projectile
+            .shooter
+            .and_then(|shooter| query.get_mut(shooter).ok())
+        {
+            stats.arrows_shot += 1;
+        }
+    }
+
+    for event in projectile_hit_events.read() {
+        if event.victim.is_none() {
+            continue;
+        }
+
+        if let Some(mut stats) = event
+            .shooter
+            .and_then(|shooter| query.get_mut(shooter).ok())
+        {
+            stats.arrows_hit += 1;
+        }
+    }
+}
+
+fn end_match(
+    mut match_end_events: EventReader<MatchEndEvent>,
+    mut summary_writer: EventWriter<MatchSummaryEvent>,
+    mut query: Query<(&mut CombatStats, &UniqueId, Option<&Username>)>,
+) {
+    for event in match_end_events.read() {
+        let ended_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+
+        let players = event
+            .players
+            .iter()
+            .filter_map(|player| query.get_mut(*player).ok())
+            .map(|(mut stats, uuid, username)| PlayerStats {
+                uuid: uuid.0.to_string(),
+                username: username
+                    .map(|username| username.0.clone())
+                    .unwrap_or_default(),
+                stats: std::mem::take(&mut *stats),
+            })
+            .collect();
+
+        summary_writer.send(MatchSummaryEvent(MatchSummary {
+            match_id: event.match_id.clone(),
+            ended_at,
+            players,
+        }));
+    }
+}
+
+fn export_match_summaries(
+    mut summary_events: EventReader<MatchSummaryEvent>,
+    exporter: Option<Res<StatsExporter>>,
+) {
+    let Some(exporter) = exporter else {
+        return;
+    };
+
+    for MatchSummaryEvent(summary) in summary_events.read() {
+        if let Err(err) = summary.export(&exporter.path, exporter.format) {
+            tracing::warn!("{}: {err}", exporter.path.display());
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use valence::{
+    prelude::*,
+    protocol::{
+        packets::play::{
+            team_s2c::{CollisionRule, Mode, NameTagVisibility, TeamColor, TeamFlags},
+            TeamS2c,
+        },
+        WritePacket,
+    },
+};
+
+use crate::PlayerCombatConfig;
+
+/// A Team component that is attached to entities that are part of a team.
+///
+/// The entity is added to the scoreboard team of the [`TeamConfig`] with the same id in [`Teams`].
+#[derive(Component, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Team(pub u16);
+
+/// If two entities are friendly (the same team or an allied team in
+/// [`PlayerCombatConfig::friendly_teams`] of the attacker).
+pub fn are_friendly(
+    config: &PlayerCombatConfig,
+    team: Option<&Team>,
+    other: Option<&Team>,
+) -> bool {
+    match (team, other) {
+        (Some(team), Some(other)) => team == other || config.friendly_teams.contains(&other.0),
+        _ => false,
+    }
+}
+
+/// How a team is shown to the clients (a minecraft scoreboard team).
+#[derive(Clone)]
+pub struct TeamConfig {
+    /// The unique name of the scoreboard team (at most 16 characters).
+    pub name: String,
+    pub display_name: Text,
+    /// The color of the name tags of the members.
+    pub color: TeamColor,
+    /// Shown in front of the names of the members.
+    pub prefix: Text,
+    /// Shown after the names of the members.
+    pub suffix: Text,
+    pub name_tag_visibility: NameTagVisibility,
+    pub collision_rule: CollisionRule,
+    /// If members see invisible members of their team as translucent.
+    pub see_invisible_teammates: bool,
+    /// This is only sent to the clients, the damage between teammates is configured with
+    /// [`PlayerCombatConfig::friendly_fire_damage_multiplier`].
+    pub friendly_fire: bool,
+}
+
+impl TeamConfig {
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+
+        Self {
+            display_name: name.clone().into(),
+            name,
+            color: TeamColor::Reset,
+            prefix: Text::default(),
+            suffix: Text::default(),
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+            see_invisible_teammates: true,
+            friendly_fire: false,
+        }
+    }
+
+    fn flags(&self) -> TeamFlags {
+        TeamFlags::new()
+            .with_friendly_fire(self.friendly_fire)
+            .with_see_invisible_teammates(self.see_invisible_teammates)
+    }
+}
+
+/// The teams that are sent to the clients as scoreboard teams.
+#[derive(Resource, Default)]
+pub struct Teams {
+    teams: HashMap<u16, TeamConfig>,
+    /// Maps the entities with a [`Team`] to their team and scoreboard entry.
+    members: HashMap<Entity, (u16, String)>,
+    /// The teams the clients know about.
+    created: HashSet<u16>,
+    updated: HashSet<u16>,
+    removed: HashMap<u16, String>,
+}
+
+impl Teams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a team.
+    pub fn insert(&mut self, id: u16, config: TeamConfig) {
+        if let Some(old) = self.teams.insert(id, config) {
+            if self.created.contains(&id) {
+                // The scoreboard name can not be changed, so the team has to be recreated.
+                if old.name != self.teams[&id].name {
+                    self.removed.insert(id, old.name);
+                    self.created.remove(&id);
+                } else {
+                    self.updated.insert(id);
+                }
+            }
+        }
+    }
+
+    /// Remove a team, the members keep their [`Team`] component.
+    pub fn remove(&mut self, id: u16) -> Option<TeamConfig> {
+        let config = self.teams.remove(&id)?;
+
+        if self.created.remove(&id) {
+            self.removed.insert(id, config.name.clone());
+        }
+
+        Some(config)
+    }
+
+    pub fn get(&self, id: u16) -> Option<&TeamConfig> {
+        self.teams.get(&id)
+    }
+
+    /// Get the team to change it, the changes are sent to the clients.
+    pub fn get_mut(&mut self, id: u16) -> Option<&mut TeamConfig> {
+        if self.created.contains(&id) {
+            self.updated.insert(id);
+        }
+
+        self.teams.get_mut(&id)
+    }
+
+    /// The entities in the team.
+    pub fn members(&self, id: u16) -> impl Iterator<Item = Entity> + '_ {
+        self.members
+            .iter()
+            .filter(move |(_, (team, _))| *team == id)
+            .map(|(entity, _)| *entity)
+    }
+
+    fn entries(&self, id: u16) -> Vec<&str> {
+        self.members
+            .values()
+            .filter(|(team, _)| *team == id)
+            .map(|(_, entry)| entry.as_str())
+            .collect()
+    }
+
+    fn create_packet(&self, id: u16) -> Option<TeamS2c> {
+        let config = self.teams.get(&id)?;
+
+        Some(TeamS2c {
+            team_name: &config.name,
+            mode: Mode::CreateTeam {
+                team_display_name: Cow::Borrowed(&config.display_name),
+                friendly_flags: config.flags(),
+                name_tag_visibility: config.name_tag_visibility,
+                collision_rule: config.collision_rule,
+                team_color: config.color,
+                team_prefix: Cow::Borrowed(&config.prefix),
+                team_suffix: Cow::Borrowed(&config.suffix),
+                entities: self.entries(id),
+            },
+        })
+    }
+
+    fn update_packet(&self, id: u16) -> Option<TeamS2c> {
+        let config = self.teams.get(&id)?;
+
+        Some(TeamS2c {
+            team_name: &config.name,
+            mode: Mode::UpdateTeamInfo {
+                team_display_name: Cow::Borrowed(&config.display_name),
+                friendly_flags: config.flags(),
+                name_tag_visibility: config.name_tag_visibility,
+                collision_rule: config.collision_rule,
+                team_color: config.color,
+                team_prefix: Cow::Borrowed(&config.prefix),
+                team_suffix: Cow::Borrowed(&config.suffix),
+            },
+        })
+    }
+}
+
+/// An event that will be fired after an entity joined a team.
+#[derive(Event)]
+pub struct TeamJoinEvent {
+    pub entity: Entity,
+    pub team: u16,
+}
+
+/// An event that will be fired after an entity left a team (the [`Team`] was changed or removed).
+#[derive(Event)]
+pub struct TeamLeaveEvent {
+    pub entity: Entity,
+    pub team: u16,
+}
+
+/// Sends the [`Teams`] to the clients, this is added by [`CombatPlugin`](crate::CombatPlugin).
+pub struct TeamPlugin;
+
+impl Plugin for TeamPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Teams>()
+            .add_event::<TeamJoinEvent>()
+            .add_event::<TeamLeaveEvent>()
+            .add_systems(Update, update_teams);
+    }
+}
+
+fn update_teams(
+    mut teams: ResMut<Teams>,
+    mut clients: Query<&mut Client>,
+    changed: Query<(Entity, &Team, Option<&Username>, &UniqueId), Changed<Team>>,
+    mut removed: RemovedComponents<Team>,
+    mut join_writer: EventWriter<TeamJoinEvent>,
+    mut leave_writer: EventWriter<TeamLeaveEvent>,
+) {
+    // New clients get the current teams, the changes of this tick are sent to all clients below.
+    for mut client in clients.iter_mut() {
+        if !client.is_added() {
+            continue;
+        }
+
+        for id in &teams.created {
+            if let Some(packet) = teams.create_packet(*id) {
+                client.write_packet(&packet);
+            }
+        }
+    }
+
+    let teams = &mut *teams;
+
+    for (_, name) in teams.removed.drain() {
+        for mut client in clients.iter_mut() {
+            client.write_packet(&TeamS2c {
+                team_name: &name,
+                mode: Mode::RemoveTeam,
+            });
+        }
+    }
+
+    for id in teams.updated.drain().collect::<Vec<_>>() {
+        if let Some(packet) = teams.update_packet(id) {
+            for mut client in clients.iter_mut() {
+                client.write_packet(&packet);
+            }
+        }
+    }
+
+    let mut left = Vec::new();
+
+    for entity in removed.read() {
+        if let Some((team, entry)) = teams.members.remove(&entity) {
+            left.push((entity, team, entry));
+        }
+    }
+
+    let mut joined = Vec::new();
+
+    for (entity, team, username, uuid) in changed.iter() {
+        let entry = match username {
+            Some(username) => username.0.clone(),
+            None => uuid.0.to_string(),
+        };
+
+        match teams.members.insert(entity, (team.0, entry.clone())) {
+            Some((old_team, _)) if old_team == team.0 => continue,
+            Some((old_team, old_entry)) => left.push((entity, old_team, old_entry)),
+            None => {}
+        }
+
+        joined.push((entity, team.0, entry));
+    }
+
+    for (entity, team, entry) in left {
+        if let Some(config) = teams
+            .teams
+            .get(&team)
+            .filter(|_| teams.created.contains(&team))
+        {
+            for mut client in clients.iter_mut() {
+                client.write_packet(&TeamS2c {
+                    team_name: &config.name,
+                    mode: Mode::RemoveEntities {
+                        entities: vec![&entry],
+                    },
+                });
+            }
+        }
+
+        leave_writer.send(TeamLeaveEvent { entity, team });
+    }
+
+    for (entity, team, entry) in joined {
+        if let Some(config) = teams
+            .teams
+            .get(&team)
+            .filter(|_| teams.created.contains(&team))
+        {
+            for mut client in clients.iter_mut() {
+                client.write_packet(&TeamS2c {
+                    team_name: &config.name,
+                    mode: Mode::AddEntities {
+                        entities: vec![&entry],
+                    },
+                });
+            }
+        }
+
+        join_writer.send(TeamJoinEvent { entity, team });
+    }
+
+    // Teams are created last, so the packet already contains the members that joined this tick.
+    let new_teams: Vec<u16> = teams
+        .teams
+        .keys()
+        .filter(|id| !teams.created.contains(id))
+        .copied()
+        .collect();
+
+    for id in new_teams {
+        if let Some(packet) = teams.create_packet(id) {
+            for mut client in clients.iter_mut() {
+                client.write_packet(&packet);
+            }
+        }
+
+        teams.created.insert(id);
+    }
+}
@@ -0,0 +1,180 @@
+//! The random rolls of the combat (critical hits, thorns and unbreaking) only depend on the seed
+//! of the [`GameRng`], so two fights with the same seed have the same outcome.
+
+use std::time::{Duration, Instant};
+
+use bevy_time::TimePlugin;
+use combat::{
+    durability::item_damage, AttackEvent, AttackWeapon, CombatConfigs, CombatHitEvent,
+    CombatPlugin, CombatState, PlayerCombatConfig, PlayerStateDependantValue,
+};
+use fall_damage::FallingState;
+use utils::{
+    damage::{DamagePlugin, TakesDamage},
+    enchantments::Enchantment,
+    random::GameRng,
+};
+use valence::{
+    entity::{living::Health, player::PlayerEntityBundle, EntityStatuses},
+    nbt::{compound, List},
+    prelude::*,
+    testing::ScenarioSingleClient,
+};
+
+const HITS: usize = 40;
+const HEALTH: f32 = 100.0;
+
+#[derive(Resource, Default)]
+struct Hits(Vec<CombatHitEvent>);
+
+fn record_hits(mut events: EventReader<CombatHitEvent>, mut hits: ResMut<Hits>) {
+    hits.0.extend(events.read().copied());
+}
+
+/// The outcome of one hit.
+#[derive(Debug, PartialEq)]
+struct Roll {
+    critical: bool,
+    /// The thorns damage the attacker took.
+    thorns_damage: f32,
+    /// The durability the sword lost (unbreaking can prevent it).
+    sword_wear: i32,
+}
+
+fn enchanted(item: ItemKind, enchantment: Enchantment, level: i64) -> ItemStack {
+    let nbt = compound! {
+        "Enchantments" => List::Compound(vec![compound! {
+            "id" => enchantment.id(),
+            "lvl" => level,
+        }]),
+    };
+
+    ItemStack::new(item, 1, Some(nbt))
+}
+
+fn spawn_fighter(app: &mut App, layer: Entity, position: DVec3, equipment: Equipment) -> Entity {
+    app.world_mut()
+        .spawn(PlayerEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position(position),
+            ..Default::default()
+        })
+        .insert((
+            Health(HEALTH),
+            TakesDamage::default(),
+            CombatState::default(),
+            FallingState::default(),
+            EntityStatuses::default(),
+            equipment,
+        ))
+        .id()
+}
+
+/// An attacker with an unbreaking sword hits a victim with a thorns chestplate [`HITS`] times,
+/// critical hits have a random chance of 50%.
+fn fight(seed: u64) -> Vec<Roll> {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+
+    let config = PlayerCombatConfig {
+        random_critical_hit_chance: PlayerStateDependantValue::always(0.5),
+        ..PlayerCombatConfig::legacy_1_8()
+    };
+
+    app.add_plugins((TimePlugin, DamagePlugin, CombatPlugin))
+        .insert_resource(CombatConfigs::new(config))
+        .insert_resource(GameRng::new(seed))
+        .init_resource::<Hits>()
+        .add_systems(PostUpdate, record_hits);
+
+    let mut attacker_equipment = Equipment::default();
+    attacker_equipment.set_main_hand(enchanted(
+        ItemKind::DiamondSword,
+        Enchantment::Unbreaking,
+        3,
+    ));
+    let mut victim_equipment = Equipment::default();
+    victim_equipment.set_chest(enchanted(
+        ItemKind::DiamondChestplate,
+        Enchantment::Thorns,
+        3,
+    ));
+
+    let attacker = spawn_fighter(
+        &mut app,
+        layer,
+        DVec3::new(0.0, 64.0, 0.0),
+        attacker_equipment,
+    );
+    let victim = spawn_fighter(
+        &mut app,
+        layer,
+        DVec3::new(0.0, 64.0, 2.0),
+        victim_equipment,
+    );
+    app.update();
+
+    let sword_damage =
+        |app: &App| item_damage(app.world().get::<Equipment>(attacker).unwrap().main_hand());
+
+    (0..HITS)
+        .map(|_| {
+            let now = Instant::now();
+            let mut state = app.world_mut().get_mut::<CombatState>(attacker).unwrap();
+            state.last_hit = now - Duration::from_secs(5);
+            state.last_attack = now - Duration::from_secs(5);
+
+            app.world_mut().get_mut::<Health>(attacker).unwrap().0 = HEALTH;
+            app.world_mut().get_mut::<Health>(victim).unwrap().0 = HEALTH;
+            app.world_mut().resource_mut::<Hits>().0.clear();
+
+            let wear_before = sword_damage(&app);
+
+            app.world_mut().send_event(AttackEvent {
+                attacker,
+                victim,
+                weapon: AttackWeapon::HeldItem,
+            });
+
+            // The damage is applied in the same or in the next update.
+            app.update();
+            app.update();
+
+            let hits = std::mem::take(&mut app.world_mut().resource_mut::<Hits>().0);
+            assert_eq!(hits.len(), 1, "expected exactly one hit, got {hits:?}");
+
+            Roll {
+                critical: hits[0].critical,
+                thorns_damage: HEALTH - app.world().get::<Health>(attacker).unwrap().0,
+                sword_wear: sword_damage(&app) - wear_before,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn same_seed_same_rolls() {
+    let first = fight(42);
+    let second = fight(42);
+
+    assert_eq!(first, second);
+
+    // The rolls are actually random, otherwise the comparison would prove nothing.
+    assert!(first.iter().any(|roll| roll.critical));
+    assert!(first.iter().any(|roll| !roll.critical));
+    assert!(first.iter().any(|roll| roll.thorns_damage > 0.0));
+    assert!(first.iter().any(|roll| roll.thorns_damage == 0.0));
+    assert!(first.iter().any(|roll| roll.sword_wear > 0));
+    assert!(first.iter().any(|roll| roll.sword_wear == 0));
+}
+
+#[test]
+fn reseeding_restarts_the_rolls() {
+    let mut rng = GameRng::new(7);
+    let rolls: Vec<f32> = (0..HITS).map(|_| rng.f32()).collect();
+
+    rng.reseed(7);
+    let reseeded: Vec<f32> = (0..HITS).map(|_| rng.f32()).collect();
+
+    assert_eq!(rolls, reseeded);
+    assert_eq!(rng.seed(), 7);
+}
@@ -0,0 +1,326 @@
+//! Scripted duels between two NPCs for every combat preset, checking the damage and knockback of
+//! each hit.
+
+use std::time::{Duration, Instant};
+
+use bevy_time::TimePlugin;
+use combat::{
+    AttackEvent, AttackWeapon, CombatConfigs, CombatHitEvent, CombatPlugin, CombatState,
+    PlayerCombatConfig,
+};
+use fall_damage::FallingState;
+use utils::{
+    damage::{DamagePlugin, TakesDamage},
+    random::GameRng,
+};
+use valence::{
+    entity::{living::Health, player::PlayerEntityBundle, EntityStatuses, Velocity},
+    prelude::*,
+    testing::ScenarioSingleClient,
+};
+
+/// The health of the victim, high enough to survive every script.
+const VICTIM_HEALTH: f32 = 100.0;
+
+#[derive(Resource, Default)]
+struct Hits(Vec<CombatHitEvent>);
+
+fn record_hits(mut events: EventReader<CombatHitEvent>, mut hits: ResMut<Hits>) {
+    hits.0.extend(events.read().copied());
+}
+
+/// An attacker with a diamond sword and a victim two blocks in front of it (in `+z`).
+struct Duel {
+    app: App,
+    attacker: Entity,
+    victim: Entity,
+}
+
+impl Duel {
+    fn new(config: PlayerCombatConfig) -> Self {
+        let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+
+        app.add_plugins((TimePlugin, DamagePlugin, CombatPlugin))
+            .insert_resource(CombatConfigs::new(config))
+            .insert_resource(GameRng::new(0))
+            .init_resource::<Hits>()
+            .add_systems(PostUpdate, record_hits);
+
+        let mut attacker_equipment = Equipment::default();
+        attacker_equipment.set_main_hand(ItemStack::new(ItemKind::DiamondSword, 1, None));
+
+        let attacker = spawn_duelist(&mut app, layer, DVec3::new(0.0, 64.0, 0.0), 0.0);
+        let victim = spawn_duelist(&mut app, layer, DVec3::new(0.0, 64.0, 2.0), 0.0);
+
+        app.world_mut()
+            .entity_mut(attacker)
+            .insert(attacker_equipment);
+        app.update();
+
+        Self {
+            app,
+            attacker,
+            victim,
+        }
+    }
+
+    fn state(&mut self, entity: Entity) -> Mut<CombatState> {
+        self.app.world_mut().get_mut::<CombatState>(entity).unwrap()
+    }
+
+    fn set_falling(&mut self, entity: Entity, falling: bool) {
+        let mut falling_state = self
+            .app
+            .world_mut()
+            .get_mut::<FallingState>(entity)
+            .unwrap();
+        falling_state.falling = falling;
+    }
+
+    fn set_victim_yaw(&mut self, yaw: f32) {
+        let victim = self.victim;
+        self.app.world_mut().get_mut::<Look>(victim).unwrap().yaw = yaw;
+    }
+
+    fn equip_victim(&mut self, chest: ItemKind) {
+        let victim = self.victim;
+        let mut equipment = self.app.world_mut().get_mut::<Equipment>(victim).unwrap();
+        equipment.set_chest(ItemStack::new(chest, 1, None));
+    }
+
+    fn health(&self) -> f32 {
+        self.app.world().get::<Health>(self.victim).unwrap().0
+    }
+
+    /// A fully charged attack.
+    fn hit(&mut self) -> CombatHitEvent {
+        self.hit_after(Duration::from_secs(5))
+    }
+
+    /// An attack `since_last_attack` after the previous attack of the attacker (the charge of the
+    /// attack cooldown), the hit cooldown is always over.
+    fn hit_after(&mut self, since_last_attack: Duration) -> CombatHitEvent {
+        let now = Instant::now();
+        let (attacker, victim) = (self.attacker, self.victim);
+
+        let mut state = self.state(attacker);
+        state.last_hit = now - Duration::from_secs(5);
+        state.last_attack = now - since_last_attack;
+
+        self.app.world_mut().get_mut::<Velocity>(victim).unwrap().0 = Vec3::ZERO;
+        self.app.world_mut().resource_mut::<Hits>().0.clear();
+
+        let health = self.health();
+
+        self.app.world_mut().send_event(AttackEvent {
+            attacker,
+            victim,
+            weapon: AttackWeapon::HeldItem,
+        });
+
+        // The damage is applied in the same or in the next update.
+        self.app.update();
+        self.app.update();
+
+        let hits = std::mem::take(&mut self.app.world_mut().resource_mut::<Hits>().0);
+        assert_eq!(hits.len(), 1, "expected exactly one hit, got {hits:?}");
+
+        let hit = hits[0];
+        assert_close(health - self.health(), hit.damage);
+        assert_vec_close(
+            self.app.world().get::<Velocity>(victim).unwrap().0,
+            hit.knockback,
+        );
+
+        hit
+    }
+}
+
+fn spawn_duelist(app: &mut App, layer: Entity, position: DVec3, yaw: f32) -> Entity {
+    app.world_mut()
+        .spawn(PlayerEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position(position),
+            look: Look::new(yaw, 0.0),
+            ..Default::default()
+        })
+        .insert((
+            Health(VICTIM_HEALTH),
+            TakesDamage::default(),
+            CombatState::default(),
+            FallingState::default(),
+            EntityStatuses::default(),
+            Equipment::default(),
+        ))
+        .id()
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-4,
+        "expected {expected}, got {actual}"
+    );
+}
+
+fn assert_vec_close(actual: Vec3, expected: Vec3) {
+    assert!(
+        actual.abs_diff_eq(expected, 1e-4),
+        "expected {expected}, got {actual}"
+    );
+}
+
+/// The knockback of a hit in `+z` with the horizontal and vertical knockback of the preset (in
+/// blocks per tick).
+fn knockback(horizontal: f32, vertical: f32) -> Vec3 {
+    Vec3::new(0.0, vertical * 20.0, horizontal * 20.0)
+}
+
+/// 7 damage of a diamond sword against the 8 armor points of a diamond chestplate, 1.8 ignores the
+/// armor toughness.
+const LEGACY_DAMAGE_AGAINST_DIAMOND_CHESTPLATE: f32 = 7.0 * (1.0 - 8.0 / 25.0);
+
+/// 7 damage of a diamond sword against 8 armor points and 2 toughness of a diamond chestplate, the
+/// toughness lowers the armor points to `8 - 4 * 7 / (2 + 8) = 5.2`.
+const MODERN_DAMAGE_AGAINST_DIAMOND_CHESTPLATE: f32 = 7.0 * (1.0 - 5.2 / 25.0);
+
+#[test]
+fn legacy_1_8_duel() {
+    let mut duel = Duel::new(PlayerCombatConfig::legacy_1_8());
+    let attacker = duel.attacker;
+
+    let hit = duel.hit();
+    assert_close(hit.damage, 7.0);
+    assert!(!hit.critical);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+
+    // No attack cooldown, a hit right after the previous one deals the full damage.
+    let hit = duel.hit_after(Duration::ZERO);
+    assert_close(hit.damage, 7.0);
+
+    duel.state(attacker).sprinting = true;
+    let hit = duel.hit();
+    assert_close(hit.damage, 7.0);
+    assert_vec_close(hit.knockback, knockback(0.8, 0.42));
+
+    // Critical hits while sprinting, with the sprint knockback.
+    duel.set_falling(attacker, true);
+    let hit = duel.hit();
+    assert!(hit.critical);
+    assert_close(hit.damage, 10.5);
+    assert_vec_close(hit.knockback, knockback(0.8, 0.42));
+
+    duel.state(attacker).sprinting = false;
+    let hit = duel.hit();
+    assert!(hit.critical);
+    assert_close(hit.damage, 10.5);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+    duel.set_falling(attacker, false);
+
+    // A sword block halves the damage, the knockback stays the same.
+    let victim = duel.victim;
+    duel.state(victim).blocking = true;
+    let hit = duel.hit();
+    assert_close(hit.damage, 3.5);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+    duel.state(victim).blocking = false;
+
+    duel.equip_victim(ItemKind::DiamondChestplate);
+    let hit = duel.hit();
+    assert_close(hit.damage, LEGACY_DAMAGE_AGAINST_DIAMOND_CHESTPLATE);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+}
+
+#[test]
+fn modern_duel() {
+    let mut duel = Duel::new(PlayerCombatConfig::modern());
+    let (attacker, victim) = (duel.attacker, duel.victim);
+
+    let hit = duel.hit();
+    assert_close(hit.damage, 7.0);
+    assert!(!hit.critical);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+
+    duel.state(attacker).sprinting = true;
+    let hit = duel.hit();
+    assert_close(hit.damage, 7.0);
+    assert_vec_close(hit.knockback, knockback(0.8, 0.42));
+
+    // 5 ticks of the 12.5 ticks cooldown of a sword: about 0.44 attack strength, so
+    // 0.2 + 0.44² * 0.8 of the damage and no sprint knockback.
+    let hit = duel.hit_after(Duration::from_millis(250));
+    assert!(
+        (2.45..2.7).contains(&hit.damage),
+        "uncharged damage {}",
+        hit.damage
+    );
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+
+    // No critical hits while sprinting.
+    duel.set_falling(attacker, true);
+    let hit = duel.hit();
+    assert!(!hit.critical);
+    assert_close(hit.damage, 7.0);
+    assert_vec_close(hit.knockback, knockback(0.8, 0.42));
+
+    duel.state(attacker).sprinting = false;
+    let hit = duel.hit();
+    assert!(hit.critical);
+    assert_close(hit.damage, 10.5);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+    duel.set_falling(attacker, false);
+
+    // A shield blocks everything from the front (the victim looks at the attacker in -z)...
+    duel.set_victim_yaw(180.0);
+    duel.state(victim).blocking = true;
+    let hit = duel.hit();
+    assert_close(hit.damage, 0.0);
+    assert_vec_close(hit.knockback, Vec3::ZERO);
+
+    // ...but nothing from behind.
+    duel.set_victim_yaw(0.0);
+    let hit = duel.hit();
+    assert_close(hit.damage, 7.0);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+    duel.state(victim).blocking = false;
+
+    duel.equip_victim(ItemKind::DiamondChestplate);
+    let hit = duel.hit();
+    assert_close(hit.damage, MODERN_DAMAGE_AGAINST_DIAMOND_CHESTPLATE);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+}
+
+#[test]
+fn old_combat_mechanics_duel() {
+    let mut duel = Duel::new(PlayerCombatConfig::old_combat_mechanics());
+    let (attacker, victim) = (duel.attacker, duel.victim);
+
+    let hit = duel.hit();
+    assert_close(hit.damage, 7.0);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+
+    // No attack cooldown.
+    let hit = duel.hit_after(Duration::ZERO);
+    assert_close(hit.damage, 7.0);
+
+    // Critical hits while sprinting like in 1.8.
+    duel.state(attacker).sprinting = true;
+    duel.set_falling(attacker, true);
+    let hit = duel.hit();
+    assert!(hit.critical);
+    assert_close(hit.damage, 10.5);
+    assert_vec_close(hit.knockback, knockback(0.8, 0.42));
+    duel.state(attacker).sprinting = false;
+    duel.set_falling(attacker, false);
+
+    // The shield halves the damage from every direction and keeps the knockback.
+    duel.state(victim).blocking = true;
+    let hit = duel.hit();
+    assert_close(hit.damage, 3.5);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+    duel.state(victim).blocking = false;
+
+    duel.equip_victim(ItemKind::DiamondChestplate);
+    let hit = duel.hit();
+    assert_close(hit.damage, LEGACY_DAMAGE_AGAINST_DIAMOND_CHESTPLATE);
+    assert_vec_close(hit.knockback, knockback(0.4, 0.36));
+}
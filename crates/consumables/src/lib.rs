@@ -0,0 +1,482 @@
+//! Items that apply effects when they are eaten, like golden apples.
+//!
+//! The client does not tell the server when it finished eating, so the server times the use of
+//! the item: a consumable is consumed when the player used it for [`Consumable::use_duration`]
+//! without releasing it or switching the item.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use bevy_ecs::query::QueryData;
+use utils::effects::{max_health, Absorption};
+use valence::{
+    entity::{
+        active_status_effects::{ActiveStatusEffect, ActiveStatusEffects},
+        attributes::EntityAttributes,
+        living::Health,
+        EntityStatus, EntityStatuses,
+    },
+    event_loop::PacketEvent,
+    interact_item::InteractItemEvent,
+    inventory::{HeldItem, UpdateSelectedSlotEvent},
+    nbt::Value,
+    prelude::*,
+    protocol::{
+        packets::play::{player_action_c2s::PlayerAction, CooldownUpdateS2c, PlayerActionC2s},
+        sound::SoundCategory,
+        Hand, Sound, VarInt, WritePacket,
+    },
+    status_effects::StatusEffect,
+};
+
+/// The slot of the off hand in the player inventory.
+const OFF_HAND_SLOT: u16 = 45;
+/// The time it takes to eat food (java behavior).
+const EAT_DURATION: Duration = Duration::from_millis(1600);
+/// The effect durations are sent to the client in ticks.
+const TICKS_PER_SECOND: f32 = 20.0;
+
+pub struct ConsumablesPlugin;
+
+impl Plugin for ConsumablesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Consumables>()
+            .add_event::<ConsumeEvent>()
+            .add_systems(
+                Update,
+                (
+                    add_consumable_state,
+                    start_consuming,
+                    cancel_consuming,
+                    finish_consuming,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The items that can be consumed, by default the golden apples.
+#[derive(Resource)]
+pub struct Consumables {
+    consumables: Vec<Consumable>,
+}
+
+impl Default for Consumables {
+    fn default() -> Self {
+        Self::new(vec![
+            Consumable::golden_apple(),
+            Consumable::enchanted_golden_apple(),
+        ])
+    }
+}
+
+impl Consumables {
+    pub fn new(consumables: Vec<Consumable>) -> Self {
+        Self { consumables }
+    }
+
+    /// Add a consumable or replace the consumable with the same name.
+    ///
+    /// The consumables added last are checked first, so custom items (e.g. a golden head) take
+    /// precedence over consumables of the same item kind.
+    pub fn insert(&mut self, consumable: Consumable) {
+        self.remove(&consumable.name);
+        self.consumables.push(consumable);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Consumable> {
+        let index = self
+            .consumables
+            .iter()
+            .position(|consumable| consumable.name == name)?;
+
+        Some(self.consumables.remove(index))
+    }
+
+    /// The consumable of the item stack.
+    pub fn get(&self, stack: &ItemStack) -> Option<&Consumable> {
+        if stack.is_empty() {
+            return None;
+        }
+
+        self.consumables
+            .iter()
+            .rev()
+            .find(|consumable| consumable.item.matches(stack))
+    }
+
+    fn by_name(&self, name: &str) -> Option<&Consumable> {
+        self.consumables
+            .iter()
+            .find(|consumable| consumable.name == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Consumable {
+    /// Identifies the consumable (e.g. for the cooldowns).
+    pub name: String,
+    pub item: ItemMatcher,
+    /// How long the item has to be used, zero for items that are consumed instantly (e.g. items
+    /// that can not be eaten in vanilla).
+    pub use_duration: Duration,
+    /// The health that is restored instantly.
+    pub heal: f32,
+    pub effects: Vec<ConsumableEffect>,
+    /// How long the player has to wait before consuming this consumable again.
+    pub cooldown: Option<Duration>,
+}
+
+impl Consumable {
+    /// The golden apple (1.9+).
+    ///
+    /// https://minecraft.wiki/w/Golden_Apple
+    pub fn golden_apple() -> Self {
+        Self {
+            name: "golden_apple".to_owned(),
+            item: ItemMatcher::Kind(ItemKind::GoldenApple),
+            use_duration: EAT_DURATION,
+            heal: 0.0,
+            effects: vec![
+                ConsumableEffect::new(StatusEffect::Regeneration, 1, Duration::from_secs(5)),
+                ConsumableEffect::new(StatusEffect::Absorption, 0, Duration::from_secs(120)),
+            ],
+            cooldown: None,
+        }
+    }
+
+    /// The enchanted golden apple (1.9+).
+    ///
+    /// https://minecraft.wiki/w/Enchanted_Golden_Apple
+    pub fn enchanted_golden_apple() -> Self {
+        Self {
+            name: "enchanted_golden_apple".to_owned(),
+            item: ItemMatcher::Kind(ItemKind::EnchantedGoldenApple),
+            use_duration: EAT_DURATION,
+            heal: 0.0,
+            effects: vec![
+                ConsumableEffect::new(StatusEffect::Regeneration, 1, Duration::from_secs(20)),
+                ConsumableEffect::new(StatusEffect::Absorption, 3, Duration::from_secs(120)),
+                ConsumableEffect::new(StatusEffect::Resistance, 0, Duration::from_secs(300)),
+                ConsumableEffect::new(StatusEffect::FireResistance, 0, Duration::from_secs(300)),
+            ],
+            cooldown: None,
+        }
+    }
+
+    /// The golden head of UHC, a player head named "Golden Head" that is consumed instantly.
+    pub fn golden_head() -> Self {
+        Self {
+            name: "golden_head".to_owned(),
+            item: ItemMatcher::Custom(is_golden_head),
+            use_duration: Duration::ZERO,
+            heal: 0.0,
+            effects: vec![
+                ConsumableEffect::new(StatusEffect::Regeneration, 1, Duration::from_secs(10)),
+                ConsumableEffect::new(StatusEffect::Absorption, 0, Duration::from_secs(120)),
+            ],
+            cooldown: None,
+        }
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+}
+
+/// Decides which item stacks are a [`Consumable`].
+#[derive(Debug, Clone, Copy)]
+pub enum ItemMatcher {
+    /// All items of the kind.
+    Kind(ItemKind),
+    /// The items for which the function returns true (e.g. items with a custom name).
+    Custom(fn(&ItemStack) -> bool),
+}
+
+impl ItemMatcher {
+    pub fn matches(&self, stack: &ItemStack) -> bool {
+        match self {
+            ItemMatcher::Kind(kind) => stack.item == *kind,
+            ItemMatcher::Custom(matches) => matches(stack),
+        }
+    }
+}
+
+/// A status effect that is applied when the consumable is consumed.
+#[derive(Debug, Clone, Copy)]
+pub struct ConsumableEffect {
+    pub effect: StatusEffect,
+    /// The level of the effect - 1.
+    pub amplifier: u8,
+    pub duration: Duration,
+}
+
+impl ConsumableEffect {
+    pub fn new(effect: StatusEffect, amplifier: u8, duration: Duration) -> Self {
+        Self {
+            effect,
+            amplifier,
+            duration,
+        }
+    }
+}
+
+/// If the item is a player head with "Golden Head" in its name.
+pub fn is_golden_head(stack: &ItemStack) -> bool {
+    if stack.item != ItemKind::PlayerHead {
+        return false;
+    }
+
+    let Some(Value::Compound(display)) = stack.nbt.as_ref().and_then(|nbt| nbt.get("display"))
+    else {
+        return false;
+    };
+
+    // The name is a JSON text component.
+    matches!(display.get("Name"), Some(Value::String(name)) if name.contains("Golden Head"))
+}
+
+/// An event that will be fired when an entity consumed a [`Consumable`].
+#[derive(Event)]
+pub struct ConsumeEvent {
+    pub entity: Entity,
+    /// The [`Consumable::name`].
+    pub consumable: String,
+}
+
+/// The consumable a player is using and the cooldowns, added to all clients.
+#[derive(Component, Debug, Default)]
+pub struct ConsumableState {
+    using: Option<UsingConsumable>,
+    /// When the consumables can be consumed again.
+    cooldowns: HashMap<String, Instant>,
+}
+
+#[derive(Debug)]
+struct UsingConsumable {
+    consumable: String,
+    slot: u16,
+    started: Instant,
+}
+
+impl ConsumableState {
+    /// If the player is using a consumable.
+    pub fn is_using(&self) -> bool {
+        self.using.is_some()
+    }
+
+    /// The time until the consumable can be consumed again.
+    pub fn cooldown(&self, consumable: &str) -> Option<Duration> {
+        self.cooldowns
+            .get(consumable)
+            .and_then(|until| until.checked_duration_since(Instant::now()))
+    }
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct ConsumerQuery {
+    entity: Entity,
+    state: &'static mut ConsumableState,
+    client: &'static mut Client,
+    inventory: &'static mut Inventory,
+    held_item: &'static HeldItem,
+    game_mode: &'static GameMode,
+    position: &'static Position,
+    layer: &'static EntityLayerId,
+    health: &'static mut Health,
+    attributes: Option<&'static EntityAttributes>,
+    effects: &'static mut ActiveStatusEffects,
+    absorption: Option<&'static mut Absorption>,
+    statuses: &'static mut EntityStatuses,
+}
+
+fn add_consumable_state(
+    mut commands: Commands,
+    clients: Query<Entity, (With<Client>, Without<ConsumableState>)>,
+) {
+    for entity in clients.iter() {
+        commands.entity(entity).insert(ConsumableState::default());
+    }
+}
+
+fn start_consuming(
+    mut commands: Commands,
+    mut query: Query<ConsumerQuery>,
+    mut interact_item_events: EventReader<InteractItemEvent>,
+    mut consume_writer: EventWriter<ConsumeEvent>,
+    mut layers: Query<&mut ChunkLayer>,
+    consumables: Res<Consumables>,
+) {
+    for &InteractItemEvent { client, hand, .. } in interact_item_events.read() {
+        let Ok(mut player) = query.get_mut(client) else {
+            continue;
+        };
+
+        let slot = match hand {
+            Hand::Main => player.held_item.slot(),
+            Hand::Off => OFF_HAND_SLOT,
+        };
+
+        let Some(consumable) = consumables.get(player.inventory.slot(slot)) else {
+            continue;
+        };
+
+        if player.state.cooldown(&consumable.name).is_some() {
+            continue;
+        }
+
+        if consumable.use_duration.is_zero() {
+            consume(&mut commands, &mut player, consumable, slot, &mut layers);
+            consume_writer.send(ConsumeEvent {
+                entity: client,
+                consumable: consumable.name.clone(),
+            });
+            continue;
+        }
+
+        player.state.using = Some(UsingConsumable {
+            consumable: consumable.name.clone(),
+            slot,
+            started: Instant::now(),
+        });
+    }
+}
+
+/// Players stop using the item when they release it or switch the held item.
+fn cancel_consuming(
+    mut query: Query<&mut ConsumableState>,
+    mut slot_events: EventReader<UpdateSelectedSlotEvent>,
+    mut packets: EventReader<PacketEvent>,
+) {
+    for event in slot_events.read() {
+        if let Ok(mut state) = query.get_mut(event.client) {
+            state.using = None;
+        }
+    }
+
+    for packet in packets.read() {
+        let Some(action) = packet.decode::<PlayerActionC2s>() else {
+            continue;
+        };
+
+        if matches!(action.action, PlayerAction::ReleaseUseItem) {
+            if let Ok(mut state) = query.get_mut(packet.client) {
+                state.using = None;
+            }
+        }
+    }
+}
+
+fn finish_consuming(
+    mut commands: Commands,
+    mut query: Query<ConsumerQuery>,
+    mut consume_writer: EventWriter<ConsumeEvent>,
+    mut layers: Query<&mut ChunkLayer>,
+    consumables: Res<Consumables>,
+) {
+    for mut player in query.iter_mut() {
+        let Some(using) = &player.state.using else {
+            continue;
+        };
+
+        let Some(consumable) = consumables.by_name(&using.consumable) else {
+            player.state.using = None;
+            continue;
+        };
+
+        if using.started.elapsed() < consumable.use_duration {
+            continue;
+        }
+
+        let slot = using.slot;
+        player.state.using = None;
+
+        // The item could have been moved or dropped while it was used.
+        if !consumable.item.matches(player.inventory.slot(slot)) {
+            continue;
+        }
+
+        consume(&mut commands, &mut player, consumable, slot, &mut layers);
+        consume_writer.send(ConsumeEvent {
+            entity: player.entity,
+            consumable: consumable.name.clone(),
+        });
+    }
+}
+
+/// Takes the item and applies the heal, the effects and the cooldown of the consumable.
+fn consume(
+    commands: &mut Commands,
+    player: &mut ConsumerQueryItem,
+    consumable: &Consumable,
+    slot: u16,
+    layers: &mut Query<&mut ChunkLayer>,
+) {
+    let item = player.inventory.slot(slot).item;
+
+    if *player.game_mode != GameMode::Creative {
+        let stack = player.inventory.slot(slot);
+        let remaining = if stack.count > 1 {
+            stack.clone().with_count(stack.count - 1)
+        } else {
+            ItemStack::EMPTY
+        };
+
+        player.inventory.set_slot(slot, remaining);
+    }
+
+    if consumable.heal > 0.0 && player.health.0 > 0.0 {
+        player.health.0 = (player.health.0 + consumable.heal).min(max_health(player.attributes));
+    }
+
+    for effect in &consumable.effects {
+        let ticks = (effect.duration.as_secs_f32() * TICKS_PER_SECOND) as i32;
+
+        player.effects.apply(
+            ActiveStatusEffect::from_effect(effect.effect)
+                .with_amplifier(effect.amplifier)
+                .with_duration(ticks),
+        );
+
+        if effect.effect == StatusEffect::Absorption {
+            match player.absorption.as_mut() {
+                Some(absorption) => absorption.apply_level(effect.amplifier),
+                None => {
+                    let mut absorption = Absorption::default();
+                    absorption.apply_level(effect.amplifier);
+                    commands.entity(player.entity).insert(absorption);
+                }
+            }
+        }
+    }
+
+    if let Some(cooldown) = consumable.cooldown {
+        player
+            .state
+            .cooldowns
+            .insert(consumable.name.clone(), Instant::now() + cooldown);
+
+        // Shows the cooldown on the item, like ender pearls.
+        player.client.write_packet(&CooldownUpdateS2c {
+            item_id: VarInt(i32::from(item.to_raw())),
+            cooldown_ticks: VarInt((cooldown.as_secs_f32() * TICKS_PER_SECOND) as i32),
+        });
+    }
+
+    // Stops the eating animation of the client.
+    player.statuses.trigger(EntityStatus::ConsumeItem);
+
+    if let Ok(mut layer) = layers.get_mut(player.layer.0) {
+        layer.play_sound(
+            Sound::EntityPlayerBurp,
+            SoundCategory::Player,
+            player.position.0,
+            0.5,
+            1.0,
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use bevy_time::{Time, Timer, TimerMode};
+use utils::{
+    damage::{DamageEvent, DamageType, TakesDamage},
+    enchantments::{Enchantment, ItemStackEnchantmentsExt},
+    random::GameRng,
+};
+use valence::{entity::entity::Air, prelude::*};
+
+use crate::eye_block;
+
+/// Attached to a layer to let entities run out of air and drown under water.
+///
+/// The air supply of the entity is stored in its [`Air`] component, so the client shows the bubbles.
+#[derive(Component)]
+pub struct DrowningConfig {
+    /// The maximum air supply in ticks.
+    pub max_air: i32,
+    /// The air (in ticks) regained every tick out of water.
+    pub air_regen_per_tick: i32,
+    /// The damage dealt every second once the air supply ran out.
+    pub damage: f32,
+    /// If the respiration enchantment on the helmet should slow down the air consumption.
+    pub respiration: bool,
+    timer: Timer,
+}
+
+impl Default for DrowningConfig {
+    /// The vanilla drowning values.
+    fn default() -> Self {
+        Self {
+            max_air: 300,
+            air_regen_per_tick: 4,
+            damage: 2.0,
+            respiration: true,
+            timer: Timer::new(Duration::from_millis(50), TimerMode::Repeating),
+        }
+    }
+}
+
+/// After how many ticks without air the entity takes damage again.
+const DROWNING_DAMAGE_TICKS: i32 = 20;
+
+#[allow(clippy::type_complexity)]
+pub(super) fn drowning_system(
+    mut layers: Query<(&ChunkLayer, &mut DrowningConfig)>,
+    mut entities: Query<
+        (
+            Entity,
+            &mut Air,
+            &Hitbox,
+            &EntityLayerId,
+            Option<&Equipment>,
+            Option<&GameMode>,
+        ),
+        With<TakesDamage>,
+    >,
+    mut event_writer: EventWriter<DamageEvent>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    for (_, mut config) in layers.iter_mut() {
+        config.timer.tick(time.delta());
+    }
+
+    for (entity, mut air, hitbox, layer_id, equipment, game_mode) in entities.iter_mut() {
+        let Ok((layer, config)) = layers.get(layer_id.0) else {
+            continue;
+        };
+
+        let ticks = config.timer.times_finished_this_tick();
+
+        if ticks == 0 {
+            continue;
+        }
+
+        let invulnerable = matches!(game_mode, Some(GameMode::Creative | GameMode::Spectator));
+
+        let in_water = layer
+            .block(eye_block(&hitbox.get()))
+            .map_or(false, |block| is_water(block.state));
+
+        if !in_water || invulnerable {
+            if air.0 < config.max_air {
+                air.0 = (air.0 + config.air_regen_per_tick * ticks as i32).min(config.max_air);
+            }
+            continue;
+        }
+
+        let respiration = match equipment {
+            Some(equipment) if config.respiration => equipment
+                .head()
+                .enchantments()
+                .get(&Enchantment::Respiration)
+                .copied()
+                .unwrap_or(0),
+            _ => 0,
+        };
+
+        for _ in 0..ticks {
+            // https://minecraft.wiki/w/Respiration
+            if respiration > 0 && rng.chance(respiration as f32 / (respiration as f32 + 1.0)) {
+                continue;
+            }
+
+            air.0 -= 1;
+
+            if air.0 <= -DROWNING_DAMAGE_TICKS {
+                air.0 = 0;
+
+                event_writer.send(DamageEvent {
+                    victim: entity,
+                    attacker: None,
+                    damage: config.damage,
+                    damage_type: DamageType::Drowning,
+                });
+            }
+        }
+    }
+}
+
+/// Returns true if the block contains water.
+fn is_water(state: BlockState) -> bool {
+    matches!(
+        state.to_kind(),
+        BlockKind::Water
+            | BlockKind::BubbleColumn
+            | BlockKind::Kelp
+            | BlockKind::KelpPlant
+            | BlockKind::Seagrass
+            | BlockKind::TallSeagrass
+    ) || state.get(PropName::Waterlogged) == Some(PropValue::True)
+}
@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use bevy_time::{Time, Timer, TimerMode};
+use utils::{
+    aabb_full_block_intersections,
+    damage::{DamageEvent, DamageType, StartBurningEvent, TakesDamage},
+};
+use valence::{
+    entity::{entity::Flags, OldPosition},
+    math::Aabb,
+    prelude::*,
+};
+
+use crate::eye_block;
+
+/// Attached to a layer to damage entities that touch dangerous blocks.
+///
+/// Every field set to `None` disables that kind of damage.
+#[derive(Component)]
+pub struct BlockDamageConfig {
+    /// Damage and burn time when standing in lava.
+    pub lava: Option<IgnitingBlockDamage>,
+    /// Damage and burn time when standing in a fire block.
+    pub fire: Option<IgnitingBlockDamage>,
+    /// Damage when touching a cactus.
+    pub cactus: Option<f32>,
+    /// Damage when standing on a magma block without sneaking.
+    pub magma: Option<f32>,
+    /// Damage when moving through a grown sweet berry bush.
+    pub sweet_berry_bush: Option<f32>,
+    /// Damage when the head of the entity is inside a solid block.
+    pub suffocation: Option<f32>,
+    timer: Timer,
+}
+
+impl BlockDamageConfig {
+    /// Set the time between two damage ticks, vanilla is half a second.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.timer = Timer::new(interval, TimerMode::Repeating);
+        self
+    }
+}
+
+impl Default for BlockDamageConfig {
+    /// The vanilla block damage values.
+    fn default() -> Self {
+        Self {
+            lava: Some(IgnitingBlockDamage {
+                damage: 4.0,
+                burn_duration: Duration::from_secs(15),
+                burn_damage_per_second: 1.0,
+            }),
+            fire: Some(IgnitingBlockDamage {
+                damage: 1.0,
+                burn_duration: Duration::from_secs(8),
+                burn_damage_per_second: 1.0,
+            }),
+            cactus: Some(1.0),
+            magma: Some(1.0),
+            sweet_berry_bush: Some(1.0),
+            suffocation: Some(1.0),
+            timer: Timer::new(Duration::from_millis(500), TimerMode::Repeating),
+        }
+    }
+}
+
+/// Damage of a block that also sets the entity on fire.
+pub struct IgnitingBlockDamage {
+    /// The damage dealt every interval while inside the block.
+    pub damage: f32,
+    /// How long the entity burns after touching the block.
+    pub burn_duration: Duration,
+    /// The damage per second while the entity is burning.
+    pub burn_damage_per_second: f32,
+}
+
+#[allow(clippy::type_complexity)]
+pub(super) fn block_damage_system(
+    mut layers: Query<(&ChunkLayer, &mut BlockDamageConfig)>,
+    entities: Query<(
+        Entity,
+        &Hitbox,
+        &Position,
+        &OldPosition,
+        &EntityLayerId,
+        Option<&Flags>,
+        Option<&GameMode>,
+        &TakesDamage,
+    )>,
+    mut damage_writer: EventWriter<DamageEvent>,
+    mut burn_writer: EventWriter<StartBurningEvent>,
+    time: Res<Time>,
+) {
+    for (_, mut config) in layers.iter_mut() {
+        config.timer.tick(time.delta());
+    }
+
+    for (entity, hitbox, position, old_position, layer_id, flags, game_mode, takes_damage) in
+        entities.iter()
+    {
+        if matches!(game_mode, Some(GameMode::Creative | GameMode::Spectator)) {
+            continue;
+        }
+
+        let Ok((layer, config)) = layers.get(layer_id.0) else {
+            continue;
+        };
+
+        if !config.timer.just_finished() {
+            continue;
+        }
+
+        let hitbox = hitbox.get();
+
+        // Like the vanilla invulnerability ticks, only the strongest damage is applied.
+        let mut damage: Option<(f32, DamageType)> = None;
+        let mut apply = |amount: Option<f32>, damage_type: DamageType| {
+            if let Some(amount) = amount {
+                if damage.map_or(true, |(current, _)| amount > current) {
+                    damage = Some((amount, damage_type));
+                }
+            }
+        };
+
+        let mut ignite: Option<&IgnitingBlockDamage> = None;
+        let moved = position.0.distance_squared(old_position.get()) > 0.0;
+
+        for block_pos in aabb_full_block_intersections(&hitbox) {
+            let Some(block) = layer.block(block_pos) else {
+                continue;
+            };
+
+            match block.state.to_kind() {
+                BlockKind::Lava => {
+                    if let Some(lava) = &config.lava {
+                        apply(Some(lava.damage), DamageType::Lava);
+                        ignite = Some(lava);
+                    }
+                }
+                BlockKind::Fire | BlockKind::SoulFire => {
+                    if let Some(fire) = &config.fire {
+                        apply(Some(fire.damage), DamageType::InFire);
+                        ignite = ignite.or(Some(fire));
+                    }
+                }
+                BlockKind::Cactus => apply(config.cactus, DamageType::Cactus),
+                BlockKind::SweetBerryBush => {
+                    let grown = block.state.get(PropName::Age) != Some(PropValue::_0);
+
+                    if grown && moved {
+                        apply(config.sweet_berry_bush, DamageType::SweetBerryBush);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let sneaking = flags.map_or(false, |flags| flags.sneaking());
+
+        if !sneaking && is_on(&hitbox, layer, BlockKind::MagmaBlock) {
+            apply(config.magma, DamageType::HotFloor);
+        }
+
+        if let Some(block) = layer.block(eye_block(&hitbox)) {
+            if block.state.is_opaque() {
+                apply(config.suffocation, DamageType::InWall);
+            }
+        }
+
+        // Fire immune entities are not set on fire (java behavior).
+        if let Some(ignite) = ignite.filter(|_| !takes_damage.fire_immune) {
+            burn_writer.send(StartBurningEvent {
+                victim: entity,
+                attacker: None,
+                duration: ignite.burn_duration,
+                damage_per_second: ignite.burn_damage_per_second,
+            });
+        }
+
+        if let Some((damage, damage_type)) = damage {
+            damage_writer.send(DamageEvent {
+                victim: entity,
+                attacker: None,
+                damage,
+                damage_type,
+            });
+        }
+    }
+}
+
+/// Returns true if the hitbox stands on a block of the given kind.
+fn is_on(hitbox: &Aabb, layer: &ChunkLayer, kind: BlockKind) -> bool {
+    let below = Aabb::new(
+        hitbox.min() + DVec3::new(0.0, -0.001, 0.0),
+        DVec3::new(hitbox.max().x, hitbox.min().y, hitbox.max().z),
+    );
+
+    aabb_full_block_intersections(&below).into_iter().any(|b| {
+        layer
+            .block(b)
+            .map_or(false, |block| block.state.to_kind() == kind)
+    })
+}
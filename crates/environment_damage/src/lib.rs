@@ -0,0 +1,45 @@
+pub mod air;
+pub mod blocks;
+pub mod void;
+pub mod world_border;
+
+use air::drowning_system;
+use blocks::block_damage_system;
+use utils::{aaab::AabbExt, random::GameRng};
+use valence::{math::Aabb, prelude::*};
+use void::void_damage_system;
+use world_border::world_border_damage_system;
+
+/// Damage dealt by the world itself (e.g. the void, the world border or blocks like lava).
+///
+/// The configs are components attached to the layer entity, so every layer can have different rules.
+pub struct EnvironmentDamagePlugin;
+
+impl Plugin for EnvironmentDamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRng>().add_systems(
+            Update,
+            (
+                void_damage_system,
+                world_border_damage_system,
+                block_damage_system,
+                drowning_system,
+            ),
+        );
+    }
+}
+
+/// The share of the hitbox height where the eyes of an entity are.
+const EYE_HEIGHT: f64 = 0.85;
+
+/// Returns the block the eyes of the entity are in.
+pub(crate) fn eye_block(hitbox: &Aabb) -> BlockPos {
+    let eye_y = hitbox.min().y + hitbox.width_y() * EYE_HEIGHT;
+    let center = (hitbox.min() + hitbox.max()) / 2.0;
+
+    BlockPos {
+        x: center.x.floor() as i32,
+        y: eye_y.floor() as i32,
+        z: center.z.floor() as i32,
+    }
+}
@@ -0,0 +1,129 @@
+use std::{collections::HashSet, time::Duration};
+
+use bevy_time::{Time, Timer, TimerMode};
+use utils::damage::{DamageEvent, DamageType, DeathEvent, TakesDamage};
+use valence::{entity::living::Health, prelude::*};
+
+/// Attached to a layer to damage entities that fall below a certain height.
+#[derive(Component)]
+pub struct VoidDamageConfig {
+    /// Entities below this y level take void damage.
+    ///
+    /// In vanilla this is 64 blocks below the minimum height of the world.
+    pub min_y: f64,
+    /// The damage dealt every interval.
+    pub damage: VoidDamage,
+    timer: Timer,
+}
+
+impl VoidDamageConfig {
+    /// Creates a new void damage config.
+    ///
+    /// # Arguments
+    /// - `min_y`: Entities below this y level take void damage.
+    /// - `damage`: The damage dealt every `interval`.
+    /// - `interval`: The time between two damage ticks.
+    pub fn new(min_y: f64, damage: VoidDamage, interval: Duration) -> Self {
+        Self {
+            min_y,
+            damage,
+            timer: Timer::new(interval, TimerMode::Repeating),
+        }
+    }
+
+    /// Vanilla void damage, 4 damage every half second.
+    pub fn vanilla(min_y: f64) -> Self {
+        Self::new(min_y, VoidDamage::Damage(4.0), Duration::from_millis(500))
+    }
+
+    /// Kills entities as soon as they fall below `min_y`, useful for mini-games.
+    pub fn instant_kill(min_y: f64) -> Self {
+        Self::new(min_y, VoidDamage::InstantKill, Duration::from_millis(50))
+    }
+}
+
+/// The damage the void deals.
+pub enum VoidDamage {
+    /// Deal the given damage every interval.
+    Damage(f32),
+    /// Kill the entity immediately.
+    InstantKill,
+}
+
+/// Marks the entities that died in the void until they leave it.
+///
+/// Entities can be alive again without leaving the void (e.g. when their health is reset after
+/// death without the respawn plugin), [`VoidDamage::InstantKill`] should not kill them every
+/// interval.
+#[derive(Component)]
+struct KilledByVoid;
+
+#[allow(clippy::type_complexity)]
+pub(super) fn void_damage_system(
+    mut commands: Commands,
+    mut layers: Query<&mut VoidDamageConfig>,
+    entities: Query<
+        (
+            Entity,
+            &Position,
+            &EntityLayerId,
+            Option<&GameMode>,
+            Option<&Health>,
+            Has<KilledByVoid>,
+        ),
+        With<TakesDamage>,
+    >,
+    mut death_reader: EventReader<DeathEvent>,
+    mut event_writer: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    for mut config in layers.iter_mut() {
+        config.timer.tick(time.delta());
+    }
+
+    let died = death_reader
+        .read()
+        .map(|death| death.victim)
+        .collect::<HashSet<_>>();
+
+    for (entity, position, layer_id, game_mode, health, killed) in entities.iter() {
+        let Some(config) = layers
+            .get(layer_id.0)
+            .ok()
+            .filter(|config| position.0.y < config.min_y)
+        else {
+            if killed {
+                commands.entity(entity).remove::<KilledByVoid>();
+            }
+
+            continue;
+        };
+
+        if !killed && died.contains(&entity) {
+            commands.entity(entity).insert(KilledByVoid);
+        }
+
+        let killed = killed || died.contains(&entity);
+
+        // Spectators are invulnerable, like in vanilla.
+        if game_mode == Some(&GameMode::Spectator) || !config.timer.just_finished() {
+            continue;
+        }
+
+        let damage = match config.damage {
+            VoidDamage::Damage(damage) => damage,
+            // The entity is already dead, it should only be killed once.
+            VoidDamage::InstantKill if killed || health.is_some_and(|health| health.0 <= 0.0) => {
+                continue
+            }
+            VoidDamage::InstantKill => f32::MAX,
+        };
+
+        event_writer.send(DamageEvent {
+            victim: entity,
+            attacker: None,
+            damage,
+            damage_type: DamageType::Void,
+        });
+    }
+}
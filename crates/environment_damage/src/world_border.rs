@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use bevy_time::{Time, Timer, TimerMode};
+use utils::damage::{DamageEvent, DamageType, TakesDamage, VelocityChangedEvent};
+use valence::{
+    entity::Velocity,
+    prelude::*,
+    world_border::{WorldBorderCenter, WorldBorderLerp},
+};
+
+/// Attached to a layer (next to the world border components) to damage entities outside the world border.
+#[derive(Component)]
+pub struct WorldBorderDamageConfig {
+    /// The distance outside the border where entities don't take damage.
+    pub safe_zone: f64,
+    /// The damage per block outside the safe zone, dealt every interval.
+    /// At least 1 damage is dealt.
+    pub damage_per_block: f64,
+    /// If `Some`, entities outside the border will be pushed towards the center with this velocity.
+    pub pushback: Option<f32>,
+    timer: Timer,
+}
+
+impl WorldBorderDamageConfig {
+    /// Creates a new world border damage config.
+    ///
+    /// # Arguments
+    /// - `safe_zone`: The distance outside the border where entities don't take damage.
+    /// - `damage_per_block`: The damage per block outside the safe zone.
+    /// - `interval`: The time between two damage ticks.
+    pub fn new(safe_zone: f64, damage_per_block: f64, interval: Duration) -> Self {
+        Self {
+            safe_zone,
+            damage_per_block,
+            pushback: None,
+            timer: Timer::new(interval, TimerMode::Repeating),
+        }
+    }
+
+    /// Push entities outside the border back towards the center.
+    pub fn with_pushback(mut self, velocity: f32) -> Self {
+        self.pushback = Some(velocity);
+        self
+    }
+}
+
+impl Default for WorldBorderDamageConfig {
+    /// The vanilla world border damage.
+    fn default() -> Self {
+        Self::new(5.0, 0.2, Duration::from_millis(500))
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(super) fn world_border_damage_system(
+    mut layers: Query<(
+        &mut WorldBorderDamageConfig,
+        &WorldBorderCenter,
+        &WorldBorderLerp,
+    )>,
+    mut entities: Query<
+        (
+            Entity,
+            &Position,
+            &EntityLayerId,
+            &mut Velocity,
+            Option<&mut Client>,
+            Option<&GameMode>,
+        ),
+        With<TakesDamage>,
+    >,
+    mut event_writer: EventWriter<DamageEvent>,
+    mut velocity_writer: EventWriter<VelocityChangedEvent>,
+    time: Res<Time>,
+) {
+    for (mut config, _, _) in layers.iter_mut() {
+        config.timer.tick(time.delta());
+    }
+
+    for (entity, position, layer_id, mut velocity, client, game_mode) in entities.iter_mut() {
+        if game_mode == Some(&GameMode::Spectator) {
+            continue;
+        }
+
+        let Ok((config, center, lerp)) = layers.get(layer_id.0) else {
+            continue;
+        };
+
+        let radius = lerp.current_diameter / 2.0;
+        let offset = DVec3::new(position.0.x - center.x, 0.0, position.0.z - center.z);
+
+        // The border is a square, so the distance is the largest distance on one of the axes.
+        let distance_outside = offset.x.abs().max(offset.z.abs()) - radius;
+
+        if distance_outside <= 0.0 {
+            continue;
+        }
+
+        if let Some(pushback) = config.pushback {
+            let direction = (-offset).normalize_or_zero().as_vec3();
+            let pushback = direction * pushback;
+
+            if let Some(mut client) = client {
+                client.set_velocity(pushback);
+                velocity_writer.send(VelocityChangedEvent {
+                    client: entity,
+                    velocity: pushback,
+                });
+            } else {
+                // Only the velocity towards the center is replaced, so it does not add up.
+                let towards_center = velocity.0.dot(direction);
+                velocity.0 += pushback - direction * towards_center;
+            }
+        }
+
+        let damage_distance = distance_outside - config.safe_zone;
+
+        if damage_distance <= 0.0 || !config.timer.just_finished() {
+            continue;
+        }
+
+        let damage = (damage_distance * config.damage_per_block).floor().max(1.0);
+
+        event_writer.send(DamageEvent {
+            victim: entity,
+            attacker: None,
+            damage: damage as f32,
+            damage_type: DamageType::WorldBorder,
+        });
+    }
+}
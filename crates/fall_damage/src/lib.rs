@@ -1,4 +1,7 @@
-use utils::damage::DamageEvent;
+use utils::{
+    damage::{DamageEvent, DamageType},
+    region::Regions,
+};
 use valence::prelude::*;
 
 #[derive(Component, Default)]
@@ -41,6 +44,16 @@ impl FallingState {
     pub fn on_ground(&self) -> bool {
         !self.falling
     }
+
+    /// Resets the fall to start at the given position, keeping the config.
+    ///
+    /// This should be called when the entity is teleported (e.g. on respawn), otherwise the
+    /// distance to the old position would be counted as a fall.
+    pub fn reset(&mut self, start_pos: DVec3) {
+        self.fall_start = start_pos;
+        self.falling = false;
+        self.in_air = false;
+    }
 }
 
 pub struct FallDamagePlugin;
@@ -53,11 +66,11 @@ impl Plugin for FallDamagePlugin {
 
 fn fall_damage_system(
     mut query: Query<(Entity, &mut FallingState, &Position, &Hitbox)>,
-    layers: Query<&ChunkLayer, With<EntityLayer>>, // TODO: Get the correct layer that the entity is on
+    layers: Query<(&ChunkLayer, Option<&Regions>), With<EntityLayer>>, // TODO: Get the correct layer that the entity is on
     mut event_writer: EventWriter<DamageEvent>,
 ) {
     for (entity, mut fall_damage_state, position, hitbox) in query.iter_mut() {
-        let layer = layers.single();
+        let (layer, regions) = layers.single();
 
         let is_on_ground = utils::is_on_block(&hitbox.get(), layer);
 
@@ -65,7 +78,12 @@ fn fall_damage_system(
             if fall_damage_state.falling {
                 let blocks_fallen = (fall_damage_state.fall_start.y - position.0.y).max(0.0);
 
-                if blocks_fallen > fall_damage_state.falling_state_config.no_damage_distance {
+                let fall_damage_allowed =
+                    regions.map_or(true, |regions| regions.flags_at(position.0).fall_damage());
+
+                if fall_damage_allowed
+                    && blocks_fallen > fall_damage_state.falling_state_config.no_damage_distance
+                {
                     let damage = (blocks_fallen
                         - fall_damage_state.falling_state_config.no_damage_distance)
                         * fall_damage_state.falling_state_config.damage_per_block;
@@ -75,6 +93,7 @@ fn fall_damage_system(
                             victim: entity,
                             attacker: None,
                             damage: damage as f32,
+                            damage_type: DamageType::Fall,
                         });
                     }
                 }
@@ -0,0 +1,508 @@
+pub mod pathfinding;
+
+use std::time::{Duration, Instant};
+
+use combat::{
+    projectile::Projectile, team::are_friendly, AttackEvent, AttackWeapon, CombatConfigs,
+    CombatState, Team,
+};
+use fall_damage::FallingState;
+use physics::{
+    Acceleration, BlockCollisionConfig, Drag, EntityCollisionConfig, StopOnBlockCollision,
+};
+use utils::is_on_block;
+use valence::{
+    entity::{
+        arrow::ArrowEntityBundle, entity::NoGravity, hitbox::Hitbox, EntityAnimation,
+        EntityAnimations, EntityStatuses, HeadYaw, Velocity,
+    },
+    math::Aabb,
+    prelude::*,
+};
+
+/// How often the path to the target is recalculated.
+const PATH_INTERVAL: Duration = Duration::from_millis(500);
+/// The distance to a waypoint at which it counts as reached.
+const WAYPOINT_REACHED: f64 = 0.3;
+/// How long a mob can't walk after it was hit.
+const KNOCKBACK_TIME: Duration = Duration::from_millis(500);
+/// The height of the eyes of the mobs (zombies and skeletons).
+const EYE_HEIGHT: f64 = 1.74;
+/// The height of the eyes of the players the mobs target.
+const TARGET_EYE_HEIGHT: f64 = 1.62;
+
+/// Makes an entity with a [`CombatState`] fight the players near it.
+///
+/// The entity is moved by its [`Velocity`], so it needs the physics components
+/// (see [`MobAiBundle`]).
+#[derive(Component)]
+pub struct MobAi {
+    /// The distance in which players are targeted.
+    pub aggro_range: f64,
+    /// The walking speed in blocks per second.
+    pub speed: f32,
+    /// The upward velocity of a jump in blocks per second.
+    pub jump_velocity: f32,
+    /// How the mob attacks its target.
+    pub attack: MobAttack,
+    /// How many blocks the pathfinding searches at most.
+    pub max_path_nodes: usize,
+    target: Option<Entity>,
+    /// If the horizontal velocity is the walking of the mob.
+    walking: bool,
+    path: Vec<BlockPos>,
+    last_path: Instant,
+    last_attack: Instant,
+}
+
+/// How a mob attacks its target.
+pub enum MobAttack {
+    /// Attack through the combat pipeline like a player (e.g. a zombie).
+    Melee {
+        /// The distance from which the mob can attack.
+        reach: f64,
+        /// The time between two attacks.
+        interval: Duration,
+        weapon: AttackWeapon,
+    },
+    /// Shoot arrows at the target (e.g. a skeleton).
+    Ranged {
+        /// The distance from which the mob shoots.
+        range: f64,
+        /// The time between two shots.
+        interval: Duration,
+        /// The speed of the arrows in blocks per second.
+        projectile_speed: f32,
+        /// The damage of the arrows before armor.
+        damage: f32,
+        /// The knockback of the arrows.
+        knockback: f32,
+    },
+}
+
+impl MobAi {
+    pub fn new(attack: MobAttack) -> Self {
+        Self {
+            aggro_range: 35.0,
+            speed: 3.0,
+            jump_velocity: 9.0,
+            attack,
+            max_path_nodes: 256,
+            target: None,
+            walking: false,
+            path: Vec::new(),
+            last_path: Instant::now(),
+            last_attack: Instant::now(),
+        }
+    }
+
+    /// A mob that chases its target and attacks with its held item (like a zombie).
+    pub fn melee() -> Self {
+        Self::new(MobAttack::Melee {
+            reach: 2.0,
+            interval: Duration::from_secs(1),
+            weapon: AttackWeapon::HeldItem,
+        })
+    }
+
+    /// A mob that keeps its distance and shoots arrows (like a skeleton).
+    pub fn ranged() -> Self {
+        Self {
+            aggro_range: 16.0,
+            ..Self::new(MobAttack::Ranged {
+                range: 15.0,
+                interval: Duration::from_secs(2),
+                projectile_speed: 32.0,
+                damage: 4.0,
+                knockback: 0.4,
+            })
+        }
+    }
+
+    /// The entity the mob is attacking.
+    pub fn target(&self) -> Option<Entity> {
+        self.target
+    }
+
+    /// The distance at which the mob stops walking towards its target.
+    fn keep_distance(&self) -> f64 {
+        match &self.attack {
+            MobAttack::Melee { reach, .. } => reach * 0.8,
+            MobAttack::Ranged { range, .. } => range * 0.75,
+        }
+    }
+}
+
+/// The components a mob needs besides its entity bundle (e.g. `ZombieEntityBundle`).
+#[derive(Bundle)]
+pub struct MobAiBundle {
+    pub ai: MobAi,
+    pub combat_state: CombatState,
+    pub falling_state: FallingState,
+    pub equipment: Equipment,
+    pub statuses: EntityStatuses,
+    pub acceleration: Acceleration,
+    pub block_collision: BlockCollisionConfig,
+    /// Mobs push each other apart instead of walking into each other.
+    pub entity_collision: EntityCollisionConfig,
+}
+
+impl MobAiBundle {
+    pub fn new(ai: MobAi) -> Self {
+        Self {
+            ai,
+            combat_state: CombatState::default(),
+            falling_state: FallingState::default(),
+            equipment: Equipment::default(),
+            statuses: EntityStatuses::default(),
+            acceleration: Acceleration(Vec3::new(0.0, -32.0, 0.0)),
+            block_collision: BlockCollisionConfig::default(),
+            entity_collision: EntityCollisionConfig::push(),
+        }
+    }
+}
+
+pub struct MobAiPlugin;
+
+impl Plugin for MobAiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, mob_ai_system);
+    }
+}
+
+/// The block the feet of an entity are in.
+fn feet_block(position: DVec3) -> BlockPos {
+    // Entities standing on a block can be slightly below its top.
+    BlockPos::new(
+        position.x.floor() as i32,
+        (position.y + 0.1).floor() as i32,
+        position.z.floor() as i32,
+    )
+}
+
+/// The distance along the ray from `origin` at which it enters the box, if it hits the box.
+fn ray_intersection(aabb: &Aabb, origin: DVec3, direction: DVec3) -> Option<f64> {
+    let inverse = direction.recip();
+    let t1 = (aabb.min() - origin) * inverse;
+    let t2 = (aabb.max() - origin) * inverse;
+
+    let near = t1.min(t2).max_element();
+    let far = t1.max(t2).min_element();
+
+    (near <= far && far >= 0.0).then_some(near.max(0.0))
+}
+
+/// If no block is between `from` and `to` (e.g. the eyes of a mob and its target).
+///
+/// The ray walks through the blocks it crosses and is blocked by their collision shapes, so a
+/// mob can see through grass or open doors but not through walls.
+fn has_line_of_sight(layer: &ChunkLayer, from: DVec3, to: DVec3) -> bool {
+    let offset = to - from;
+    let length = offset.length();
+
+    if length == 0.0 {
+        return true;
+    }
+
+    let direction = offset / length;
+
+    let mut block = [
+        from.x.floor() as i32,
+        from.y.floor() as i32,
+        from.z.floor() as i32,
+    ];
+    let end = [
+        to.x.floor() as i32,
+        to.y.floor() as i32,
+        to.z.floor() as i32,
+    ];
+    let origin = from.to_array();
+    let direction_components = direction.to_array();
+
+    // The step to the next block and the distances along the ray to the next block boundary and
+    // between two boundaries on every axis.
+    let mut step = [0; 3];
+    let mut next_boundary = [f64::INFINITY; 3];
+    let mut boundary_distance = [f64::INFINITY; 3];
+
+    for axis in 0..3 {
+        let d = direction_components[axis];
+
+        if d > 0.0 {
+            step[axis] = 1;
+            next_boundary[axis] = (block[axis] as f64 + 1.0 - origin[axis]) / d;
+            boundary_distance[axis] = 1.0 / d;
+        } else if d < 0.0 {
+            step[axis] = -1;
+            next_boundary[axis] = (block[axis] as f64 - origin[axis]) / d;
+            boundary_distance[axis] = -1.0 / d;
+        }
+    }
+
+    loop {
+        let pos = BlockPos::new(block[0], block[1], block[2]);
+
+        if let Some(state) = layer.block(pos).map(|block| block.state) {
+            let block_offset = DVec3::new(pos.x as f64, pos.y as f64, pos.z as f64);
+
+            let blocked = state.collision_shapes().any(|shape| {
+                ray_intersection(&shape.translate(block_offset), from, direction)
+                    .is_some_and(|distance| distance <= length)
+            });
+
+            if blocked {
+                return false;
+            }
+        }
+
+        if block == end {
+            return true;
+        }
+
+        let axis = (0..3)
+            .min_by(|a, b| next_boundary[*a].total_cmp(&next_boundary[*b]))
+            .unwrap_or(0);
+
+        if next_boundary[axis] > length {
+            return true;
+        }
+
+        block[axis] += step[axis];
+        next_boundary[axis] += boundary_distance[axis];
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn mob_ai_system(
+    mut commands: Commands,
+    mut mobs: Query<
+        (
+            Entity,
+            &mut MobAi,
+            &Position,
+            &mut Look,
+            &mut HeadYaw,
+            &mut Velocity,
+            &EntityLayerId,
+            &Hitbox,
+            Option<&Team>,
+            &CombatState,
+            Option<&mut EntityAnimations>,
+        ),
+        Without<Client>,
+    >,
+    players: Query<(Entity, &Position, &EntityLayerId, &GameMode, Option<&Team>), With<Client>>,
+    layers: Query<&ChunkLayer>,
+    configs: Res<CombatConfigs>,
+    mut attack_writer: EventWriter<AttackEvent>,
+) {
+    for (
+        mob_ent,
+        mut ai,
+        position,
+        mut look,
+        mut head_yaw,
+        mut velocity,
+        layer_id,
+        hitbox,
+        team,
+        combat_state,
+        animations,
+    ) in mobs.iter_mut()
+    {
+        // Borrow the fields separately.
+        let ai = &mut *ai;
+
+        let Ok(layer) = layers.get(layer_id.0) else {
+            continue;
+        };
+
+        let config = combat_state.config(&configs);
+
+        let can_target = |(_, target_position, target_layer, game_mode, target_team): &(
+            Entity,
+            &Position,
+            &EntityLayerId,
+            &GameMode,
+            Option<&Team>,
+        ),
+                          range: f64| {
+            target_layer.0 == layer_id.0
+                && matches!(game_mode, GameMode::Survival | GameMode::Adventure)
+                && !are_friendly(&config, team, *target_team)
+                && target_position.0.distance(position.0) <= range
+        };
+
+        // Keep the current target while it is in range, otherwise target the nearest player.
+        let target = ai
+            .target
+            .and_then(|target| players.get(target).ok())
+            .filter(|target| can_target(target, ai.aggro_range))
+            .or_else(|| {
+                players
+                    .iter()
+                    .filter(|target| can_target(target, ai.aggro_range))
+                    .min_by(|a, b| {
+                        a.1 .0
+                            .distance(position.0)
+                            .total_cmp(&b.1 .0.distance(position.0))
+                    })
+            })
+            .map(|(target, target_position, ..)| (target, target_position.0));
+
+        let Some((target, target_position)) = target else {
+            ai.target = None;
+            ai.path.clear();
+
+            // Only stop walking, the knockback of idle mobs plays out.
+            if ai.walking && combat_state.last_got_hit.elapsed() >= KNOCKBACK_TIME {
+                ai.walking = false;
+                velocity.0.x = 0.0;
+                velocity.0.z = 0.0;
+            }
+
+            continue;
+        };
+
+        if ai.target != Some(target) {
+            ai.target = Some(target);
+            ai.path.clear();
+        }
+
+        let offset = target_position - position.0;
+        let horizontal_distance = offset.x.hypot(offset.z);
+        let distance = offset.length();
+
+        let yaw = (-offset.x).atan2(offset.z).to_degrees() as f32;
+        look.yaw = yaw;
+        look.pitch = -(offset.y.atan2(horizontal_distance).to_degrees() as f32);
+        head_yaw.0 = yaw;
+
+        let eye = position.0 + DVec3::new(0.0, EYE_HEIGHT, 0.0);
+        let in_sight = has_line_of_sight(
+            layer,
+            eye,
+            target_position + DVec3::new(0.0, TARGET_EYE_HEIGHT, 0.0),
+        );
+
+        match &ai.attack {
+            MobAttack::Melee {
+                reach,
+                interval,
+                weapon,
+            } => {
+                // Don't hit through walls.
+                if distance <= *reach && in_sight && ai.last_attack.elapsed() >= *interval {
+                    attack_writer.send(AttackEvent {
+                        attacker: mob_ent,
+                        victim: target,
+                        weapon: weapon.clone(),
+                    });
+
+                    if let Some(mut animations) = animations {
+                        animations.trigger(EntityAnimation::SwingMainHand);
+                    }
+
+                    ai.last_attack = Instant::now();
+                }
+            }
+            MobAttack::Ranged {
+                range,
+                interval,
+                projectile_speed,
+                damage,
+                knockback,
+            } => {
+                // Don't waste arrows on walls.
+                if distance <= *range && in_sight && ai.last_attack.elapsed() >= *interval {
+                    // Aim a bit higher the further the target is away to make up for gravity.
+                    // (java behavior)
+                    let mut aim = target_position + DVec3::new(0.0, 0.6, 0.0) - eye;
+                    aim.y += horizontal_distance * 0.2;
+
+                    let direction = aim.normalize().as_vec3();
+
+                    commands
+                        .spawn(ArrowEntityBundle {
+                            position: Position(eye + (direction * 0.5).as_dvec3()),
+                            look: *look,
+                            velocity: Velocity(direction * *projectile_speed),
+                            entity_no_gravity: NoGravity(true),
+                            layer: *layer_id,
+                            ..Default::default()
+                        })
+                        .insert(Projectile {
+                            shooter: Some(mob_ent),
+                            damage: *damage,
+                            knockback: *knockback,
+                            burn: None,
+                        })
+                        .insert(Acceleration(Vec3::new(0.0, -20.0, 0.0)))
+                        .insert(Drag(Vec3::splat(0.2)))
+                        .insert(BlockCollisionConfig::default())
+                        .insert(StopOnBlockCollision::all());
+
+                    ai.last_attack = Instant::now();
+                }
+            }
+        }
+
+        // Don't walk while taking knockback.
+        if combat_state.last_got_hit.elapsed() < KNOCKBACK_TIME {
+            continue;
+        }
+
+        // Walk around obstacles until the target is in sight.
+        if distance <= ai.keep_distance() && in_sight {
+            ai.path.clear();
+            ai.walking = false;
+            velocity.0.x = 0.0;
+            velocity.0.z = 0.0;
+            continue;
+        }
+
+        let feet = feet_block(position.0);
+
+        if ai.path.is_empty() || ai.last_path.elapsed() >= PATH_INTERVAL {
+            let max_path_nodes = ai.max_path_nodes;
+            ai.path =
+                pathfinding::find_path(layer, feet, feet_block(target_position), max_path_nodes);
+            ai.last_path = Instant::now();
+        }
+
+        while let Some(waypoint) = ai.path.first() {
+            let center = DVec3::new(
+                waypoint.x as f64 + 0.5,
+                position.0.y,
+                waypoint.z as f64 + 0.5,
+            );
+
+            if center.distance(position.0) > WAYPOINT_REACHED || waypoint.y > feet.y {
+                break;
+            }
+
+            ai.path.remove(0);
+        }
+
+        // Walk directly to the target if there is no path (e.g. it is in the same block).
+        let (goal, goal_y) = match ai.path.first() {
+            Some(waypoint) => (
+                DVec3::new(waypoint.x as f64 + 0.5, 0.0, waypoint.z as f64 + 0.5),
+                waypoint.y,
+            ),
+            None => (target_position, feet.y),
+        };
+
+        let direction = DVec3::new(goal.x - position.0.x, 0.0, goal.z - position.0.z)
+            .normalize_or_zero()
+            .as_vec3();
+
+        ai.walking = true;
+        velocity.0.x = direction.x * ai.speed;
+        velocity.0.z = direction.z * ai.speed;
+
+        if goal_y > feet.y && is_on_block(&hitbox.get(), layer) {
+            velocity.0.y = ai.jump_velocity;
+        }
+    }
+}
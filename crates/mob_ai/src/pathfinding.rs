@@ -0,0 +1,139 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use valence::prelude::*;
+
+/// The cost of a step to a neighbouring block.
+const STEP_COST: u32 = 10;
+/// The additional cost of jumping up a block.
+const JUMP_COST: u32 = 5;
+/// How far a mob is willing to drop down.
+const MAX_DROP: i32 = 3;
+
+/// If an entity can move through the block.
+fn is_passable(layer: &ChunkLayer, pos: BlockPos) -> bool {
+    match layer.block(pos) {
+        Some(block) => block.state.collision_shapes().next().is_none() && !block.state.is_liquid(),
+        None => false,
+    }
+}
+
+/// If an entity can stand on the block.
+fn is_solid(layer: &ChunkLayer, pos: BlockPos) -> bool {
+    layer
+        .block(pos)
+        .is_some_and(|block| block.state.collision_shapes().next().is_some())
+}
+
+/// If an entity that is two blocks tall can stand with its feet in the block.
+pub fn is_walkable(layer: &ChunkLayer, pos: BlockPos) -> bool {
+    is_passable(layer, pos)
+        && is_passable(layer, BlockPos::new(pos.x, pos.y + 1, pos.z))
+        && is_solid(layer, BlockPos::new(pos.x, pos.y - 1, pos.z))
+}
+
+/// The walkable blocks next to the block, including one block up (jumping) and a few blocks down.
+fn neighbours(layer: &ChunkLayer, pos: BlockPos) -> impl Iterator<Item = (BlockPos, u32)> + '_ {
+    [(1, 0), (-1, 0), (0, 1), (0, -1)]
+        .into_iter()
+        .filter_map(move |(dx, dz)| {
+            let (x, z) = (pos.x + dx, pos.z + dz);
+
+            // Jumping needs room above the head.
+            let up = BlockPos::new(x, pos.y + 1, z);
+            if is_passable(layer, BlockPos::new(pos.x, pos.y + 2, pos.z)) && is_walkable(layer, up)
+            {
+                return Some((up, STEP_COST + JUMP_COST));
+            }
+
+            (-MAX_DROP..=0)
+                .rev()
+                .map(|dy| BlockPos::new(x, pos.y + dy, z))
+                .take_while(|pos| is_passable(layer, *pos))
+                .find(|pos| is_walkable(layer, *pos))
+                .map(|pos| (pos, STEP_COST))
+        })
+}
+
+fn distance(a: BlockPos, b: BlockPos) -> u32 {
+    (a.x.abs_diff(b.x) + a.y.abs_diff(b.y) + a.z.abs_diff(b.z)) * STEP_COST
+}
+
+/// Finds a path from `start` to `goal` (the positions of the feet) with A*.
+///
+/// At most `max_nodes` blocks are searched, if the goal can not be reached, the path to the block
+/// that is closest to the goal is returned. The path does not contain the start.
+pub fn find_path(
+    layer: &ChunkLayer,
+    start: BlockPos,
+    goal: BlockPos,
+    max_nodes: usize,
+) -> Vec<BlockPos> {
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<BlockPos, (BlockPos, u32)> = HashMap::new();
+
+    open.push(Reverse((
+        distance(start, goal),
+        0,
+        (start.x, start.y, start.z),
+    )));
+    came_from.insert(start, (start, 0));
+
+    let mut closest = (distance(start, goal), start);
+    let mut searched = 0;
+
+    while let Some(Reverse((_, cost, (x, y, z)))) = open.pop() {
+        let pos = BlockPos::new(x, y, z);
+
+        if pos == goal {
+            closest = (0, pos);
+            break;
+        }
+
+        if came_from.get(&pos).is_some_and(|(_, best)| *best < cost) {
+            continue;
+        }
+
+        searched += 1;
+        if searched > max_nodes {
+            break;
+        }
+
+        for (next, step_cost) in neighbours(layer, pos) {
+            let next_cost = cost + step_cost;
+
+            if came_from
+                .get(&next)
+                .is_some_and(|(_, best)| *best <= next_cost)
+            {
+                continue;
+            }
+
+            came_from.insert(next, (pos, next_cost));
+
+            let remaining = distance(next, goal);
+            if remaining < closest.0 {
+                closest = (remaining, next);
+            }
+
+            open.push(Reverse((
+                next_cost + remaining,
+                next_cost,
+                (next.x, next.y, next.z),
+            )));
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut current = closest.1;
+
+    while current != start {
+        path.push(current);
+        current = came_from[&current].0;
+    }
+
+    path.reverse();
+    path
+}
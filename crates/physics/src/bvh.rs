@@ -1,14 +1,56 @@
-use valence::prelude::*;
-use valence_spatial::bvh::Bvh;
-use vek::Aabb;
+use valence::{math::Aabb, prelude::*};
+use valence_spatial::{bvh::Bvh, Bounded3D};
 
+use crate::EntityCollisionResponse;
+
+/// An entity with an [`EntityCollisionConfig`](crate::EntityCollisionConfig) in the bvh.
+#[derive(Clone, Copy)]
+pub(super) struct EntityCollider {
+    pub entity: Entity,
+    /// The collider of the entity at the start of the tick.
+    pub hitbox: Aabb,
+    /// How far the entity moves in this tick.
+    pub velocity_delta: Vec3,
+    /// If the entity is moved by the physics system (it has a velocity and is not a client).
+    pub simulated: bool,
+    pub response: EntityCollisionResponse,
+}
+
+impl EntityCollider {
+    /// The area that the collider sweeps through in this tick.
+    pub fn swept(hitbox: &Aabb, velocity_delta: Vec3) -> vek::Aabb<f64> {
+        let (min, max) = (hitbox.min(), hitbox.max());
+        let delta = velocity_delta.as_dvec3();
+
+        vek::Aabb {
+            min: vek::Vec3::new(
+                min.x.min(min.x + delta.x),
+                min.y.min(min.y + delta.y),
+                min.z.min(min.z + delta.z),
+            ),
+            max: vek::Vec3::new(
+                max.x.max(max.x + delta.x),
+                max.y.max(max.y + delta.y),
+                max.z.max(max.z + delta.z),
+            ),
+        }
+    }
+}
+
+impl Bounded3D for EntityCollider {
+    fn aabb(&self) -> vek::Aabb<f64> {
+        Self::swept(&self.hitbox, self.velocity_delta)
+    }
+}
+
+/// The broadphase of the entity-entity collisions, rebuilt every tick.
 #[derive(Resource)]
 pub(super) struct BvhResource {
-    pub _bvh: Bvh<Aabb<f64>>,
+    pub bvh: Bvh<EntityCollider>,
 }
 
 impl BvhResource {
     pub fn new() -> Self {
-        Self { _bvh: Bvh::new() }
+        Self { bvh: Bvh::new() }
     }
 }
@@ -4,9 +4,10 @@ pub mod utils;
 use ::utils::aaab::AabbExt;
 use bevy_ecs::query::QueryData;
 use bevy_time::Time;
-use bvh::BvhResource;
-use utils::swept_aabb_collide;
+use bvh::{BvhResource, EntityCollider};
+use utils::{aabb_overlap, swept_aabb_collide};
 use valence::{entity::Velocity, math::Aabb, prelude::*};
+use valence_spatial::SpatialIndex;
 
 /// The acceleration of an entity.
 #[derive(Component)]
@@ -57,12 +58,95 @@ impl StopOnBlockCollision {
 }
 
 /// The config for entity-entity collisions.
-#[derive(Component)]
+///
+/// Only entities that both have this component collide with each other.
+#[derive(Component, Default)]
 pub struct EntityCollisionConfig {
     /// The hitbox that will be used for entity collision detection.
     ///
     /// If `None`, the entity's hitbox will be used.
     pub entity_collider_hitbox: Option<Aabb>,
+    /// What happens when the entity collides with another entity.
+    pub response: EntityCollisionResponse,
+}
+
+impl EntityCollisionConfig {
+    /// Only emits the [`EntityEntityCollisionEvent`]s.
+    pub fn sensor() -> Self {
+        Self::default()
+    }
+
+    /// Pushes the entity away from the entities it overlaps with, like vanilla mobs.
+    pub fn push() -> Self {
+        Self {
+            response: EntityCollisionResponse::Push {
+                strength: VANILLA_PUSH_STRENGTH,
+            },
+            ..Default::default()
+        }
+    }
+
+    /// The entity can not go through other solid entities.
+    pub fn solid(mass: f32) -> Self {
+        Self {
+            response: EntityCollisionResponse::Solid {
+                mass,
+                restitution: 0.0,
+            },
+            ..Default::default()
+        }
+    }
+
+    /// The hitbox that is used for the collisions of the entity.
+    pub fn collider(&self, hitbox: &Hitbox) -> Aabb {
+        self.entity_collider_hitbox.unwrap_or(hitbox.get())
+    }
+}
+
+/// The velocity (blocks per second) that vanilla entities push each other with every second.
+const VANILLA_PUSH_STRENGTH: f32 = 20.0;
+
+/// How an entity reacts when it collides with another entity.
+///
+/// When one of the entities is a sensor, the entities go through each other. Otherwise they push
+/// each other if one of them pushes, and only two solid entities bounce off each other.
+///
+/// Only the entities that are moved by the physics react, clients are not moved. Vanilla clients
+/// push themselves away from the entities they overlap with, and solid entities treat them like
+/// a wall.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EntityCollisionResponse {
+    /// Only the [`EntityEntityCollisionEvent`] is emitted, e.g. for triggers or projectiles.
+    #[default]
+    Sensor,
+    /// Overlapping entities are pushed apart horizontally (vanilla behavior).
+    ///
+    /// The entities can overlap, like mobs that are crammed together. The default strength of
+    /// [`EntityCollisionConfig::push`] is the vanilla push of 0.05 blocks per tick every tick.
+    ///
+    /// https://minecraft.wiki/w/Entity_cramming
+    Push {
+        /// The velocity (blocks per second) that the entity is pushed with every second.
+        strength: f32,
+    },
+    /// The entities can not overlap and exchange their momentum when they collide.
+    Solid {
+        /// The mass of the entity, `f32::INFINITY` for entities that other entities can not move.
+        mass: f32,
+        /// How much of the velocity is kept when bouncing off (0.0 - 1.0), the higher one of the
+        /// two entities is used.
+        restitution: f32,
+    },
+}
+
+impl EntityCollisionResponse {
+    /// The inverse of the mass of a solid entity, `0.0` if it can not be moved.
+    fn inverse_mass(&self) -> f32 {
+        match self {
+            EntityCollisionResponse::Solid { mass, .. } if *mass > 0.0 => 1.0 / mass,
+            _ => 0.0,
+        }
+    }
 }
 
 /// The config for entity-block collisions.
@@ -77,10 +161,18 @@ pub struct BlockCollisionConfig {
 }
 
 /// The event emitted when an entity collides with another entity.
+///
+/// It is emitted once per pair and tick, `entity1` is the entity that is moved by the physics.
 #[derive(Event, Debug)]
 pub struct EntityEntityCollisionEvent {
     pub entity1: Entity,
     pub entity2: Entity,
+    /// The normal of the contact, pointing from `entity2` towards `entity1`.
+    pub normal: Vec3,
+    /// The fraction of the tick (0.0 - 1.0) after which the entities touched.
+    ///
+    /// It is `0.0` if the entities were already intersecting.
+    pub time_of_impact: f64,
 }
 
 /// The event emitted when an entity collides with a block.
@@ -101,7 +193,7 @@ impl Plugin for PhysicsPlugin {
         app.add_event::<EntityEntityCollisionEvent>()
             .add_event::<EntityBlockCollisionEvent>()
             .insert_resource(BvhResource::new())
-            .add_systems(PreUpdate, (physics_system, rebuild_bvh));
+            .add_systems(PreUpdate, (rebuild_bvh, physics_system).chain());
     }
 }
 
@@ -121,11 +213,9 @@ struct PhysicsQuery {
 }
 
 fn physics_system(
-    // mut bvh: ResMut<BvhResource>,
-    // mut commands: Commands,
+    bvh: Res<BvhResource>,
     time: Res<Time>,
     mut query: Query<PhysicsQuery, Without<Client>>,
-    // mut collidable_entities: Query<(Entity, &Hitbox, &EntityCollisionConfig)>,
     mut entity_entity_collision_writer: EventWriter<EntityEntityCollisionEvent>,
     mut entity_block_collision_writer: EventWriter<EntityBlockCollisionEvent>,
     // TODO: support for multiple layers
@@ -146,7 +236,7 @@ fn physics_system(
     }
 
     enum PhysicsEvent {
-        _EntityEntityCollision(EntityEntityCollisionEvent),
+        EntityEntityCollision(EntityEntityCollisionEvent),
         EntityBlockCollision(EntityBlockCollisionEvent),
     }
 
@@ -170,6 +260,59 @@ fn physics_system(
 
         let _old_velocity = entity.velocity.0;
 
+        // The entity collisions are resolved first, so the blocks can stop the response.
+        if let Some(entity_collision_config) = entity.entity_collision_config {
+            let hitbox = entity_collision_config.collider(entity.hitbox);
+            let velocity_delta = entity.velocity.0 * time.delta_seconds();
+            let swept = EntityCollider::swept(&hitbox, velocity_delta);
+
+            let mut contacts = Vec::new();
+
+            bvh.bvh.query(
+                |aabb| aabb.collides_with_aabb(swept),
+                |other| {
+                    if other.entity == entity.entity {
+                        return None::<()>;
+                    }
+
+                    let relative_velocity = velocity_delta - other.velocity_delta;
+
+                    if let Some(contact) =
+                        entity_contact(&hitbox, &relative_velocity, &other.hitbox)
+                    {
+                        contacts.push((*other, contact));
+                    }
+
+                    None
+                },
+            );
+
+            for (other, contact) in contacts {
+                resolve_entity_collision(
+                    &mut entity.position.0,
+                    &mut entity.velocity.0,
+                    entity_collision_config.response,
+                    &hitbox,
+                    &other,
+                    &contact,
+                    time.delta_seconds(),
+                );
+
+                if !reports_collision(entity.entity, &other) {
+                    continue;
+                }
+
+                let event = EntityEntityCollisionEvent {
+                    entity1: entity.entity,
+                    entity2: other.entity,
+                    normal: contact.normal,
+                    time_of_impact: contact.time_of_impact,
+                };
+
+                tx.send(PhysicsEvent::EntityEntityCollision(event)).unwrap();
+            }
+        }
+
         if let Some(block_collision_config) = entity.block_collision_config {
             let entity_hitbox = block_collision_config
                 .block_collider_hitbox
@@ -312,13 +455,11 @@ fn physics_system(
         }
 
         entity.position.0 += (entity.velocity.0 * time.delta_seconds()).as_dvec3();
-
-        // TODO: entity collision
     });
 
     for event in rx.try_iter() {
         match event {
-            PhysicsEvent::_EntityEntityCollision(event) => {
+            PhysicsEvent::EntityEntityCollision(event) => {
                 entity_entity_collision_writer.send(event);
             }
             PhysicsEvent::EntityBlockCollision(event) => {
@@ -328,30 +469,446 @@ fn physics_system(
     }
 }
 
+/// Collects the colliders of all entities with an [`EntityCollisionConfig`] for the broadphase.
 fn rebuild_bvh(
-    mut _commands: Commands,
-    query: Query<PhysicsQuery, With<EntityCollisionConfig>>,
-    mut _bvh: ResMut<BvhResource>,
+    time: Res<Time>,
+    query: Query<(
+        Entity,
+        &Hitbox,
+        &EntityCollisionConfig,
+        Option<&Velocity>,
+        Has<Client>,
+    )>,
+    mut bvh: ResMut<BvhResource>,
 ) {
-    if query.is_empty() {}
+    let colliders = query
+        .iter()
+        .map(
+            |(entity, hitbox, config, velocity, is_client)| EntityCollider {
+                entity,
+                hitbox: config.collider(hitbox),
+                // Clients move themselves, so they are treated as standing still.
+                velocity_delta: match velocity {
+                    Some(velocity) if !is_client => velocity.0 * time.delta_seconds(),
+                    _ => Vec3::ZERO,
+                },
+                simulated: velocity.is_some() && !is_client,
+                response: config.response,
+            },
+        )
+        .collect::<Vec<_>>();
+
+    bvh.bvh.rebuild(colliders);
+}
 
-    // TODO: custom spatial indexing
+/// A contact between two entity colliders.
+struct EntityContact {
+    /// The normal pointing from the other entity towards the entity.
+    normal: Vec3,
+    time_of_impact: f64,
+    /// How deep the colliders overlap, `0.0` if they only touch during the tick.
+    depth: f64,
+}
+
+/// The narrowphase of two entity colliders.
+///
+/// `velocity` is the movement of `hb1` relative to `hb2` in this tick.
+fn entity_contact(hb1: &Aabb, velocity: &Vec3, hb2: &Aabb) -> Option<EntityContact> {
+    if let Some((normal, depth)) = aabb_overlap(hb1, hb2) {
+        return Some(EntityContact {
+            normal,
+            time_of_impact: 0.0,
+            depth,
+        });
+    }
 
-    // let mut entities: Vec<vek::Aabb<f64>> = Vec::new();
-    // println!("Adding entity to BVH");
-    // for entity in query.iter_mut() {
-    //     let aabb = entity.entity_collision_config.as_ref()
-    //         .unwrap()
-    //         .entity_collider_hitbox
-    //         .unwrap_or(entity.hitbox.get());
+    let collision = swept_aabb_collide(hb1, velocity, hb2)?;
+    let normal = collision.face_direction.to_vec3();
 
-    //     let aabb: vek::Aabb<f64> = vek::Aabb {
-    //         min: vek::Vec3::new(aabb.min().x, aabb.min().y, aabb.min().z),
-    //         max: vek::Vec3::new(aabb.max().x, aabb.max().y, aabb.max().z),
-    //     };
+    if normal == Vec3::ZERO {
+        return None;
+    }
 
-    //     entities.push(aabb);
-    // }
+    Some(EntityContact {
+        normal,
+        time_of_impact: collision.entry_time.clamp(0.0, 1.0),
+        depth: 0.0,
+    })
+}
 
-    // bvh.bvh.rebuild(entities);
+/// If the entity emits the [`EntityEntityCollisionEvent`] of a contact with the other entity.
+///
+/// Both entities detect the collision when they are moved by the physics, only the lower one
+/// reports it.
+fn reports_collision(entity: Entity, other: &EntityCollider) -> bool {
+    !other.simulated || entity < other.entity
+}
+
+/// Applies the [`EntityCollisionResponse`] of a contact to the position and velocity of the
+/// entity.
+///
+/// The other entity is not changed, it resolves the same contact when it is moved itself.
+fn resolve_entity_collision(
+    position: &mut DVec3,
+    velocity: &mut Vec3,
+    response: EntityCollisionResponse,
+    hitbox: &Aabb,
+    other: &EntityCollider,
+    contact: &EntityContact,
+    delta_seconds: f32,
+) {
+    use EntityCollisionResponse::*;
+
+    match (response, other.response) {
+        (Sensor, _) | (_, Sensor) => {}
+        (Push { strength }, _) | (Solid { .. }, Push { strength }) => {
+            if contact.depth > 0.0 {
+                *velocity += push_direction(hitbox, &other.hitbox) * strength * delta_seconds;
+            }
+        }
+        (
+            Solid { restitution, .. },
+            Solid {
+                restitution: other_restitution,
+                ..
+            },
+        ) => {
+            let inverse_mass = response.inverse_mass();
+            let other_inverse_mass = if other.simulated {
+                other.response.inverse_mass()
+            } else {
+                0.0
+            };
+
+            if inverse_mass <= 0.0 {
+                return;
+            }
+
+            // The part of the collision that this entity resolves, the rest is up to the other one.
+            let share = inverse_mass / (inverse_mass + other_inverse_mass);
+
+            let other_velocity = if delta_seconds > 0.0 {
+                other.velocity_delta / delta_seconds
+            } else {
+                Vec3::ZERO
+            };
+            let approach = (*velocity - other_velocity).dot(contact.normal);
+
+            if approach < 0.0 {
+                let restitution = restitution.max(other_restitution).clamp(0.0, 1.0);
+                *velocity -= contact.normal * (1.0 + restitution) * approach * share;
+            }
+
+            *position += (contact.normal * share).as_dvec3() * contact.depth;
+        }
+    }
+}
+
+/// The horizontal direction that pushes the entity away from the other entity (vanilla behavior).
+///
+/// It gets weaker when the centers of the entities are less than a block apart.
+fn push_direction(hb1: &Aabb, hb2: &Aabb) -> Vec3 {
+    let center1 = (hb1.min() + hb1.max()) / 2.0;
+    let center2 = (hb2.min() + hb2.max()) / 2.0;
+
+    let (dx, dz) = (
+        (center1.x - center2.x) as f32,
+        (center1.z - center2.z) as f32,
+    );
+    let distance = dx.abs().max(dz.abs());
+
+    if distance < 0.01 {
+        return Vec3::ZERO;
+    }
+
+    let distance = distance.sqrt();
+    let factor = (1.0 / distance).min(1.0) / distance;
+
+    Vec3::new(dx * factor, 0.0, dz * factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELTA_SECONDS: f32 = 0.05;
+
+    fn block_at(x: f64) -> Aabb {
+        Aabb::new(DVec3::new(x, 0.0, 0.0), DVec3::new(x + 1.0, 1.0, 1.0))
+    }
+
+    fn collider(
+        entity: u32,
+        hitbox: Aabb,
+        velocity: Vec3,
+        response: EntityCollisionResponse,
+    ) -> EntityCollider {
+        EntityCollider {
+            entity: Entity::from_raw(entity),
+            hitbox,
+            velocity_delta: velocity * DELTA_SECONDS,
+            simulated: true,
+            response,
+        }
+    }
+
+    fn solid(mass: f32, restitution: f32) -> EntityCollisionResponse {
+        EntityCollisionResponse::Solid { mass, restitution }
+    }
+
+    fn push() -> EntityCollisionResponse {
+        EntityCollisionConfig::push().response
+    }
+
+    /// Resolves the contact of an entity in `0..1` moving with `velocity` towards `other`.
+    fn resolve(
+        response: EntityCollisionResponse,
+        velocity: Vec3,
+        other: &EntityCollider,
+    ) -> (DVec3, Vec3) {
+        let hitbox = block_at(0.0);
+        let relative_velocity = velocity * DELTA_SECONDS - other.velocity_delta;
+        let contact = entity_contact(&hitbox, &relative_velocity, &other.hitbox).unwrap();
+
+        let mut position = DVec3::ZERO;
+        let mut velocity = velocity;
+
+        resolve_entity_collision(
+            &mut position,
+            &mut velocity,
+            response,
+            &hitbox,
+            other,
+            &contact,
+            DELTA_SECONDS,
+        );
+
+        (position, velocity)
+    }
+
+    #[test]
+    fn overlapping_contact() {
+        let contact =
+            entity_contact(&block_at(0.0), &Vec3::new(0.1, 0.0, 0.0), &block_at(0.75)).unwrap();
+
+        // Already intersecting at the start of the tick.
+        assert_eq!(contact.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert_eq!(contact.time_of_impact, 0.0);
+        assert!(
+            (contact.depth - 0.25).abs() < 1e-9,
+            "depth {}",
+            contact.depth
+        );
+    }
+
+    #[test]
+    fn swept_contact() {
+        let contact =
+            entity_contact(&block_at(0.0), &Vec3::new(0.2, 0.0, 0.0), &block_at(1.05)).unwrap();
+
+        assert_eq!(contact.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!(
+            (contact.time_of_impact - 0.25).abs() < 1e-6,
+            "time of impact {}",
+            contact.time_of_impact
+        );
+        assert_eq!(contact.depth, 0.0);
+
+        // Moving in the same direction as fast as the other entity.
+        assert!(entity_contact(&block_at(0.0), &Vec3::ZERO, &block_at(1.05)).is_none());
+    }
+
+    #[test]
+    fn sensors_go_through_everything() {
+        use EntityCollisionResponse::Sensor;
+
+        let velocity = Vec3::new(2.0, 0.0, 0.0);
+
+        for (response, other_response) in [
+            (Sensor, Sensor),
+            (Sensor, push()),
+            (push(), Sensor),
+            (Sensor, solid(1.0, 0.0)),
+            (solid(1.0, 0.0), Sensor),
+        ] {
+            let other = collider(1, block_at(0.5), Vec3::ZERO, other_response);
+
+            assert_eq!(
+                resolve(response, velocity, &other),
+                (DVec3::ZERO, velocity),
+                "{response:?} against {other_response:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn overlapping_entities_push_each_other() {
+        let strength = VANILLA_PUSH_STRENGTH * DELTA_SECONDS;
+
+        for (response, other_response) in [
+            (push(), push()),
+            (push(), solid(1.0, 0.0)),
+            (solid(1.0, 0.0), push()),
+        ] {
+            let other = collider(1, block_at(0.5), Vec3::ZERO, other_response);
+            let (position, velocity) = resolve(response, Vec3::ZERO, &other);
+
+            // Pushed away from the other entity (in -x) without being moved out of it.
+            assert_eq!(
+                position,
+                DVec3::ZERO,
+                "{response:?} against {other_response:?}"
+            );
+            assert!(
+                (velocity - push_direction(&block_at(0.0), &other.hitbox) * strength).length()
+                    < 1e-6,
+                "{response:?} against {other_response:?}: {velocity}"
+            );
+            assert!(velocity.x < 0.0 && velocity.y == 0.0 && velocity.z == 0.0);
+        }
+    }
+
+    #[test]
+    fn entities_that_only_touch_are_not_pushed() {
+        // Moves into the other entity during the tick, but is not inside it yet.
+        let other = collider(1, block_at(1.05), Vec3::ZERO, push());
+        let (position, velocity) = resolve(push(), Vec3::new(2.0, 0.0, 0.0), &other);
+
+        assert_eq!(position, DVec3::ZERO);
+        assert_eq!(velocity, Vec3::new(2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn push_direction_is_horizontal_and_weaker_up_close() {
+        let hitbox = block_at(0.0);
+
+        // The centers are in the same place.
+        assert_eq!(push_direction(&hitbox, &hitbox), Vec3::ZERO);
+
+        // The push does not depend on the height.
+        let above = Aabb::new(DVec3::new(4.0, 3.0, 0.0), DVec3::new(5.0, 4.0, 1.0));
+        assert_eq!(push_direction(&hitbox, &above), Vec3::new(-1.0, 0.0, 0.0));
+
+        // A quarter block apart pushes with half the strength.
+        assert_eq!(
+            push_direction(&hitbox, &block_at(0.25)),
+            Vec3::new(-0.5, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn solid_entities_with_the_same_mass_share_the_collision() {
+        // Head-on, each resolves half of the overlap and stops.
+        let other = collider(1, block_at(0.9), Vec3::new(-2.0, 0.0, 0.0), solid(1.0, 0.0));
+        let (position, velocity) = resolve(solid(1.0, 0.0), Vec3::new(2.0, 0.0, 0.0), &other);
+
+        assert!(
+            (position - DVec3::new(-0.05, 0.0, 0.0)).length() < 1e-6,
+            "{position}"
+        );
+        assert!(velocity.length() < 1e-6, "{velocity}");
+    }
+
+    #[test]
+    fn the_lighter_entity_resolves_more_of_the_collision() {
+        let other = collider(1, block_at(0.9), Vec3::ZERO, solid(3.0, 0.0));
+        let (position, velocity) = resolve(solid(1.0, 0.0), Vec3::new(2.0, 0.0, 0.0), &other);
+
+        assert!(
+            (position - DVec3::new(-0.075, 0.0, 0.0)).length() < 1e-6,
+            "{position}"
+        );
+        assert!(
+            (velocity - Vec3::new(0.5, 0.0, 0.0)).length() < 1e-6,
+            "{velocity}"
+        );
+    }
+
+    #[test]
+    fn infinite_mass() {
+        let velocity = Vec3::new(2.0, 0.0, 0.0);
+
+        // The entity resolves the whole collision against a wall...
+        let wall = collider(1, block_at(0.9), Vec3::ZERO, solid(f32::INFINITY, 0.0));
+        let (position, stopped) = resolve(solid(1.0, 0.0), velocity, &wall);
+
+        assert!(
+            (position - DVec3::new(-0.1, 0.0, 0.0)).length() < 1e-6,
+            "{position}"
+        );
+        assert!(stopped.length() < 1e-6, "{stopped}");
+
+        // ...and against entities that are not moved by the physics (like clients).
+        let client = EntityCollider {
+            simulated: false,
+            ..collider(1, block_at(0.9), Vec3::ZERO, solid(1.0, 0.0))
+        };
+        assert_eq!(
+            resolve(solid(1.0, 0.0), velocity, &client),
+            (position, stopped)
+        );
+
+        // A wall is not moved by anything.
+        let other = collider(1, block_at(0.9), Vec3::new(-2.0, 0.0, 0.0), solid(1.0, 0.0));
+        assert_eq!(
+            resolve(solid(f32::INFINITY, 0.0), velocity, &other),
+            (DVec3::ZERO, velocity)
+        );
+    }
+
+    #[test]
+    fn restitution() {
+        let velocity = Vec3::new(2.0, 0.0, 0.0);
+
+        // Bounces off a wall with the same speed.
+        let wall = collider(1, block_at(0.9), Vec3::ZERO, solid(f32::INFINITY, 1.0));
+        let (_, bounced) = resolve(solid(1.0, 0.0), velocity, &wall);
+        assert!((bounced + velocity).length() < 1e-6, "{bounced}");
+
+        // Entities with the same mass exchange their velocities, the higher restitution is used.
+        let other = collider(1, block_at(0.9), Vec3::new(-2.0, 0.0, 0.0), solid(1.0, 0.0));
+        let (_, exchanged) = resolve(solid(1.0, 1.0), velocity, &other);
+        assert!((exchanged + velocity).length() < 1e-6, "{exchanged}");
+
+        // Half of the speed is kept.
+        let wall = collider(1, block_at(0.9), Vec3::ZERO, solid(f32::INFINITY, 0.5));
+        let (_, bounced) = resolve(solid(1.0, 0.0), velocity, &wall);
+        assert!((bounced + velocity / 2.0).length() < 1e-6, "{bounced}");
+    }
+
+    #[test]
+    fn separating_entities_keep_their_velocity() {
+        let velocity = Vec3::new(-2.0, 0.0, 0.0);
+        let wall = collider(1, block_at(0.9), Vec3::ZERO, solid(f32::INFINITY, 1.0));
+        let (position, kept) = resolve(solid(1.0, 0.0), velocity, &wall);
+
+        // Still moved out of the other entity.
+        assert!(
+            (position - DVec3::new(-0.1, 0.0, 0.0)).length() < 1e-6,
+            "{position}"
+        );
+        assert_eq!(kept, velocity);
+    }
+
+    #[test]
+    fn collisions_are_reported_once_per_pair() {
+        let entity = Entity::from_raw(1);
+        let lower = collider(0, block_at(0.5), Vec3::ZERO, push());
+        let higher = collider(2, block_at(0.5), Vec3::ZERO, push());
+
+        // The lower entity reports the collisions of simulated pairs.
+        assert!(!reports_collision(entity, &lower));
+        assert!(reports_collision(entity, &higher));
+        assert!(reports_collision(
+            lower.entity,
+            &collider(1, block_at(0.5), Vec3::ZERO, push())
+        ));
+
+        // Only the entity detects the collision if the other one is not moved by the physics.
+        let client = EntityCollider {
+            simulated: false,
+            ..lower
+        };
+        assert!(reports_collision(entity, &client));
+    }
 }
@@ -19,12 +19,57 @@ pub struct CollisionNormals {
     pub z: Option<bool>,
 }
 
+impl CollisionNormals {
+    /// The normal as a vector, with `0.0` on the axes without a collision.
+    pub fn to_vec3(&self) -> Vec3 {
+        fn axis(normal: Option<bool>) -> f32 {
+            match normal {
+                Some(true) => 1.0,
+                Some(false) => -1.0,
+                None => 0.0,
+            }
+        }
+
+        Vec3::new(axis(self.x), axis(self.y), axis(self.z))
+    }
+}
+
 pub struct CollisionResult {
     pub entry_time: f64,
     /// The normals of the
     pub face_direction: CollisionNormals,
 }
 
+/// The axis of the smallest overlap of two hitboxes that are already intersecting.
+///
+/// # Returns
+///
+/// * The normal pointing from `hb2` towards `hb1` and the depth of the overlap, or `None` if the
+///   hitboxes do not intersect.
+pub fn aabb_overlap(hb1: &Aabb, hb2: &Aabb) -> Option<(Vec3, f64)> {
+    let mut overlap = None;
+
+    for axis in 0..3 {
+        let depth = hb1.max()[axis].min(hb2.max()[axis]) - hb1.min()[axis].max(hb2.min()[axis]);
+
+        if depth <= 0.0 {
+            return None;
+        }
+
+        if !overlap.is_some_and(|(_, min_depth)| depth >= min_depth) {
+            let center1 = hb1.min()[axis] + hb1.max()[axis];
+            let center2 = hb2.min()[axis] + hb2.max()[axis];
+
+            let mut normal = Vec3::ZERO;
+            normal[axis] = if center1 >= center2 { 1.0 } else { -1.0 };
+
+            overlap = Some((normal, depth));
+        }
+    }
+
+    overlap
+}
+
 /// Performs a swept AABB collision
 ///
 /// # Arguments
@@ -176,3 +221,116 @@ pub fn swept_aabb_collide(hb1: &Aabb, velocity: &Vec3, hb2: &Aabb) -> Option<Col
         },
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use valence::math::DVec3;
+
+    use super::*;
+
+    fn cube(x: f64, y: f64, z: f64) -> Aabb {
+        Aabb::new(DVec3::new(x, y, z), DVec3::new(x + 1.0, y + 1.0, z + 1.0))
+    }
+
+    fn assert_overlap(hb1: &Aabb, hb2: &Aabb, normal: Vec3, depth: f64) {
+        let (actual_normal, actual_depth) = aabb_overlap(hb1, hb2).unwrap();
+
+        assert_eq!(actual_normal, normal);
+        assert!(
+            (actual_depth - depth).abs() < 1e-9,
+            "depth {actual_depth}, expected {depth}"
+        );
+    }
+
+    #[test]
+    fn overlap_uses_the_shallowest_axis() {
+        let hitbox = cube(0.0, 0.0, 0.0);
+
+        assert_overlap(
+            &hitbox,
+            &cube(0.8, 0.5, 0.5),
+            Vec3::new(-1.0, 0.0, 0.0),
+            0.2,
+        );
+        assert_overlap(
+            &hitbox,
+            &cube(0.5, -0.9, 0.5),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.1,
+        );
+        assert_overlap(
+            &hitbox,
+            &cube(0.5, 0.5, 0.7),
+            Vec3::new(0.0, 0.0, -1.0),
+            0.3,
+        );
+    }
+
+    #[test]
+    fn overlap_normal_points_towards_the_first_hitbox() {
+        let (hb1, hb2) = (cube(0.0, 0.0, 0.0), cube(0.8, 0.5, 0.5));
+
+        assert_overlap(&hb2, &hb1, Vec3::new(1.0, 0.0, 0.0), 0.2);
+        assert_eq!(
+            aabb_overlap(&hb1, &hb2).unwrap().0,
+            -aabb_overlap(&hb2, &hb1).unwrap().0
+        );
+    }
+
+    #[test]
+    fn touching_hitboxes_do_not_overlap() {
+        let hitbox = cube(0.0, 0.0, 0.0);
+
+        assert!(aabb_overlap(&hitbox, &cube(1.0, 0.0, 0.0)).is_none());
+        assert!(aabb_overlap(&hitbox, &cube(0.0, -1.0, 0.0)).is_none());
+        assert!(aabb_overlap(&hitbox, &cube(0.5, 0.5, 2.0)).is_none());
+    }
+
+    #[test]
+    fn swept_time_of_impact() {
+        let hitbox = cube(0.0, 0.0, 0.0);
+
+        let collision =
+            swept_aabb_collide(&hitbox, &Vec3::new(1.0, 0.0, 0.0), &cube(1.5, 0.0, 0.0)).unwrap();
+        assert_eq!(collision.entry_time, 0.5);
+        assert_eq!(
+            collision.face_direction.to_vec3(),
+            Vec3::new(-1.0, 0.0, 0.0)
+        );
+
+        let collision =
+            swept_aabb_collide(&hitbox, &Vec3::new(0.0, -2.0, 0.0), &cube(0.0, -1.5, 0.0)).unwrap();
+        assert_eq!(collision.entry_time, 0.25);
+        assert_eq!(collision.face_direction.to_vec3(), Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn swept_uses_the_last_axis_that_starts_touching() {
+        // Overlaps on y after a quarter of the tick, but only touches when x overlaps too.
+        let collision = swept_aabb_collide(
+            &cube(0.0, 0.0, 0.0),
+            &Vec3::new(1.0, 1.0, 0.0),
+            &cube(1.5, 1.25, 0.0),
+        )
+        .unwrap();
+
+        assert_eq!(collision.entry_time, 0.5);
+        assert_eq!(
+            collision.face_direction.to_vec3(),
+            Vec3::new(-1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn swept_misses() {
+        let hitbox = cube(0.0, 0.0, 0.0);
+        let velocity = Vec3::new(1.0, 0.0, 0.0);
+
+        // Too far away to reach in this tick.
+        assert!(swept_aabb_collide(&hitbox, &velocity, &cube(3.0, 0.0, 0.0)).is_none());
+        // Moving away.
+        assert!(swept_aabb_collide(&hitbox, &-velocity, &cube(1.5, 0.0, 0.0)).is_none());
+        // Passing by.
+        assert!(swept_aabb_collide(&hitbox, &velocity, &cube(1.5, 0.0, 2.0)).is_none());
+    }
+}
@@ -0,0 +1,548 @@
+use std::{fmt, path::Path};
+
+use utils::damage::DamageType;
+use valence::prelude::*;
+
+/// The first bytes of a replay file.
+const MAGIC: &[u8; 4] = b"VRPL";
+/// The version of the file format, files of other versions can not be loaded.
+const VERSION: u8 = 1;
+/// Marks a missing entity id (e.g. damage without an attacker).
+const NO_ID: u32 = u32::MAX;
+
+/// A recorded match, see [`ReplayRecorder`](crate::ReplayRecorder).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    /// The ticks in which something happened, ordered by the tick.
+    pub frames: Vec<ReplayFrame>,
+}
+
+/// Everything that happened in a tick.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayFrame {
+    /// The ticks since the recording started.
+    pub tick: u32,
+    pub records: Vec<ReplayRecord>,
+}
+
+/// Something that happened in a recorded match.
+///
+/// The entities are referenced by their replay id, which is assigned when they are spawned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayRecord {
+    Spawn {
+        id: u32,
+        kind: EntityKind,
+        /// The username for players, empty for other entities.
+        name: String,
+        position: DVec3,
+        yaw: f32,
+        pitch: f32,
+    },
+    Despawn {
+        id: u32,
+    },
+    Move {
+        id: u32,
+        position: DVec3,
+        yaw: f32,
+        pitch: f32,
+    },
+    Swing {
+        id: u32,
+        off_hand: bool,
+    },
+    /// An attack hit the victim, see [`CombatHitEvent`](combat::CombatHitEvent).
+    Attack {
+        attacker: u32,
+        victim: u32,
+        damage: f32,
+        critical: bool,
+        sweep: bool,
+        knockback: Vec3,
+    },
+    Damage {
+        victim: u32,
+        attacker: Option<u32>,
+        damage: f32,
+        damage_type: DamageType,
+    },
+    /// An equipment slot changed, only the item and count are recorded (no NBT).
+    Equipment {
+        id: u32,
+        /// The slot index of [`Equipment`].
+        slot: u8,
+        item: ItemKind,
+        count: u8,
+    },
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    /// The file is not a replay or it is damaged.
+    InvalidFormat,
+    /// The replay was recorded with another version of the format.
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "failed to access replay: {err}"),
+            ReplayError::InvalidFormat => write!(f, "invalid replay file"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay version {version}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    /// The number of recorded ticks.
+    pub fn duration_ticks(&self) -> u32 {
+        self.frames.last().map_or(0, |frame| frame.tick + 1)
+    }
+
+    /// Write the replay to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        std::fs::write(path, self.to_bytes()).map_err(ReplayError::Io)
+    }
+
+    /// Read a replay from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let bytes = std::fs::read(path).map_err(ReplayError::Io)?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    /// Encode the replay in the binary format (little endian).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+
+        writer.bytes(MAGIC);
+        writer.u8(VERSION);
+        writer.u32(self.frames.len() as u32);
+
+        for frame in &self.frames {
+            writer.u32(frame.tick);
+            writer.u32(frame.records.len() as u32);
+
+            for record in &frame.records {
+                write_record(&mut writer, record);
+            }
+        }
+
+        writer.0
+    }
+
+    /// Decode a replay from the binary format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = Reader { bytes, position: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ReplayError::InvalidFormat);
+        }
+
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let frame_count = reader.u32()?;
+        let mut frames = Vec::new();
+
+        for _ in 0..frame_count {
+            let tick = reader.u32()?;
+            let record_count = reader.u32()?;
+            let mut records = Vec::new();
+
+            for _ in 0..record_count {
+                records.push(read_record(&mut reader)?);
+            }
+
+            frames.push(ReplayFrame { tick, records });
+        }
+
+        Ok(Self { frames })
+    }
+}
+
+fn write_record(writer: &mut Writer, record: &ReplayRecord) {
+    match record {
+        ReplayRecord::Spawn {
+            id,
+            kind,
+            name,
+            position,
+            yaw,
+            pitch,
+        } => {
+            writer.u8(0);
+            writer.u32(*id);
+            writer.i32(kind.get());
+            writer.string(name);
+            writer.dvec3(*position);
+            writer.f32(*yaw);
+            writer.f32(*pitch);
+        }
+        ReplayRecord::Despawn { id } => {
+            writer.u8(1);
+            writer.u32(*id);
+        }
+        ReplayRecord::Move {
+            id,
+            position,
+            yaw,
+            pitch,
+        } => {
+            writer.u8(2);
+            writer.u32(*id);
+            writer.dvec3(*position);
+            writer.f32(*yaw);
+            writer.f32(*pitch);
+        }
+        ReplayRecord::Swing { id, off_hand } => {
+            writer.u8(3);
+            writer.u32(*id);
+            writer.u8(*off_hand as u8);
+        }
+        ReplayRecord::Attack {
+            attacker,
+            victim,
+            damage,
+            critical,
+            sweep,
+            knockback,
+        } => {
+            writer.u8(4);
+            writer.u32(*attacker);
+            writer.u32(*victim);
+            writer.f32(*damage);
+            writer.u8(*critical as u8 | (*sweep as u8) << 1);
+            writer.f32(knockback.x);
+            writer.f32(knockback.y);
+            writer.f32(knockback.z);
+        }
+        ReplayRecord::Damage {
+            victim,
+            attacker,
+            damage,
+            damage_type,
+        } => {
+            writer.u8(5);
+            writer.u32(*victim);
+            writer.u32(attacker.unwrap_or(NO_ID));
+            writer.f32(*damage);
+            writer.u8(damage_type_id(*damage_type));
+        }
+        ReplayRecord::Equipment {
+            id,
+            slot,
+            item,
+            count,
+        } => {
+            writer.u8(6);
+            writer.u32(*id);
+            writer.u8(*slot);
+            writer.u16(item.to_raw());
+            writer.u8(*count);
+        }
+    }
+}
+
+fn read_record(reader: &mut Reader) -> Result<ReplayRecord, ReplayError> {
+    Ok(match reader.u8()? {
+        0 => ReplayRecord::Spawn {
+            id: reader.u32()?,
+            kind: EntityKind::new(reader.i32()?),
+            name: reader.string()?,
+            position: reader.dvec3()?,
+            yaw: reader.f32()?,
+            pitch: reader.f32()?,
+        },
+        1 => ReplayRecord::Despawn { id: reader.u32()? },
+        2 => ReplayRecord::Move {
+            id: reader.u32()?,
+            position: reader.dvec3()?,
+            yaw: reader.f32()?,
+            pitch: reader.f32()?,
+        },
+        3 => ReplayRecord::Swing {
+            id: reader.u32()?,
+            off_hand: reader.u8()? != 0,
+        },
+        4 => {
+            let attacker = reader.u32()?;
+            let victim = reader.u32()?;
+            let damage = reader.f32()?;
+            let flags = reader.u8()?;
+
+            ReplayRecord::Attack {
+                attacker,
+                victim,
+                damage,
+                critical: flags & 1 != 0,
+                sweep: flags & 2 != 0,
+                knockback: Vec3::new(reader.f32()?, reader.f32()?, reader.f32()?),
+            }
+        }
+        5 => ReplayRecord::Damage {
+            victim: reader.u32()?,
+            attacker: Some(reader.u32()?).filter(|id| *id != NO_ID),
+            damage: reader.f32()?,
+            damage_type: damage_type_from_id(reader.u8()?).ok_or(ReplayError::InvalidFormat)?,
+        },
+        6 => ReplayRecord::Equipment {
+            id: reader.u32()?,
+            slot: reader.u8()?,
+            item: ItemKind::from_raw(reader.u16()?).ok_or(ReplayError::InvalidFormat)?,
+            count: reader.u8()?,
+        },
+        _ => return Err(ReplayError::InvalidFormat),
+    })
+}
+
+/// The damage types in the order of their ids, new types have to be appended.
+const DAMAGE_TYPES: [DamageType; 16] = [
+    DamageType::Generic,
+    DamageType::EntityAttack,
+    DamageType::Projectile,
+    DamageType::Explosion,
+    DamageType::Fall,
+    DamageType::OnFire,
+    DamageType::Void,
+    DamageType::WorldBorder,
+    DamageType::Lava,
+    DamageType::InFire,
+    DamageType::Cactus,
+    DamageType::HotFloor,
+    DamageType::SweetBerryBush,
+    DamageType::InWall,
+    DamageType::Drowning,
+    DamageType::Thorns,
+];
+
+fn damage_type_id(damage_type: DamageType) -> u8 {
+    DAMAGE_TYPES
+        .iter()
+        .position(|other| *other == damage_type)
+        .unwrap_or(0) as u8
+}
+
+fn damage_type_from_id(id: u8) -> Option<DamageType> {
+    DAMAGE_TYPES.get(id as usize).copied()
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn dvec3(&mut self, value: DVec3) {
+        for component in value.to_array() {
+            self.bytes(&component.to_le_bytes());
+        }
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u16(value.len() as u16);
+        self.bytes(value.as_bytes());
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ReplayError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + len)
+            .ok_or(ReplayError::InvalidFormat)?;
+
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ReplayError> {
+        Ok(self.take(N)?.try_into().expect("the slice has N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ReplayError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, ReplayError> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, ReplayError> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn dvec3(&mut self) -> Result<DVec3, ReplayError> {
+        Ok(DVec3::new(
+            f64::from_le_bytes(self.array()?),
+            f64::from_le_bytes(self.array()?),
+            f64::from_le_bytes(self.array()?),
+        ))
+    }
+
+    fn string(&mut self) -> Result<String, ReplayError> {
+        let len = self.u16()? as usize;
+
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| ReplayError::InvalidFormat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A replay with every kind of record.
+    fn replay() -> Replay {
+        Replay {
+            frames: vec![
+                ReplayFrame {
+                    tick: 0,
+                    records: vec![
+                        ReplayRecord::Spawn {
+                            id: 0,
+                            kind: EntityKind::PLAYER,
+                            name: "Steve".to_owned(),
+                            position: DVec3::new(0.5, 64.0, -12.25),
+                            yaw: 90.0,
+                            pitch: -15.5,
+                        },
+                        ReplayRecord::Spawn {
+                            id: 1,
+                            kind: EntityKind::ZOMBIE,
+                            name: String::new(),
+                            position: DVec3::new(3.0, 64.0, -12.0),
+                            yaw: -90.0,
+                            pitch: 0.0,
+                        },
+                        ReplayRecord::Equipment {
+                            id: 0,
+                            slot: 0,
+                            item: ItemKind::DiamondSword,
+                            count: 1,
+                        },
+                    ],
+                },
+                ReplayFrame {
+                    tick: 7,
+                    records: vec![
+                        ReplayRecord::Move {
+                            id: 0,
+                            position: DVec3::new(1.5, 64.42, -12.0),
+                            yaw: 85.0,
+                            pitch: -10.0,
+                        },
+                        ReplayRecord::Swing {
+                            id: 0,
+                            off_hand: false,
+                        },
+                        ReplayRecord::Attack {
+                            attacker: 0,
+                            victim: 1,
+                            damage: 10.5,
+                            critical: true,
+                            sweep: false,
+                            knockback: Vec3::new(0.0, 7.2, 8.0),
+                        },
+                        ReplayRecord::Damage {
+                            victim: 1,
+                            attacker: Some(0),
+                            damage: 10.5,
+                            damage_type: DamageType::EntityAttack,
+                        },
+                        ReplayRecord::Damage {
+                            victim: 0,
+                            attacker: None,
+                            damage: 4.0,
+                            damage_type: DamageType::Thorns,
+                        },
+                    ],
+                },
+                ReplayFrame {
+                    tick: 40,
+                    records: vec![ReplayRecord::Despawn { id: 1 }],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = replay();
+
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()).unwrap(), replay);
+    }
+
+    #[test]
+    fn empty_round_trip() {
+        let replay = Replay::default();
+
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()).unwrap(), replay);
+    }
+
+    #[test]
+    fn truncated_replay_is_invalid() {
+        let bytes = replay().to_bytes();
+
+        for len in [0, 3, MAGIC.len() + 1, bytes.len() - 1] {
+            assert!(matches!(
+                Replay::from_bytes(&bytes[..len]),
+                Err(ReplayError::InvalidFormat)
+            ));
+        }
+    }
+
+    #[test]
+    fn other_version_is_unsupported() {
+        let mut bytes = replay().to_bytes();
+        bytes[MAGIC.len()] = VERSION + 1;
+
+        assert!(matches!(
+            Replay::from_bytes(&bytes),
+            Err(ReplayError::UnsupportedVersion(version)) if version == VERSION + 1
+        ));
+    }
+}
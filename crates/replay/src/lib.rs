@@ -0,0 +1,28 @@
+//! Records fights into compact binary replays and plays them back with NPCs.
+//!
+//! Add a [`ReplayRecorder`] to a layer to record it, save the [`Replay`] with [`Replay::save`]
+//! and spawn a [`ReplayPlayback`] to show it to the clients in a layer.
+
+pub mod format;
+pub mod playback;
+pub mod recorder;
+
+use valence::prelude::*;
+
+pub use format::{Replay, ReplayError, ReplayFrame, ReplayRecord};
+pub use playback::{ReplayFinishedEvent, ReplayNpc, ReplayPlayback};
+pub use recorder::ReplayRecorder;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ReplayFinishedEvent>()
+            .add_systems(
+                Update,
+                (playback::play_replays, playback::despawn_orphaned_npcs).chain(),
+            )
+            // After the combat and movement of the tick.
+            .add_systems(PostUpdate, recorder::record_replays);
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+
+use valence::{
+    entity::{
+        player::PlayerEntityBundle, skeleton::SkeletonEntityBundle, zombie::ZombieEntityBundle,
+        EntityAnimation, EntityAnimations, EntityId,
+    },
+    player_list::{Listed, PlayerListEntryBundle},
+    prelude::*,
+    protocol::{packets::play::EntityDamageS2c, sound::SoundCategory, Sound, VarInt, WritePacket},
+    Layer,
+};
+
+use crate::format::{Replay, ReplayRecord};
+
+/// Plays a [`Replay`] in a layer, spawn an entity with this component to start the playback.
+///
+/// The recorded entities are spawned as NPCs ([`ReplayNpc`]) in the layer, so clients that view
+/// the layer (e.g. spectators) see the recorded fights. Players are spawned as players, other
+/// entities as zombies (or skeletons). The NPCs do not take part in combat.
+///
+/// The playback entity is despawned when the replay ended, despawn it to stop the playback.
+#[derive(Component, Debug)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    layer: Entity,
+    tick: u32,
+    next_frame: usize,
+    /// The playback does not advance while paused.
+    pub paused: bool,
+    /// The NPCs of the recorded entities by their replay id.
+    npcs: HashMap<u32, Entity>,
+    /// The player list entries of the player NPCs by their replay id.
+    list_entries: HashMap<u32, Entity>,
+    /// The equipment of the NPCs, it is replaced as a whole when a slot changes.
+    equipment: HashMap<u32, Equipment>,
+}
+
+impl ReplayPlayback {
+    /// Plays the replay in the layer (an entity with a [`ChunkLayer`] and an [`EntityLayer`]).
+    pub fn new(replay: Replay, layer: Entity) -> Self {
+        Self {
+            replay,
+            layer,
+            tick: 0,
+            next_frame: 0,
+            paused: false,
+            npcs: HashMap::new(),
+            list_entries: HashMap::new(),
+            equipment: HashMap::new(),
+        }
+    }
+
+    /// The current tick of the playback.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// The number of ticks of the replay.
+    pub fn duration_ticks(&self) -> u32 {
+        self.replay.duration_ticks()
+    }
+}
+
+/// An entity that was spawned by a [`ReplayPlayback`].
+#[derive(Component, Debug)]
+pub struct ReplayNpc {
+    /// The entity with the [`ReplayPlayback`].
+    pub playback: Entity,
+    /// The replay id of the recorded entity.
+    pub id: u32,
+}
+
+/// An event that will be fired when a [`ReplayPlayback`] reached the end of the replay.
+#[derive(Event)]
+pub struct ReplayFinishedEvent {
+    pub playback: Entity,
+}
+
+/// Spawns the NPC of a recorded entity, returns the NPC and the player list entry of players.
+#[allow(clippy::too_many_arguments)]
+fn spawn_npc(
+    commands: &mut Commands,
+    playback_ent: Entity,
+    layer: Entity,
+    id: u32,
+    kind: EntityKind,
+    name: &str,
+    position: DVec3,
+    look: Look,
+) -> (Entity, Option<Entity>) {
+    let npc = ReplayNpc {
+        playback: playback_ent,
+        id,
+    };
+    let position = Position(position);
+    let head_yaw = HeadYaw(look.yaw);
+    let layer = EntityLayerId(layer);
+
+    if kind == EntityKind::PLAYER {
+        // Clients only show players that are in the player list.
+        let uuid = UniqueId(Uuid::from_u64_pair(playback_ent.to_bits(), u64::from(id)));
+
+        let list_entry = commands
+            .spawn((
+                PlayerListEntryBundle {
+                    uuid,
+                    username: Username(name.to_owned()),
+                    listed: Listed(false),
+                    ..Default::default()
+                },
+                ReplayNpc {
+                    playback: playback_ent,
+                    id,
+                },
+            ))
+            .id();
+
+        let player = commands
+            .spawn(PlayerEntityBundle {
+                uuid,
+                layer,
+                position,
+                look,
+                head_yaw,
+                ..Default::default()
+            })
+            .insert(npc)
+            .id();
+
+        return (player, Some(list_entry));
+    }
+
+    if kind == EntityKind::SKELETON {
+        let skeleton = commands
+            .spawn(SkeletonEntityBundle {
+                layer,
+                position,
+                look,
+                head_yaw,
+                ..Default::default()
+            })
+            .insert(npc)
+            .id();
+
+        return (skeleton, None);
+    }
+
+    let zombie = commands
+        .spawn(ZombieEntityBundle {
+            layer,
+            position,
+            look,
+            head_yaw,
+            ..Default::default()
+        })
+        .insert(npc)
+        .id();
+
+    (zombie, None)
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn play_replays(
+    mut commands: Commands,
+    mut playbacks: Query<(Entity, &mut ReplayPlayback)>,
+    mut npcs: Query<
+        (
+            &mut Position,
+            &mut Look,
+            &mut HeadYaw,
+            &mut EntityAnimations,
+            &EntityId,
+        ),
+        With<ReplayNpc>,
+    >,
+    mut layers: Query<&mut ChunkLayer>,
+    mut finished_writer: EventWriter<ReplayFinishedEvent>,
+) {
+    for (playback_ent, mut playback) in playbacks.iter_mut() {
+        if playback.paused {
+            continue;
+        }
+
+        let playback = &mut *playback;
+
+        while let Some(frame) = playback.replay.frames.get(playback.next_frame) {
+            if frame.tick > playback.tick {
+                break;
+            }
+
+            playback.next_frame += 1;
+
+            for record in &frame.records {
+                match *record {
+                    ReplayRecord::Spawn {
+                        id,
+                        kind,
+                        ref name,
+                        position,
+                        yaw,
+                        pitch,
+                    } => {
+                        let (npc, list_entry) = spawn_npc(
+                            &mut commands,
+                            playback_ent,
+                            playback.layer,
+                            id,
+                            kind,
+                            name,
+                            position,
+                            Look { yaw, pitch },
+                        );
+
+                        playback.npcs.insert(id, npc);
+                        if let Some(list_entry) = list_entry {
+                            playback.list_entries.insert(id, list_entry);
+                        }
+                        playback.equipment.insert(id, Equipment::default());
+                    }
+                    ReplayRecord::Despawn { id } => {
+                        if let Some(npc) = playback.npcs.remove(&id) {
+                            commands.entity(npc).despawn();
+                        }
+
+                        // Otherwise the player stays in the tab list of the viewers.
+                        if let Some(list_entry) = playback.list_entries.remove(&id) {
+                            commands.entity(list_entry).despawn();
+                        }
+
+                        playback.equipment.remove(&id);
+                    }
+                    ReplayRecord::Move {
+                        id,
+                        position,
+                        yaw,
+                        pitch,
+                    } => {
+                        let Some(Ok((mut npc_position, mut look, mut head_yaw, _, _))) =
+                            playback.npcs.get(&id).map(|npc| npcs.get_mut(*npc))
+                        else {
+                            continue;
+                        };
+
+                        npc_position.0 = position;
+                        look.yaw = yaw;
+                        look.pitch = pitch;
+                        head_yaw.0 = yaw;
+                    }
+                    ReplayRecord::Swing { id, off_hand } => {
+                        let Some(Ok((_, _, _, mut animations, _))) =
+                            playback.npcs.get(&id).map(|npc| npcs.get_mut(*npc))
+                        else {
+                            continue;
+                        };
+
+                        animations.trigger(if off_hand {
+                            EntityAnimation::SwingOffHand
+                        } else {
+                            EntityAnimation::SwingMainHand
+                        });
+                    }
+                    ReplayRecord::Attack {
+                        victim, critical, ..
+                    } => {
+                        if !critical {
+                            continue;
+                        }
+
+                        if let Some(Ok((_, _, _, mut animations, _))) =
+                            playback.npcs.get(&victim).map(|npc| npcs.get_mut(*npc))
+                        {
+                            animations.trigger(EntityAnimation::CriticalEffect);
+                        }
+                    }
+                    ReplayRecord::Damage { victim, .. } => {
+                        let Some(Ok((position, _, _, _, entity_id))) =
+                            playback.npcs.get(&victim).map(|npc| npcs.get_mut(*npc))
+                        else {
+                            continue;
+                        };
+
+                        let Ok(mut layer) = layers.get_mut(playback.layer) else {
+                            continue;
+                        };
+
+                        let entity_id: VarInt = entity_id.get().into();
+
+                        layer
+                            .view_writer(position.0)
+                            .write_packet(&EntityDamageS2c {
+                                entity_id,
+                                source_type_id: 1.into(),
+                                source_cause_id: 0.into(),
+                                source_direct_id: 0.into(),
+                                source_pos: Some(position.0),
+                            });
+
+                        layer.play_sound(
+                            Sound::EntityPlayerHurt,
+                            SoundCategory::Player,
+                            position.0,
+                            1.0,
+                            1.0,
+                        );
+                    }
+                    ReplayRecord::Equipment {
+                        id,
+                        slot,
+                        item,
+                        count,
+                    } => {
+                        let (Some(&npc), Some(equipment)) =
+                            (playback.npcs.get(&id), playback.equipment.get_mut(&id))
+                        else {
+                            continue;
+                        };
+
+                        equipment.set_slot(slot, ItemStack::new(item, count as i8, None));
+
+                        // The NPC may have been spawned this tick, so the component is replaced.
+                        commands.entity(npc).insert(equipment.clone());
+                    }
+                }
+            }
+        }
+
+        playback.tick += 1;
+
+        if playback.next_frame >= playback.replay.frames.len() {
+            commands.entity(playback_ent).despawn();
+            finished_writer.send(ReplayFinishedEvent {
+                playback: playback_ent,
+            });
+        }
+    }
+}
+
+/// Despawns the NPCs of playbacks that ended or were despawned.
+pub(crate) fn despawn_orphaned_npcs(
+    mut commands: Commands,
+    npcs: Query<(Entity, &ReplayNpc)>,
+    playbacks: Query<(), With<ReplayPlayback>>,
+) {
+    for (entity, npc) in npcs.iter() {
+        if playbacks.get(npc.playback).is_err() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
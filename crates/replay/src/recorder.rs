@@ -0,0 +1,215 @@
+use std::collections::{HashMap, HashSet};
+
+use combat::{CombatHitEvent, CombatState};
+use utils::damage::DamageEvent;
+use valence::{hand_swing::HandSwingEvent, prelude::*, protocol::Hand};
+
+use crate::format::{Replay, ReplayFrame, ReplayRecord};
+
+/// The number of equipment slots, see [`Equipment`].
+const EQUIPMENT_SLOTS: usize = 6;
+
+/// Records the fights in a layer, add it to the layer entity to start recording.
+///
+/// All entities with a [`CombatState`] in the layer are recorded every tick: their movement,
+/// swings, attacks, damage and equipment. Movement caused by physics or knockback is recorded
+/// through the position, like the movement of the players.
+#[derive(Component, Debug, Default)]
+pub struct ReplayRecorder {
+    replay: Replay,
+    tick: u32,
+    next_id: u32,
+    /// The replay ids of the recorded entities.
+    ids: HashMap<Entity, u32>,
+    /// The last recorded equipment (item and count) of the entities.
+    equipment: HashMap<Entity, [(ItemKind, u8); EQUIPMENT_SLOTS]>,
+}
+
+impl ReplayRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of ticks recorded so far.
+    pub fn ticks(&self) -> u32 {
+        self.tick
+    }
+
+    /// Takes the replay recorded so far and starts a new one.
+    ///
+    /// Entities that are still in the layer are spawned again at the start of the new replay.
+    pub fn take_replay(&mut self) -> Replay {
+        let replay = std::mem::take(&mut self.replay);
+        *self = Self::default();
+
+        replay
+    }
+}
+
+fn equipment_items(equipment: Option<&Equipment>) -> [(ItemKind, u8); EQUIPMENT_SLOTS] {
+    std::array::from_fn(|slot| {
+        equipment.map_or((ItemKind::Air, 0), |equipment| {
+            let stack = equipment.slot(slot as u8);
+
+            (stack.item, stack.count as u8)
+        })
+    })
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn record_replays(
+    mut recorders: Query<(Entity, &mut ReplayRecorder)>,
+    entities: Query<
+        (
+            Entity,
+            &EntityLayerId,
+            &EntityKind,
+            Ref<Position>,
+            Ref<Look>,
+            Option<&Username>,
+            Option<&Equipment>,
+        ),
+        With<CombatState>,
+    >,
+    mut swing_events: EventReader<HandSwingEvent>,
+    mut hit_events: EventReader<CombatHitEvent>,
+    mut damage_events: EventReader<DamageEvent>,
+) {
+    // The events are read once and shared by all recorders.
+    let swings: Vec<_> = swing_events
+        .read()
+        .map(|event| (event.client, event.hand == Hand::Off))
+        .collect();
+    let hits: Vec<_> = hit_events.read().copied().collect();
+    let damages: Vec<_> = damage_events
+        .read()
+        .map(|event| {
+            (
+                event.victim,
+                event.attacker,
+                event.damage,
+                event.damage_type,
+            )
+        })
+        .collect();
+
+    for (layer, mut recorder) in recorders.iter_mut() {
+        let recorder = &mut *recorder;
+        let mut records = Vec::new();
+        let mut present = HashSet::new();
+
+        for (entity, layer_id, kind, position, look, username, equipment) in entities.iter() {
+            if layer_id.0 != layer {
+                continue;
+            }
+
+            present.insert(entity);
+
+            let id = match recorder.ids.get(&entity) {
+                Some(&id) => {
+                    if position.is_changed() || look.is_changed() {
+                        records.push(ReplayRecord::Move {
+                            id,
+                            position: position.0,
+                            yaw: look.yaw,
+                            pitch: look.pitch,
+                        });
+                    }
+
+                    id
+                }
+                None => {
+                    let id = recorder.next_id;
+                    recorder.next_id += 1;
+                    recorder.ids.insert(entity, id);
+
+                    records.push(ReplayRecord::Spawn {
+                        id,
+                        kind: *kind,
+                        name: username.map_or_else(String::new, |name| name.0.clone()),
+                        position: position.0,
+                        yaw: look.yaw,
+                        pitch: look.pitch,
+                    });
+
+                    id
+                }
+            };
+
+            let items = equipment_items(equipment);
+            let last_items = recorder
+                .equipment
+                .insert(entity, items)
+                .unwrap_or([(ItemKind::Air, 0); EQUIPMENT_SLOTS]);
+
+            for (slot, (&(item, count), last)) in items.iter().zip(last_items.iter()).enumerate() {
+                if (item, count) != *last {
+                    records.push(ReplayRecord::Equipment {
+                        id,
+                        slot: slot as u8,
+                        item,
+                        count,
+                    });
+                }
+            }
+        }
+
+        recorder.ids.retain(|entity, id| {
+            if present.contains(entity) {
+                return true;
+            }
+
+            records.push(ReplayRecord::Despawn { id: *id });
+            false
+        });
+        recorder
+            .equipment
+            .retain(|entity, _| present.contains(entity));
+
+        let ids = &recorder.ids;
+
+        for &(entity, off_hand) in &swings {
+            if let Some(&id) = ids.get(&entity) {
+                records.push(ReplayRecord::Swing { id, off_hand });
+            }
+        }
+
+        for hit in &hits {
+            let (Some(&attacker), Some(&victim)) = (ids.get(&hit.attacker), ids.get(&hit.victim))
+            else {
+                continue;
+            };
+
+            records.push(ReplayRecord::Attack {
+                attacker,
+                victim,
+                damage: hit.damage,
+                critical: hit.critical,
+                sweep: hit.sweep,
+                knockback: hit.knockback,
+            });
+        }
+
+        for &(victim, attacker, damage, damage_type) in &damages {
+            let Some(&victim) = ids.get(&victim) else {
+                continue;
+            };
+
+            records.push(ReplayRecord::Damage {
+                victim,
+                attacker: attacker.and_then(|attacker| ids.get(&attacker).copied()),
+                damage,
+                damage_type,
+            });
+        }
+
+        if !records.is_empty() {
+            recorder.replay.frames.push(ReplayFrame {
+                tick: recorder.tick,
+                records,
+            });
+        }
+
+        recorder.tick += 1;
+    }
+}
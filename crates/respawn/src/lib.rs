@@ -0,0 +1,308 @@
+use std::time::Duration;
+
+use bevy_ecs::query::QueryData;
+use bevy_time::{Time, Timer, TimerMode};
+use combat::CombatState;
+use fall_damage::FallingState;
+use utils::{damage::DeathEvent, random::GameRng, spawn_protection::SpawnProtection};
+use valence::{
+    entity::{
+        active_status_effects::ActiveStatusEffects,
+        item::{ItemEntityBundle, Stack},
+        living::Health,
+        Velocity,
+    },
+    prelude::*,
+    protocol::{packets::play::HealthUpdateS2c, WritePacket},
+};
+
+/// Describes what should happen to an entity after it died.
+///
+/// The death is detected through the [`DeathEvent`], so the entity needs a
+/// [`TakesDamage`](utils::damage::TakesDamage) component with a
+/// `set_hp_after_death` > 0, otherwise the vanilla death screen will be shown.
+#[derive(Component)]
+pub struct DeathHandling {
+    /// If the inventory should be dropped at the death position.
+    /// If `false`, the inventory will be kept (keep-inventory).
+    pub drop_inventory: bool,
+    /// How long the entity will be in spectator mode before it respawns.
+    ///
+    /// If `None`, the entity will respawn immediately.
+    pub spectator_duration: Option<Duration>,
+    /// Where the entity will be teleported to when it respawns.
+    pub respawn_point: RespawnPoint,
+    /// The health that will be restored when the entity respawns.
+    ///
+    /// If `None`, the health will not be changed.
+    pub restore_health: Option<f32>,
+    /// The food level and saturation that will be restored when the entity respawns.
+    ///
+    /// If `None`, the food will not be changed. This only affects clients.
+    pub restore_food: Option<(i32, f32)>,
+    /// If all status effects should be removed when the entity respawns.
+    pub clear_effects: bool,
+    /// Protect the entity from damage for this long after it respawned, see [`SpawnProtection`].
+    pub spawn_protection: Option<Duration>,
+}
+
+impl Default for DeathHandling {
+    fn default() -> Self {
+        Self {
+            drop_inventory: true,
+            spectator_duration: None,
+            respawn_point: RespawnPoint::DeathPosition,
+            restore_health: Some(20.0),
+            restore_food: Some((20, 5.0)),
+            clear_effects: true,
+            spawn_protection: None,
+        }
+    }
+}
+
+/// Provides the position an entity respawns at.
+pub enum RespawnPoint {
+    /// Respawn where the entity died.
+    DeathPosition,
+    /// Respawn at a fixed position.
+    Fixed(DVec3),
+    /// Respawn at a random position of the list.
+    Random(Vec<DVec3>),
+    /// Respawn at the position returned by the function.
+    ///
+    /// The parameters are: `entity`, `death_position`.
+    Custom(fn(Entity, DVec3) -> DVec3),
+}
+
+impl RespawnPoint {
+    /// Get the respawn position for the entity.
+    pub fn get(&self, entity: Entity, death_position: DVec3, rng: &mut GameRng) -> DVec3 {
+        match self {
+            RespawnPoint::DeathPosition => death_position,
+            RespawnPoint::Fixed(position) => *position,
+            RespawnPoint::Random(positions) => {
+                if positions.is_empty() {
+                    return death_position;
+                }
+
+                let idx = (rng.f32() * positions.len() as f32) as usize;
+                positions[idx.min(positions.len() - 1)]
+            }
+            RespawnPoint::Custom(provider) => provider(entity, death_position),
+        }
+    }
+}
+
+/// An event that will be fired after an entity respawned.
+#[derive(Event)]
+pub struct RespawnEvent {
+    pub entity: Entity,
+    pub position: DVec3,
+}
+
+/// Attached to entities that are waiting in spectator mode to respawn.
+#[derive(Component)]
+pub struct Respawning {
+    timer: Timer,
+    previous_game_mode: Option<GameMode>,
+}
+
+impl Respawning {
+    /// The time left until the entity respawns.
+    pub fn remaining(&self) -> Duration {
+        self.timer.remaining()
+    }
+}
+
+pub struct RespawnPlugin;
+
+impl Plugin for RespawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRng>()
+            .add_event::<RespawnEvent>()
+            .add_systems(Update, (death_system, respawn_timer_system));
+    }
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct RespawnQuery {
+    entity: Entity,
+    death_handling: &'static DeathHandling,
+    position: &'static mut Position,
+    layer: &'static EntityLayerId,
+    client: Option<&'static mut Client>,
+    game_mode: Option<&'static mut GameMode>,
+    inventory: Option<&'static mut Inventory>,
+    health: Option<&'static mut Health>,
+    status_effects: Option<&'static mut ActiveStatusEffects>,
+    falling_state: Option<&'static mut FallingState>,
+    combat_state: Option<&'static mut CombatState>,
+    spawn_protection: Option<&'static mut SpawnProtection>,
+}
+
+fn death_system(
+    mut commands: Commands,
+    mut query: Query<RespawnQuery, Without<Respawning>>,
+    mut events: EventReader<DeathEvent>,
+    mut respawn_writer: EventWriter<RespawnEvent>,
+    mut rng: ResMut<GameRng>,
+) {
+    for event in events.read() {
+        let Ok(mut entity) = query.get_mut(event.victim) else {
+            continue;
+        };
+
+        if entity.death_handling.drop_inventory {
+            if let Some(inventory) = entity.inventory.as_mut() {
+                drop_inventory(
+                    &mut commands,
+                    inventory,
+                    entity.position.0,
+                    *entity.layer,
+                    &mut rng,
+                );
+            }
+        }
+
+        if let Some(duration) = entity.death_handling.spectator_duration {
+            let previous_game_mode = entity.game_mode.as_deref().copied();
+
+            if let Some(game_mode) = entity.game_mode.as_mut() {
+                **game_mode = GameMode::Spectator;
+            }
+
+            commands.entity(entity.entity).insert(Respawning {
+                timer: Timer::new(duration, TimerMode::Once),
+                previous_game_mode,
+            });
+        } else {
+            respawn(&mut entity, None, &mut rng);
+            protect_after_respawn(&mut commands, &mut entity);
+            respawn_writer.send(RespawnEvent {
+                entity: entity.entity,
+                position: entity.position.0,
+            });
+        }
+    }
+}
+
+fn respawn_timer_system(
+    mut commands: Commands,
+    mut query: Query<(RespawnQuery, &mut Respawning)>,
+    mut respawn_writer: EventWriter<RespawnEvent>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    for (mut entity, mut respawning) in query.iter_mut() {
+        if !respawning.timer.tick(time.delta()).finished() {
+            continue;
+        }
+
+        respawn(&mut entity, respawning.previous_game_mode, &mut rng);
+        protect_after_respawn(&mut commands, &mut entity);
+        commands.entity(entity.entity).remove::<Respawning>();
+
+        respawn_writer.send(RespawnEvent {
+            entity: entity.entity,
+            position: entity.position.0,
+        });
+    }
+}
+
+/// Teleports the entity to its respawn point and restores its state.
+fn respawn(entity: &mut RespawnQueryItem, previous_game_mode: Option<GameMode>, rng: &mut GameRng) {
+    let death_handling = entity.death_handling;
+
+    let respawn_position = death_handling
+        .respawn_point
+        .get(entity.entity, entity.position.0, rng);
+    entity.position.0 = respawn_position;
+
+    if let (Some(game_mode), Some(previous_game_mode)) =
+        (entity.game_mode.as_mut(), previous_game_mode)
+    {
+        **game_mode = previous_game_mode;
+    }
+
+    if let (Some(health), Some(restore_health)) =
+        (entity.health.as_mut(), death_handling.restore_health)
+    {
+        health.0 = restore_health;
+    }
+
+    if let (Some(client), Some((food, food_saturation))) =
+        (entity.client.as_mut(), death_handling.restore_food)
+    {
+        let health = entity
+            .health
+            .as_ref()
+            .map(|health| health.0)
+            .unwrap_or(20.0);
+
+        client.write_packet(&HealthUpdateS2c {
+            health,
+            food: food.into(),
+            food_saturation,
+        });
+    }
+
+    if death_handling.clear_effects {
+        if let Some(status_effects) = entity.status_effects.as_mut() {
+            status_effects.remove_all();
+        }
+    }
+
+    // Stale timers (e.g. the fall start or the last hit) should not leak into the next life.
+    if let Some(falling_state) = entity.falling_state.as_mut() {
+        falling_state.reset(respawn_position);
+    }
+
+    if let Some(combat_state) = entity.combat_state.as_mut() {
+        combat_state.reset();
+    }
+}
+
+fn protect_after_respawn(commands: &mut Commands, entity: &mut RespawnQueryItem) {
+    let Some(duration) = entity.death_handling.spawn_protection else {
+        return;
+    };
+
+    // The void kills protected entities, replacing their protection would lose the glowing it
+    // turned on.
+    if let Some(protection) = entity.spawn_protection.as_mut() {
+        protection.restart(duration);
+    } else {
+        commands
+            .entity(entity.entity)
+            .insert(SpawnProtection::new(duration));
+    }
+}
+
+/// Spawns the items of the inventory at the given position and clears the inventory.
+fn drop_inventory(
+    commands: &mut Commands,
+    inventory: &mut Inventory,
+    position: DVec3,
+    layer: EntityLayerId,
+    rng: &mut GameRng,
+) {
+    // Slot 0 is the crafting result of the player inventory, which is not a real item.
+    for slot in 1..inventory.slot_count() {
+        let stack = inventory.replace_slot(slot, ItemStack::EMPTY);
+
+        if stack.is_empty() {
+            continue;
+        }
+
+        let velocity = Vec3::new(rng.f32() - 0.5, rng.f32() * 0.5 + 0.5, rng.f32() - 0.5) * 4.0;
+
+        commands.spawn(ItemEntityBundle {
+            item_stack: Stack(stack),
+            position: Position(position + DVec3::new(0.0, 0.5, 0.0)),
+            velocity: Velocity(velocity),
+            layer,
+            ..Default::default()
+        });
+    }
+}
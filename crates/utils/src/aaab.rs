@@ -5,6 +5,12 @@ pub trait AabbExt {
     fn width_y(&self) -> f64;
     fn width_z(&self) -> f64;
     fn translate(&self, translation: DVec3) -> Aabb;
+    /// The fraction (0.0 - 1.0) of the segment from `start` to `end` where it enters the aabb.
+    ///
+    /// Returns `None` if the segment does not touch the aabb.
+    fn segment_intersection(&self, start: DVec3, end: DVec3) -> Option<f64>;
+    /// If the position is inside the aabb (the minimum is inside, the maximum is not).
+    fn contains_position(&self, position: DVec3) -> bool;
 }
 
 impl AabbExt for Aabb {
@@ -23,4 +29,37 @@ impl AabbExt for Aabb {
     fn translate(&self, translation: DVec3) -> Aabb {
         Aabb::new(self.min() + translation, self.max() + translation)
     }
+
+    fn segment_intersection(&self, start: DVec3, end: DVec3) -> Option<f64> {
+        let direction = end - start;
+        let (mut entry, mut exit) = (0.0_f64, 1.0_f64);
+
+        for axis in 0..3 {
+            let (origin, delta) = (start[axis], direction[axis]);
+            let (min, max) = (self.min()[axis], self.max()[axis]);
+
+            if delta == 0.0 {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let t1 = (min - origin) / delta;
+            let t2 = (max - origin) / delta;
+
+            entry = entry.max(t1.min(t2));
+            exit = exit.min(t1.max(t2));
+
+            if entry > exit {
+                return None;
+            }
+        }
+
+        Some(entry)
+    }
+
+    fn contains_position(&self, position: DVec3) -> bool {
+        (0..3).all(|axis| position[axis] >= self.min()[axis] && position[axis] < self.max()[axis])
+    }
 }
@@ -0,0 +1,204 @@
+use valence::{
+    entity::attributes::{EntityAttribute, EntityAttributes},
+    nbt::{value::ValueRef, Value},
+    ItemStack,
+};
+
+/// The slot an item needs to be in for its attribute modifiers to apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttributeSlot {
+    MainHand,
+    OffHand,
+    Head,
+    Chest,
+    Legs,
+    Feet,
+}
+
+impl AttributeSlot {
+    pub fn id(&self) -> &'static str {
+        match self {
+            AttributeSlot::MainHand => "mainhand",
+            AttributeSlot::OffHand => "offhand",
+            AttributeSlot::Head => "head",
+            AttributeSlot::Chest => "chest",
+            AttributeSlot::Legs => "legs",
+            AttributeSlot::Feet => "feet",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "mainhand" => Some(AttributeSlot::MainHand),
+            "offhand" => Some(AttributeSlot::OffHand),
+            "head" => Some(AttributeSlot::Head),
+            "chest" => Some(AttributeSlot::Chest),
+            "legs" => Some(AttributeSlot::Legs),
+            "feet" => Some(AttributeSlot::Feet),
+            _ => None,
+        }
+    }
+}
+
+/// How an attribute modifier changes the value of the attribute.
+///
+/// https://minecraft.wiki/w/Attribute#Operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttributeOperation {
+    /// Adds the amount to the base value.
+    Add,
+    /// Adds the base value (after [`AttributeOperation::Add`]) multiplied by the amount.
+    MultiplyBase,
+    /// Multiplies the value by `1 + amount`.
+    MultiplyTotal,
+}
+
+impl AttributeOperation {
+    pub fn from_id(id: i32) -> Option<Self> {
+        match id {
+            0 => Some(AttributeOperation::Add),
+            1 => Some(AttributeOperation::MultiplyBase),
+            2 => Some(AttributeOperation::MultiplyTotal),
+            _ => None,
+        }
+    }
+}
+
+/// An attribute modifier of an item stack (an entry of the `AttributeModifiers` NBT tag).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeModifier {
+    pub attribute: EntityAttribute,
+    pub amount: f64,
+    pub operation: AttributeOperation,
+    /// The slot the item needs to be in, `None` if the modifier applies in every slot.
+    pub slot: Option<AttributeSlot>,
+}
+
+/// Returns the attribute for the name used in the `AttributeName` NBT tag.
+///
+/// Only the attributes relevant for combat are supported.
+fn attribute_from_name(name: &str) -> Option<EntityAttribute> {
+    match name.strip_prefix("minecraft:").unwrap_or(name) {
+        "generic.attack_damage" => Some(EntityAttribute::GenericAttackDamage),
+        "generic.attack_speed" => Some(EntityAttribute::GenericAttackSpeed),
+        "generic.attack_knockback" => Some(EntityAttribute::GenericAttackKnockback),
+        "generic.armor" => Some(EntityAttribute::GenericArmor),
+        "generic.armor_toughness" => Some(EntityAttribute::GenericArmorToughness),
+        "generic.knockback_resistance" => Some(EntityAttribute::GenericKnockbackResistance),
+        "generic.max_health" => Some(EntityAttribute::GenericMaxHealth),
+        "generic.movement_speed" => Some(EntityAttribute::GenericMovementSpeed),
+        _ => None,
+    }
+}
+
+/// Applies the modifiers to the base value (java behavior).
+pub fn apply_attribute_modifiers<'a>(
+    base: f64,
+    modifiers: impl IntoIterator<Item = &'a AttributeModifier>,
+) -> f64 {
+    let (mut add, mut multiply_base, mut multiply_total) = (0.0, 0.0, 1.0);
+
+    for modifier in modifiers {
+        match modifier.operation {
+            AttributeOperation::Add => add += modifier.amount,
+            AttributeOperation::MultiplyBase => multiply_base += modifier.amount,
+            AttributeOperation::MultiplyTotal => multiply_total *= 1.0 + modifier.amount,
+        }
+    }
+
+    let value = base + add;
+    (value + value * multiply_base) * multiply_total
+}
+
+/// The difference between the computed value and the base value of an entity attribute.
+///
+/// This is the bonus the attribute modifiers of the entity (e.g. from status effects) give.
+/// Returns 0 if the entity does not have the attribute.
+pub fn entity_attribute_bonus(attributes: &EntityAttributes, attribute: EntityAttribute) -> f64 {
+    match (
+        attributes.get_compute_value(attribute),
+        attributes.get_base_value(attribute),
+    ) {
+        (Some(value), Some(base)) => value - base,
+        _ => 0.0,
+    }
+}
+
+pub trait ItemStackAttributesExt {
+    /// The attribute modifiers of the item stack (the `AttributeModifiers` NBT tag).
+    fn attribute_modifiers(&self) -> Vec<AttributeModifier>;
+    /// Applies the modifiers of the item for the attribute to the base value.
+    ///
+    /// Only modifiers without a slot or for the given slot are applied.
+    fn apply_attribute_modifiers(
+        &self,
+        attribute: EntityAttribute,
+        slot: AttributeSlot,
+        base: f64,
+    ) -> f64;
+}
+
+impl ItemStackAttributesExt for ItemStack {
+    fn attribute_modifiers(&self) -> Vec<AttributeModifier> {
+        let mut modifiers = Vec::new();
+        if let Some(nbt) = &self.nbt {
+            if let Some(Value::List(list)) = nbt.get("AttributeModifiers") {
+                for modifier in list {
+                    if let ValueRef::Compound(modifier) = modifier {
+                        let attribute = match modifier.get("AttributeName") {
+                            Some(Value::String(name)) => attribute_from_name(name),
+                            _ => None,
+                        };
+
+                        let amount = match modifier.get("Amount") {
+                            Some(Value::Double(amount)) => Some(*amount),
+                            Some(Value::Float(amount)) => Some(*amount as f64),
+                            Some(Value::Int(amount)) => Some(*amount as f64),
+                            _ => None,
+                        };
+
+                        let operation = match modifier.get("Operation") {
+                            Some(Value::Int(operation)) => AttributeOperation::from_id(*operation),
+                            None => Some(AttributeOperation::Add),
+                            _ => None,
+                        };
+
+                        let slot = match modifier.get("Slot") {
+                            Some(Value::String(slot)) => AttributeSlot::from_id(slot),
+                            _ => None,
+                        };
+
+                        if let (Some(attribute), Some(amount), Some(operation)) =
+                            (attribute, amount, operation)
+                        {
+                            modifiers.push(AttributeModifier {
+                                attribute,
+                                amount,
+                                operation,
+                                slot,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        modifiers
+    }
+
+    fn apply_attribute_modifiers(
+        &self,
+        attribute: EntityAttribute,
+        slot: AttributeSlot,
+        base: f64,
+    ) -> f64 {
+        let modifiers = self.attribute_modifiers();
+
+        apply_attribute_modifiers(
+            base,
+            modifiers.iter().filter(|modifier| {
+                modifier.attribute == attribute && modifier.slot.map_or(true, |s| s == slot)
+            }),
+        )
+    }
+}
@@ -1,19 +1,109 @@
 use std::time::Duration;
 
+use bevy_ecs::query::QueryData;
 use bevy_time::{Time, Timer, TimerMode};
 use valence::{
-    entity::{entity::Flags, living::Health, EntityId},
+    entity::{
+        active_status_effects::ActiveStatusEffects, entity::Flags, living::Health, EntityId,
+        EntityStatuses,
+    },
+    inventory::HeldItem,
     prelude::*,
     protocol::{packets::play::EntityDamageS2c, sound::SoundCategory, Sound, VarInt, WritePacket},
     Layer,
 };
 
+use crate::{
+    death_prevention::{DeathContext, DeathPreventedEvent, DeathPreventionRules},
+    effects::{damage_after_effects, health_effects_system, sync_absorption, Absorption},
+    enchantments::{damage_after_enchantments, EnchantmentProtection},
+    item_values::EquipmentExt,
+    random::GameRng,
+    region::Regions,
+    spawn_protection::{update_spawn_protection, SpawnProtection},
+};
+
 /// An event that will be fired if an entity takes damage.
 #[derive(Event)]
 pub struct DamageEvent {
     pub victim: Entity,
     pub attacker: Option<Entity>,
     pub damage: f32,
+    /// The source of the damage.
+    pub damage_type: DamageType,
+}
+
+/// An event that will be fired after an entity took the damage of a [`DamageEvent`].
+///
+/// Unlike the [`DamageEvent`], this is not fired if the damage was prevented (e.g. by spawn
+/// protection or a region) and contains the damage after the effects and protection enchantments.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DamageTakenEvent {
+    pub victim: Entity,
+    pub attacker: Option<Entity>,
+    /// The damage the entity took, including the damage absorbed by absorption hearts.
+    pub damage: f32,
+    pub damage_type: DamageType,
+}
+
+/// The source of a [`DamageEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    /// Damage without a specific source.
+    Generic,
+    /// Melee damage dealt by another entity.
+    EntityAttack,
+    /// Damage from a projectile (e.g. an arrow).
+    Projectile,
+    /// Damage from an explosion.
+    Explosion,
+    /// Damage from falling.
+    Fall,
+    /// Damage from burning (e.g. after being hit with fire aspect).
+    OnFire,
+    /// Damage from being below the void level of the layer.
+    Void,
+    /// Damage from being outside the world border.
+    WorldBorder,
+    /// Damage from standing in lava.
+    Lava,
+    /// Damage from standing in a fire block.
+    InFire,
+    /// Damage from touching a cactus.
+    Cactus,
+    /// Damage from standing on a magma block.
+    HotFloor,
+    /// Damage from moving through a sweet berry bush.
+    SweetBerryBush,
+    /// Damage from suffocating inside a block.
+    InWall,
+    /// Damage from running out of air under water.
+    Drowning,
+    /// Damage from hitting an entity that wears armor with the thorns enchantment.
+    Thorns,
+}
+
+impl DamageType {
+    /// If the damage is caused by fire or heat.
+    ///
+    /// Entities that are [`TakesDamage::fire_immune`] will not take this damage.
+    pub fn is_fire(&self) -> bool {
+        matches!(
+            self,
+            DamageType::OnFire | DamageType::Lava | DamageType::InFire | DamageType::HotFloor
+        )
+    }
+
+    /// If the armor and the protection enchantments are already applied by the combat plugin.
+    ///
+    /// The protection enchantments against the other damage types are applied when the damage is
+    /// taken.
+    pub fn is_combat(&self) -> bool {
+        matches!(
+            self,
+            DamageType::EntityAttack | DamageType::Projectile | DamageType::Thorns
+        )
+    }
 }
 
 #[derive(Event)]
@@ -35,6 +125,17 @@ pub struct DeathEvent {
     pub attacker: Option<Entity>,
 }
 
+/// An event that will be fired when the server sets the velocity of a client (e.g. knockback or
+/// the world border pushback).
+///
+/// The client is moved by a velocity it did not cause, so checks of its movement should skip the
+/// following moves.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct VelocityChangedEvent {
+    pub client: Entity,
+    pub velocity: Vec3,
+}
+
 /// This component will be added to entities that register damage with the [`DamageEvent`]
 #[derive(Component)]
 pub struct TakesDamage {
@@ -57,6 +158,9 @@ pub struct TakesDamage {
     pub burn_duration_multiplier: f32,
     /// Burn damage multiplier.
     pub burn_damage_multiplier: f32,
+    /// If the entity is immune to fire damage (like the fire resistance effect).
+    /// See [`DamageType::is_fire`].
+    pub fire_immune: bool,
 }
 
 #[derive(Component)]
@@ -91,6 +195,7 @@ impl Default for TakesDamage {
             show_burning: true,
             burn_duration_multiplier: 1.0,
             burn_damage_multiplier: 1.0,
+            fire_immune: false,
         }
     }
 }
@@ -99,72 +204,218 @@ pub struct DamagePlugin;
 
 impl Plugin for DamagePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<DamageEvent>()
+        app.init_resource::<GameRng>()
+            .init_resource::<DeathPreventionRules>()
+            .add_event::<DamageEvent>()
+            .add_event::<DamageTakenEvent>()
             .add_event::<DeathEvent>()
+            .add_event::<DeathPreventedEvent>()
             .add_event::<StartBurningEvent>()
-            .add_systems(Update, (damage_system, burn_system));
+            .add_event::<VelocityChangedEvent>()
+            .add_systems(
+                Update,
+                (
+                    damage_system,
+                    burn_system,
+                    update_spawn_protection,
+                    health_effects_system,
+                    sync_absorption.after(damage_system),
+                ),
+            );
     }
 }
 
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct DamageQuery {
+    entity: Entity,
+    health: &'static mut Health,
+    takes_damage: &'static TakesDamage,
+    position: &'static Position,
+    entity_id: &'static EntityId,
+    layer: &'static EntityLayerId,
+    spawn_protection: Option<&'static SpawnProtection>,
+    effects: Option<&'static mut ActiveStatusEffects>,
+    absorption: Option<&'static mut Absorption>,
+    // Used by the death prevention (e.g. the totem of undying).
+    inventory: Option<&'static mut Inventory>,
+    held_item: Option<&'static HeldItem>,
+    equipment: Option<&'static mut Equipment>,
+    enchantment_protection: Option<&'static EnchantmentProtection>,
+    statuses: Option<&'static mut EntityStatuses>,
+}
+
+#[allow(clippy::too_many_arguments)]
 fn damage_system(
+    mut commands: Commands,
     mut events: EventReader<DamageEvent>,
+    mut damage_taken_writer: EventWriter<DamageTakenEvent>,
     mut event_writer: EventWriter<DeathEvent>,
-    mut query: Query<(&mut Health, &TakesDamage, &Position, &EntityId)>,
+    mut prevented_writer: EventWriter<DeathPreventedEvent>,
+    mut query: Query<DamageQuery>,
     mut layer: Query<&mut ChunkLayer>,
+    regions: Query<&Regions>,
+    death_prevention: Res<DeathPreventionRules>,
 ) {
     for events in events.read() {
-        for (mut health, takes_damage, position, entity_id) in query.iter_mut() {
-            if health.0 <= 0.0 {
+        let Ok(victim) = query.get_mut(events.victim) else {
+            continue;
+        };
+
+        let DamageQueryItem {
+            entity,
+            mut health,
+            takes_damage,
+            position,
+            entity_id,
+            layer: layer_id,
+            spawn_protection,
+            mut effects,
+            absorption,
+            inventory,
+            held_item,
+            equipment,
+            enchantment_protection,
+            statuses,
+        } = victim;
+
+        // The void kills protected entities too, otherwise they would fall forever. (java behavior)
+        if spawn_protection.is_some_and(SpawnProtection::is_active)
+            && events.damage_type != DamageType::Void
+        {
+            continue;
+        }
+
+        if health.0 <= 0.0 {
+            continue;
+        }
+
+        if takes_damage.fire_immune && events.damage_type.is_fire() {
+            continue;
+        }
+
+        if let Ok(regions) = regions.get(layer_id.0) {
+            let flags = regions.flags_at(position.0);
+
+            if !flags.allows_damage(events.damage_type, events.attacker.is_some()) {
                 continue;
             }
+        }
 
-            let entity_id: VarInt = entity_id.get().into();
+        let entity_id: VarInt = entity_id.get().into();
 
-            let damage = events.damage * takes_damage.damage_multiplier;
-            health.0 -= damage;
+        let mut damage = damage_after_effects(
+            effects.as_deref(),
+            events.damage_type,
+            events.damage * takes_damage.damage_multiplier,
+        );
 
-            let mut layer = layer.single_mut();
+        // The damage was prevented by an effect (fire resistance).
+        if damage <= 0.0 && events.damage > 0.0 {
+            continue;
+        }
 
-            if takes_damage.show_hurt {
-                layer
-                    .view_writer(position.0)
-                    .write_packet(&EntityDamageS2c {
-                        entity_id,
-                        source_type_id: 1.into(),
-                        source_cause_id: 0.into(),
-                        source_direct_id: 0.into(),
-                        source_pos: Some(position.0),
-                    });
+        // E.g. feather falling against fall damage and fire protection against lava.
+        if let Some(equipment) = equipment
+            .as_deref()
+            .filter(|_| !events.damage_type.is_combat())
+        {
+            let protection_factor = equipment.protection_factor(events.damage_type);
+
+            damage = match enchantment_protection {
+                Some(protection) => protection.apply(damage, protection_factor as f32),
+                None => damage_after_enchantments(damage, protection_factor as f32),
+            };
+        }
+
+        damage_taken_writer.send(DamageTakenEvent {
+            victim: entity,
+            attacker: events.attacker,
+            damage,
+            damage_type: events.damage_type,
+        });
+
+        // Entities without absorption get it when a death prevention gives them absorption.
+        let mut new_absorption = Absorption::default();
+        let (damage, absorption) = match absorption {
+            Some(absorption) => {
+                let absorption = absorption.into_inner();
+                (absorption.absorb(damage), absorption)
             }
+            None => (damage, &mut new_absorption),
+        };
+        health.0 -= damage;
 
-            if health.0 <= 0.0 {
-                if takes_damage.play_sound {
-                    layer.play_sound(
-                        Sound::EntityPlayerDeath,
-                        SoundCategory::Player,
-                        position.0,
-                        1.0,
-                        1.0,
-                    );
-                }
+        if health.0 <= 0.0 {
+            let mut context = DeathContext {
+                entity,
+                attacker: events.attacker,
+                damage_type: events.damage_type,
+                health: &mut health.0,
+                absorption,
+                inventory: inventory.map(Mut::into_inner),
+                held_item,
+                equipment: equipment.map(Mut::into_inner),
+                effects: effects.as_mut().map(|effects| &mut **effects),
+                statuses: statuses.map(Mut::into_inner),
+            };
 
-                if !takes_damage.suppress_death_event {
-                    event_writer.send(DeathEvent {
-                        victim: events.victim,
-                        attacker: events.attacker,
-                    });
-                }
+            if death_prevention.prevent_death(&mut context) {
+                // A rule that forgot to restore the health should not kill the entity.
+                health.0 = health.0.max(1.0);
 
-                health.0 = takes_damage.set_hp_after_death;
-            } else if takes_damage.play_sound {
+                prevented_writer.send(DeathPreventedEvent {
+                    victim: entity,
+                    attacker: events.attacker,
+                });
+            }
+        }
+
+        if new_absorption.0 > 0.0 {
+            commands.entity(entity).insert(new_absorption);
+        }
+
+        let mut layer = layer.single_mut();
+
+        if takes_damage.show_hurt {
+            layer
+                .view_writer(position.0)
+                .write_packet(&EntityDamageS2c {
+                    entity_id,
+                    source_type_id: 1.into(),
+                    source_cause_id: 0.into(),
+                    source_direct_id: 0.into(),
+                    source_pos: Some(position.0),
+                });
+        }
+
+        if health.0 <= 0.0 {
+            if takes_damage.play_sound {
                 layer.play_sound(
-                    Sound::EntityPlayerHurt,
+                    Sound::EntityPlayerDeath,
                     SoundCategory::Player,
                     position.0,
                     1.0,
                     1.0,
                 );
             }
+
+            if !takes_damage.suppress_death_event {
+                event_writer.send(DeathEvent {
+                    victim: events.victim,
+                    attacker: events.attacker,
+                });
+            }
+
+            health.0 = takes_damage.set_hp_after_death;
+        } else if takes_damage.play_sound {
+            layer.play_sound(
+                Sound::EntityPlayerHurt,
+                SoundCategory::Player,
+                position.0,
+                1.0,
+                1.0,
+            );
         }
     }
 }
@@ -185,6 +436,7 @@ fn burn_system(
                         victim,
                         attacker: burn_timer.attacker,
                         damage: burn_timer.damage_per_second * takes_damage.burn_damage_multiplier,
+                        damage_type: DamageType::OnFire,
                     });
                 }
             } else {
@@ -196,14 +448,18 @@ fn burn_system(
     }
 
     for event in events.read() {
-        for (victim, takes_damage, _, mut flags) in query.iter_mut() {
-            let duration = event
-                .duration
-                .mul_f32(takes_damage.burn_duration_multiplier);
-            let burn_timer = BurnTimer::new(duration, event.attacker, event.damage_per_second);
-            commands.entity(victim).insert(burn_timer);
-            commands.entity(victim).insert(OnFire);
+        let Ok((victim, takes_damage, _, mut flags)) = query.get_mut(event.victim) else {
+            continue;
+        };
+
+        let duration = event
+            .duration
+            .mul_f32(takes_damage.burn_duration_multiplier);
+        let burn_timer = BurnTimer::new(duration, event.attacker, event.damage_per_second);
+        commands.entity(victim).insert(burn_timer);
+        commands.entity(victim).insert(OnFire);
 
+        if takes_damage.show_burning {
             flags.set_on_fire(true);
         }
     }
@@ -0,0 +1,167 @@
+//! Lets items and perks prevent the death of an entity, like the totem of undying.
+//!
+//! When an entity would die from a [`DamageEvent`](crate::damage::DamageEvent), the
+//! [`DeathPreventionRules`] are checked in order. The first rule that prevents the death stops
+//! the [`DeathEvent`](crate::damage::DeathEvent) and a [`DeathPreventedEvent`] is fired instead.
+
+use valence::{
+    entity::{
+        active_status_effects::{ActiveStatusEffect, ActiveStatusEffects},
+        EntityStatus, EntityStatuses,
+    },
+    inventory::HeldItem,
+    prelude::*,
+    protocol::Hand,
+    status_effects::StatusEffect,
+};
+
+use crate::{damage::DamageType, effects::Absorption};
+
+/// The slot of the off hand in the player inventory.
+const OFF_HAND_SLOT: u16 = 45;
+
+/// The entity that would die and the damage that killed it.
+///
+/// The components that an entity does not have are `None`.
+pub struct DeathContext<'a> {
+    pub entity: Entity,
+    pub attacker: Option<Entity>,
+    pub damage_type: DamageType,
+    /// The health after the damage, a rule that prevents the death has to restore it.
+    pub health: &'a mut f32,
+    pub absorption: &'a mut Absorption,
+    pub inventory: Option<&'a mut Inventory>,
+    pub held_item: Option<&'a HeldItem>,
+    pub equipment: Option<&'a mut Equipment>,
+    pub effects: Option<&'a mut ActiveStatusEffects>,
+    pub statuses: Option<&'a mut EntityStatuses>,
+}
+
+impl DeathContext<'_> {
+    /// The item in the hand, from the inventory for players and from the equipment otherwise.
+    pub fn hand(&self, hand: Hand) -> Option<&ItemStack> {
+        match (hand, &self.inventory, self.held_item, &self.equipment) {
+            (Hand::Main, Some(inventory), Some(held_item), _) => {
+                Some(inventory.slot(held_item.slot()))
+            }
+            (Hand::Off, Some(inventory), _, _) => Some(inventory.slot(OFF_HAND_SLOT)),
+            (Hand::Main, _, _, Some(equipment)) => Some(equipment.main_hand()),
+            (Hand::Off, _, _, Some(equipment)) => Some(equipment.off_hand()),
+            _ => None,
+        }
+    }
+
+    /// Replaces the item in the hand, see [`DeathContext::hand`].
+    pub fn set_hand(&mut self, hand: Hand, stack: ItemStack) {
+        match (
+            hand,
+            self.inventory.as_deref_mut(),
+            self.held_item,
+            self.equipment.as_deref_mut(),
+        ) {
+            (Hand::Main, Some(inventory), Some(held_item), _) => {
+                inventory.set_slot(held_item.slot(), stack)
+            }
+            (Hand::Off, Some(inventory), _, _) => inventory.set_slot(OFF_HAND_SLOT, stack),
+            (Hand::Main, _, _, Some(equipment)) => equipment.set_main_hand(stack),
+            (Hand::Off, _, _, Some(equipment)) => equipment.set_off_hand(stack),
+            _ => {}
+        }
+    }
+
+    /// Applies the effect for the duration in ticks.
+    pub fn apply_effect(&mut self, effect: StatusEffect, amplifier: u8, ticks: i32) {
+        if effect == StatusEffect::Absorption {
+            self.absorption.apply_level(amplifier);
+        }
+
+        if let Some(effects) = self.effects.as_deref_mut() {
+            effects.apply(
+                ActiveStatusEffect::from_effect(effect)
+                    .with_amplifier(amplifier)
+                    .with_duration(ticks),
+            );
+        }
+    }
+}
+
+/// Prevents the death if it returns true, it has to restore the health of the entity.
+pub type DeathPreventionRule = fn(&mut DeathContext) -> bool;
+
+/// The rules that can prevent the death of entities, by default only the totem of undying.
+///
+/// Register custom rules (e.g. a "second life" perk) with [`DeathPreventionRules::register`].
+#[derive(Resource)]
+pub struct DeathPreventionRules {
+    rules: Vec<DeathPreventionRule>,
+}
+
+impl Default for DeathPreventionRules {
+    fn default() -> Self {
+        Self {
+            rules: vec![totem_of_undying],
+        }
+    }
+}
+
+impl DeathPreventionRules {
+    /// No rules, entities always die.
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Adds a rule, it is checked after the rules that were registered before.
+    pub fn register(&mut self, rule: DeathPreventionRule) {
+        self.rules.push(rule);
+    }
+
+    /// Checks the rules until one prevents the death.
+    pub fn prevent_death(&self, context: &mut DeathContext) -> bool {
+        self.rules.iter().any(|rule| rule(context))
+    }
+}
+
+/// An event that will be fired when a [`DeathPreventionRule`] prevented the death of an entity.
+#[derive(Event)]
+pub struct DeathPreventedEvent {
+    pub victim: Entity,
+    pub attacker: Option<Entity>,
+}
+
+/// A totem of undying in one of the hands prevents the death (java behavior).
+///
+/// The totem is used up, the effects are replaced with regeneration, absorption and fire
+/// resistance and the totem animation is shown. It does not work against the void.
+///
+/// https://minecraft.wiki/w/Totem_of_Undying
+pub fn totem_of_undying(context: &mut DeathContext) -> bool {
+    if context.damage_type == DamageType::Void {
+        return false;
+    }
+
+    let Some(hand) = [Hand::Main, Hand::Off].into_iter().find(|hand| {
+        context
+            .hand(*hand)
+            .is_some_and(|stack| stack.item == ItemKind::TotemOfUndying)
+    }) else {
+        return false;
+    };
+
+    context.set_hand(hand, ItemStack::EMPTY);
+    *context.health = 1.0;
+
+    if let Some(effects) = context.effects.as_deref_mut() {
+        effects.remove_all();
+    }
+
+    context.apply_effect(StatusEffect::Regeneration, 1, 900);
+    context.apply_effect(StatusEffect::Absorption, 1, 100);
+    context.apply_effect(StatusEffect::FireResistance, 0, 800);
+
+    // Shows the totem animation to the player and the particles and sound to everyone.
+    if let Some(statuses) = context.statuses.as_deref_mut() {
+        statuses.trigger(EntityStatus::UseTotemOfUndying);
+    }
+
+    true
+}
@@ -0,0 +1,269 @@
+//! The status effects that change the damage and health of entities (vanilla behavior).
+//!
+//! Valence only sends the effects to the clients, the gameplay of these effects is applied here
+//! and in the damage system.
+
+use valence::{
+    entity::{
+        active_status_effects::ActiveStatusEffects,
+        attributes::{EntityAttribute, EntityAttributes},
+        living::Health,
+        player::AbsorptionAmount,
+    },
+    prelude::*,
+    status_effects::StatusEffect,
+};
+
+use crate::damage::DamageType;
+
+/// The base ticks between the heals of the regeneration effect, halved per level.
+const REGENERATION_INTERVAL: u32 = 50;
+/// The damage reduction of each level of the resistance effect.
+const RESISTANCE_PER_LEVEL: f32 = 0.2;
+/// The absorption health of each level of the absorption effect.
+const ABSORPTION_PER_LEVEL: f32 = 4.0;
+
+/// Extra health (golden hearts) that is lost before the [`Health`] when the entity takes damage.
+///
+/// It is removed when the absorption effect of the entity ends.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Absorption(pub f32);
+
+impl Absorption {
+    /// The absorption of a level of the absorption effect (vanilla keeps the higher amount).
+    pub fn apply_level(&mut self, amplifier: u8) {
+        self.0 = self
+            .0
+            .max(ABSORPTION_PER_LEVEL * (f32::from(amplifier) + 1.0));
+    }
+
+    /// Absorbs as much of the damage as possible and returns the rest.
+    pub fn absorb(&mut self, damage: f32) -> f32 {
+        let absorbed = damage.min(self.0).max(0.0);
+        self.0 -= absorbed;
+
+        damage - absorbed
+    }
+}
+
+/// The max health attribute of the entity (20 without attributes).
+pub fn max_health(attributes: Option<&EntityAttributes>) -> f32 {
+    attributes
+        .and_then(|attributes| attributes.get_compute_value(EntityAttribute::GenericMaxHealth))
+        .unwrap_or(20.0) as f32
+}
+
+/// The amplifier of the effect if it is active.
+pub fn effect_amplifier(effects: Option<&ActiveStatusEffects>, effect: StatusEffect) -> Option<u8> {
+    effects
+        .and_then(|effects| effects.get_current_effect(effect))
+        .map(|effect| effect.amplifier())
+}
+
+/// The damage after the resistance and fire resistance effects.
+///
+/// Resistance does not reduce the damage of the void (java behavior).
+pub fn damage_after_effects(
+    effects: Option<&ActiveStatusEffects>,
+    damage_type: DamageType,
+    damage: f32,
+) -> f32 {
+    damage_after_effect_levels(
+        effect_amplifier(effects, StatusEffect::Resistance),
+        effect_amplifier(effects, StatusEffect::FireResistance).is_some(),
+        damage_type,
+        damage,
+    )
+}
+
+/// The damage after the amplifier of the resistance effect and the fire resistance effect.
+fn damage_after_effect_levels(
+    resistance: Option<u8>,
+    fire_resistance: bool,
+    damage_type: DamageType,
+    damage: f32,
+) -> f32 {
+    if damage_type.is_fire() && fire_resistance {
+        return 0.0;
+    }
+
+    match resistance {
+        Some(amplifier) if damage_type != DamageType::Void => {
+            let reduction = RESISTANCE_PER_LEVEL * (f32::from(amplifier) + 1.0);
+
+            damage * (1.0 - reduction).max(0.0)
+        }
+        _ => damage,
+    }
+}
+
+/// Heals entities with the regeneration effect and removes the absorption when its effect ended.
+#[allow(clippy::type_complexity)]
+pub(crate) fn health_effects_system(
+    mut query: Query<(
+        &mut Health,
+        &ActiveStatusEffects,
+        Option<&EntityAttributes>,
+        Option<&mut Absorption>,
+    )>,
+    mut tick: Local<u32>,
+) {
+    *tick = tick.wrapping_add(1);
+
+    for (mut health, effects, attributes, absorption) in query.iter_mut() {
+        if let Some(mut absorption) = absorption {
+            if absorption.0 > 0.0
+                && effects
+                    .get_current_effect(StatusEffect::Absorption)
+                    .is_none()
+            {
+                absorption.0 = 0.0;
+            }
+        }
+
+        let Some(amplifier) = effect_amplifier(Some(effects), StatusEffect::Regeneration) else {
+            continue;
+        };
+
+        let interval = regeneration_interval(amplifier);
+        let max_health = max_health(attributes);
+
+        if *tick % interval == 0 && health.0 > 0.0 && health.0 < max_health {
+            health.0 = (health.0 + 1.0).min(max_health);
+        }
+    }
+}
+
+/// The ticks between the heals of the regeneration effect, it heals every tick from level 6 on.
+fn regeneration_interval(amplifier: u8) -> u32 {
+    (REGENERATION_INTERVAL >> amplifier.min(31)).max(1)
+}
+
+/// Shows the absorption of players as golden hearts.
+pub(crate) fn sync_absorption(
+    mut query: Query<(&Absorption, &mut AbsorptionAmount), Changed<Absorption>>,
+) {
+    for (absorption, mut amount) in query.iter_mut() {
+        amount.0 = absorption.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_damage(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "damage {actual}, expected {expected}"
+        );
+    }
+
+    #[test]
+    fn absorption_absorbs_the_damage_first() {
+        let mut absorption = Absorption(4.0);
+
+        assert_eq!(absorption.absorb(3.0), 0.0);
+        assert_eq!(absorption, Absorption(1.0));
+
+        // Only the rest of the damage is taken.
+        assert_eq!(absorption.absorb(3.0), 2.0);
+        assert_eq!(absorption, Absorption(0.0));
+
+        assert_eq!(absorption.absorb(3.0), 3.0);
+        assert_eq!(absorption.absorb(0.0), 0.0);
+        assert_eq!(absorption, Absorption(0.0));
+    }
+
+    #[test]
+    fn absorption_levels_keep_the_higher_amount() {
+        let mut absorption = Absorption::default();
+
+        absorption.apply_level(0);
+        assert_eq!(absorption, Absorption(4.0));
+
+        absorption.apply_level(3);
+        assert_eq!(absorption, Absorption(16.0));
+
+        // A lower level does not replace the absorption.
+        absorption.apply_level(1);
+        assert_eq!(absorption, Absorption(16.0));
+
+        // A used up absorption is restored.
+        absorption.absorb(14.0);
+        absorption.apply_level(0);
+        assert_eq!(absorption, Absorption(4.0));
+    }
+
+    #[test]
+    fn resistance_levels() {
+        let damage = |amplifier| {
+            damage_after_effect_levels(Some(amplifier), false, DamageType::EntityAttack, 10.0)
+        };
+
+        assert_eq!(
+            damage_after_effect_levels(None, false, DamageType::EntityAttack, 10.0),
+            10.0
+        );
+        assert_damage(damage(0), 8.0);
+        assert_damage(damage(1), 6.0);
+        assert_damage(damage(2), 4.0);
+        // Resistance V and above make the entity immune.
+        assert_eq!(damage(4), 0.0);
+        assert_eq!(damage(9), 0.0);
+    }
+
+    #[test]
+    fn resistance_does_not_reduce_the_void() {
+        assert_eq!(
+            damage_after_effect_levels(Some(4), true, DamageType::Void, 4.0),
+            4.0
+        );
+        assert_eq!(
+            damage_after_effect_levels(Some(4), true, DamageType::Fall, 4.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn fire_resistance() {
+        for damage_type in [
+            DamageType::OnFire,
+            DamageType::Lava,
+            DamageType::InFire,
+            DamageType::HotFloor,
+        ] {
+            assert_eq!(
+                damage_after_effect_levels(None, true, damage_type, 4.0),
+                0.0,
+                "{damage_type:?}"
+            );
+        }
+
+        // Only the fire damage is prevented.
+        assert_eq!(
+            damage_after_effect_levels(None, true, DamageType::Cactus, 4.0),
+            4.0
+        );
+        assert_damage(
+            damage_after_effect_levels(Some(0), true, DamageType::Cactus, 4.0),
+            3.2,
+        );
+        assert_eq!(
+            damage_after_effect_levels(None, false, DamageType::Lava, 4.0),
+            4.0
+        );
+    }
+
+    #[test]
+    fn regeneration_interval_halves_per_level() {
+        assert_eq!(regeneration_interval(0), 50);
+        assert_eq!(regeneration_interval(1), 25);
+        assert_eq!(regeneration_interval(2), 12);
+        assert_eq!(regeneration_interval(4), 3);
+        assert_eq!(regeneration_interval(5), 1);
+        // Every tick instead of never.
+        assert_eq!(regeneration_interval(6), 1);
+        assert_eq!(regeneration_interval(255), 1);
+    }
+}
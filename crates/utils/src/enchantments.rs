@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use valence::{
     nbt::{value::ValueRef, Value},
+    prelude::Component,
     ItemStack,
 };
 
+use crate::damage::DamageType;
+
 // https://help.minecraft.net/hc/en-us/articles/360058730912-Minecraft-List-of-Enchantments
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Enchantment {
@@ -46,6 +49,7 @@ pub enum Enchantment {
     LuckOftheSea,
     Lure,
     SilkTouch,
+    Unbreaking,
 }
 
 impl Enchantment {
@@ -87,6 +91,7 @@ impl Enchantment {
             Enchantment::LuckOftheSea => "luck_of_the_sea",
             Enchantment::Lure => "lure",
             Enchantment::SilkTouch => "silk_touch",
+            Enchantment::Unbreaking => "unbreaking",
         }
     }
 
@@ -132,11 +137,73 @@ impl Enchantment {
             "luck_of_the_sea" | "minecraft:luck_of_the_sea" => Some(Enchantment::LuckOftheSea),
             "lure" | "minecraft:lure" => Some(Enchantment::Lure),
             "silk_touch" | "minecraft:silk_touch" => Some(Enchantment::SilkTouch),
+            "unbreaking" | "minecraft:unbreaking" => Some(Enchantment::Unbreaking),
             _ => None,
         }
     }
 }
 
+impl Enchantment {
+    /// The enchantment protection factor (EPF) of the enchantment for the given damage type.
+    ///
+    /// Returns 0 if the enchantment does not protect against the damage type.
+    pub fn protection_factor(&self, level: u32, damage_type: DamageType) -> u32 {
+        // https://minecraft.wiki/w/Armor#Enchantments
+        match (self, damage_type) {
+            // Protection reduces all damage except the void.
+            (Enchantment::Protection, DamageType::Void) => 0,
+            (Enchantment::Protection, _) => level,
+            (Enchantment::FireProtection, damage_type) if damage_type.is_fire() => level * 2,
+            (Enchantment::BlastProtection, DamageType::Explosion) => level * 2,
+            (Enchantment::ProjectileProtection, DamageType::Projectile) => level * 2,
+            (Enchantment::FeatherFalling, DamageType::Fall) => level * 3,
+            _ => 0,
+        }
+    }
+}
+
+/// Calculates the damage after the protection enchantments of the armor (this is the java edition formula).
+/// (java behavior)
+pub fn damage_after_enchantments(damage: f32, protection_factor: f32) -> f32 {
+    // https://minecraft.wiki/w/Armor#Enchantments
+    let protection_factor = protection_factor.clamp(0.0, 20.0);
+
+    damage * (1.0 - protection_factor / 25.0)
+}
+
+/// The formula of the protection enchantments against the damage that is not dealt by the
+/// combat (e.g. fall damage or lava), the combat plugin keeps it in sync with the combat config.
+///
+/// Entities without it use [`damage_after_enchantments`].
+#[derive(Component, Clone)]
+pub struct EnchantmentProtection(Option<Arc<dyn Fn(f32, f32) -> f32 + Send + Sync>>);
+
+impl EnchantmentProtection {
+    /// The formula gets the `damage` and the `protection_factor` and returns the reduced damage.
+    pub fn new(formula: impl Fn(f32, f32) -> f32 + Send + Sync + 'static) -> Self {
+        Self(Some(Arc::new(formula)))
+    }
+
+    /// The protection enchantments do not reduce the damage.
+    pub fn none() -> Self {
+        Self(None)
+    }
+
+    /// The damage after the protection enchantments.
+    pub fn apply(&self, damage: f32, protection_factor: f32) -> f32 {
+        match &self.0 {
+            Some(formula) => formula(damage, protection_factor),
+            None => damage,
+        }
+    }
+}
+
+impl Default for EnchantmentProtection {
+    fn default() -> Self {
+        Self::new(damage_after_enchantments)
+    }
+}
+
 pub trait ItemStackEnchantmentsExt {
     fn enchantments(&self) -> HashMap<Enchantment, u32>;
 }
@@ -1,46 +1,428 @@
-use valence::{prelude::Equipment, ItemKind};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use valence::{
+    entity::attributes::EntityAttribute,
+    prelude::{Equipment, Resource},
+    ItemKind, ItemStack,
+};
+
+use crate::{
+    attributes::{AttributeSlot, ItemStackAttributesExt},
+    damage::DamageType,
+    enchantments::ItemStackEnchantmentsExt,
+};
 
 pub trait EquipmentExt {
     /// The armor points of the equipment.
-    fn armor_points(&self) -> f32;
+    fn armor_points(&self, table: &ItemValueTable) -> f32;
     /// The armor toughness of the equipment.
-    fn armor_toughness(&self) -> f32;
+    fn armor_toughness(&self, table: &ItemValueTable) -> f32;
     /// The knockback resistance of the equipment.
     ///
     /// This is a value between 0.0 and 1.0.
     ///
     /// https://minecraft.wiki/w/Knockback_(mechanic)#Natural_knockback_resistance
-    fn knockback_resistance(&self) -> f32;
+    fn knockback_resistance(&self, table: &ItemValueTable) -> f32;
+    /// The sum of the enchantment protection factors (EPF) of the armor for the given damage type.
+    ///
+    /// The value is not capped.
+    ///
+    /// https://minecraft.wiki/w/Armor#Enchantments
+    fn protection_factor(&self, damage_type: DamageType) -> u32;
 }
 
 impl EquipmentExt for Equipment {
-    fn armor_points(&self) -> f32 {
-        self.head().item.armor_points()
-            + self.chest().item.armor_points()
-            + self.legs().item.armor_points()
-            + self.feet().item.armor_points()
+    fn armor_points(&self, table: &ItemValueTable) -> f32 {
+        self.head().armor_points(table, AttributeSlot::Head)
+            + self.chest().armor_points(table, AttributeSlot::Chest)
+            + self.legs().armor_points(table, AttributeSlot::Legs)
+            + self.feet().armor_points(table, AttributeSlot::Feet)
     }
 
-    fn armor_toughness(&self) -> f32 {
-        self.head().item.armor_toughness()
-            + self.chest().item.armor_toughness()
-            + self.legs().item.armor_toughness()
-            + self.feet().item.armor_toughness()
+    fn armor_toughness(&self, table: &ItemValueTable) -> f32 {
+        self.head().armor_toughness(table, AttributeSlot::Head)
+            + self.chest().armor_toughness(table, AttributeSlot::Chest)
+            + self.legs().armor_toughness(table, AttributeSlot::Legs)
+            + self.feet().armor_toughness(table, AttributeSlot::Feet)
     }
 
-    fn knockback_resistance(&self) -> f32 {
-        self.head().item.knockback_resistance()
-            + self.chest().item.knockback_resistance()
-            + self.legs().item.knockback_resistance()
-            + self.feet().item.knockback_resistance()
+    fn knockback_resistance(&self, table: &ItemValueTable) -> f32 {
+        self.head().knockback_resistance(table, AttributeSlot::Head)
+            + self
+                .chest()
+                .knockback_resistance(table, AttributeSlot::Chest)
+            + self.legs().knockback_resistance(table, AttributeSlot::Legs)
+            + self.feet().knockback_resistance(table, AttributeSlot::Feet)
+    }
+
+    fn protection_factor(&self, damage_type: DamageType) -> u32 {
+        [self.head(), self.chest(), self.legs(), self.feet()]
+            .into_iter()
+            .flat_map(|item| item.enchantments())
+            .map(|(enchantment, level)| enchantment.protection_factor(level, damage_type))
+            .sum()
+    }
+}
+
+/// The values of an item stack, these are the values of the [`ItemValueTable`]
+/// combined with the `AttributeModifiers` NBT tag of the item.
+pub trait ItemStackExt {
+    /// The armor points of the item stack in the given slot.
+    fn armor_points(&self, table: &ItemValueTable, slot: AttributeSlot) -> f32;
+    /// The armor toughness of the item stack in the given slot.
+    fn armor_toughness(&self, table: &ItemValueTable, slot: AttributeSlot) -> f32;
+    /// The knockback resistance of the item stack in the given slot.
+    fn knockback_resistance(&self, table: &ItemValueTable, slot: AttributeSlot) -> f32;
+    /// The attack damage of the item stack held in the main hand.
+    fn attack_damage(&self, table: &ItemValueTable) -> f32;
+    /// The attack speed of the item stack held in the main hand.
+    fn attack_speed(&self, table: &ItemValueTable) -> f32;
+}
+
+impl ItemStackExt for ItemStack {
+    fn armor_points(&self, table: &ItemValueTable, slot: AttributeSlot) -> f32 {
+        self.apply_attribute_modifiers(
+            EntityAttribute::GenericArmor,
+            slot,
+            table.get(self.item).armor_points as f64,
+        ) as f32
+    }
+
+    fn armor_toughness(&self, table: &ItemValueTable, slot: AttributeSlot) -> f32 {
+        self.apply_attribute_modifiers(
+            EntityAttribute::GenericArmorToughness,
+            slot,
+            table.get(self.item).armor_toughness as f64,
+        ) as f32
+    }
+
+    fn knockback_resistance(&self, table: &ItemValueTable, slot: AttributeSlot) -> f32 {
+        self.apply_attribute_modifiers(
+            EntityAttribute::GenericKnockbackResistance,
+            slot,
+            table.get(self.item).knockback_resistance as f64,
+        ) as f32
+    }
+
+    fn attack_damage(&self, table: &ItemValueTable) -> f32 {
+        self.apply_attribute_modifiers(
+            EntityAttribute::GenericAttackDamage,
+            AttributeSlot::MainHand,
+            table.get(self.item).attack_damage as f64,
+        ) as f32
+    }
+
+    fn attack_speed(&self, table: &ItemValueTable) -> f32 {
+        self.apply_attribute_modifiers(
+            EntityAttribute::GenericAttackSpeed,
+            AttributeSlot::MainHand,
+            table.get(self.item).attack_speed as f64,
+        ) as f32
+    }
+}
+
+/// The combat relevant values of an item kind.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ItemValues {
+    pub armor_points: f32,
+    pub armor_toughness: f32,
+    pub attack_damage: f32,
+    pub attack_speed: f32,
+    pub knockback_resistance: f32,
+}
+
+impl Default for ItemValues {
+    /// The values of an empty hand.
+    fn default() -> Self {
+        Self {
+            armor_points: 0.0,
+            armor_toughness: 0.0,
+            attack_damage: 1.0,
+            attack_speed: 4.0,
+            knockback_resistance: 0.0,
+        }
     }
 }
 
+/// A table with the [`ItemValues`] of every item kind.
+///
+/// The tables can be loaded from JSON or TOML files, so the values can be changed without recompiling.
+/// The file only has to contain the values that differ from the built-in table of its
+/// `combat_system` (`new` if it is not set), the missing values are taken from the built-in table:
+///
+/// ```toml
+/// combat_system = "old"
+///
+/// [items.diamond_sword]
+/// attack_damage = 8.0
+/// ```
+///
+/// The built-in tables are shipped as `crates/utils/data/item_values_old.toml` and
+/// `crates/utils/data/item_values_new.toml`, they can be regenerated with [`ItemValueTable::to_toml`]
+/// of [`ItemValueTable::from_combat_system`] (the comment at the top is not generated).
+#[derive(Debug, Clone, Default)]
+pub struct ItemValueTable {
+    /// The built-in table the values are based on, it is written to the data files.
+    pub combat_system: Option<CombatSystem>,
+    /// The values of items that are not in the table.
+    pub default: ItemValues,
+    pub items: HashMap<ItemKind, ItemValues>,
+}
+
+/// The representation of an [`ItemValueTable`] in a data file.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct ItemValueTableFile {
+    /// The built-in table that the values of the file are applied to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    combat_system: Option<CombatSystem>,
+    default: ItemValuesFile,
+    /// Sorted by the item name, so the generated files are stable.
+    items: BTreeMap<String, ItemValuesFile>,
+}
+
+/// The representation of [`ItemValues`] in a data file, the missing values are not changed.
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(default)]
+struct ItemValuesFile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    armor_points: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    armor_toughness: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attack_damage: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attack_speed: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    knockback_resistance: Option<f32>,
+}
+
+impl ItemValuesFile {
+    /// The values with the values of the file applied.
+    fn apply(&self, values: ItemValues) -> ItemValues {
+        ItemValues {
+            armor_points: self.armor_points.unwrap_or(values.armor_points),
+            armor_toughness: self.armor_toughness.unwrap_or(values.armor_toughness),
+            attack_damage: self.attack_damage.unwrap_or(values.attack_damage),
+            attack_speed: self.attack_speed.unwrap_or(values.attack_speed),
+            knockback_resistance: self
+                .knockback_resistance
+                .unwrap_or(values.knockback_resistance),
+        }
+    }
+}
+
+impl From<ItemValues> for ItemValuesFile {
+    fn from(values: ItemValues) -> Self {
+        Self {
+            armor_points: Some(values.armor_points),
+            armor_toughness: Some(values.armor_toughness),
+            attack_damage: Some(values.attack_damage),
+            attack_speed: Some(values.attack_speed),
+            knockback_resistance: Some(values.knockback_resistance),
+        }
+    }
+}
+
+impl ItemValueTable {
+    /// The built-in values of the combat system (the [`ItemKindExt`] values).
+    pub fn from_combat_system(combat_system: &CombatSystem) -> Self {
+        let default = ItemValues::default();
+
+        let items = ItemKind::ALL
+            .into_iter()
+            .map(|item| {
+                let values = ItemValues {
+                    armor_points: item.armor_points(),
+                    armor_toughness: item.armor_toughness(),
+                    attack_damage: item.attack_damage(combat_system),
+                    attack_speed: item.attack_speed(),
+                    knockback_resistance: item.knockback_resistance(),
+                };
+
+                (item, values)
+            })
+            .filter(|(_, values)| *values != default)
+            .collect();
+
+        Self {
+            combat_system: Some(*combat_system),
+            default,
+            items,
+        }
+    }
+
+    /// Get the values of the item kind.
+    pub fn get(&self, item: ItemKind) -> &ItemValues {
+        self.items.get(&item).unwrap_or(&self.default)
+    }
+
+    /// Parse a table from JSON.
+    pub fn from_json(json: &str) -> Result<Self, ItemValueTableError> {
+        serde_json::from_str::<ItemValueTableFile>(json)
+            .map_err(ItemValueTableError::Json)?
+            .try_into()
+    }
+
+    /// Parse a table from TOML.
+    pub fn from_toml(toml: &str) -> Result<Self, ItemValueTableError> {
+        toml::from_str::<ItemValueTableFile>(toml)
+            .map_err(ItemValueTableError::Toml)?
+            .try_into()
+    }
+
+    /// Load a table from a `.json` or `.toml` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ItemValueTableError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(ItemValueTableError::Io)?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::from_json(&content),
+            Some("toml") => Self::from_toml(&content),
+            _ => Err(ItemValueTableError::UnknownFormat),
+        }
+    }
+
+    /// Serialize the table to pretty printed JSON, e.g. to ship the built-in values as a data file.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&ItemValueTableFile::from(self))
+            .expect("item value tables can always be serialized")
+    }
+
+    /// Serialize the table to TOML, e.g. to ship the built-in values as a data file.
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(&ItemValueTableFile::from(self))
+            .expect("item value tables can always be serialized")
+    }
+}
+
+impl TryFrom<ItemValueTableFile> for ItemValueTable {
+    type Error = ItemValueTableError;
+
+    fn try_from(file: ItemValueTableFile) -> Result<Self, Self::Error> {
+        let combat_system = file.combat_system.unwrap_or(CombatSystem::New);
+        let built_in = Self::from_combat_system(&combat_system);
+
+        let default = file.default.apply(built_in.default);
+        let mut items = built_in.items;
+
+        for (name, values) in file.items {
+            let item = ItemKind::from_str(name.strip_prefix("minecraft:").unwrap_or(&name))
+                .ok_or(ItemValueTableError::UnknownItem(name))?;
+
+            let built_in_values = items.get(&item).copied().unwrap_or(default);
+            items.insert(item, values.apply(built_in_values));
+        }
+
+        Ok(Self {
+            combat_system: Some(combat_system),
+            default,
+            items,
+        })
+    }
+}
+
+impl From<&ItemValueTable> for ItemValueTableFile {
+    fn from(table: &ItemValueTable) -> Self {
+        Self {
+            combat_system: table.combat_system,
+            default: table.default.into(),
+            items: table
+                .items
+                .iter()
+                .map(|(item, values)| (item.to_str().to_string(), (*values).into()))
+                .collect(),
+        }
+    }
+}
+
+/// An error that can occur while loading an [`ItemValueTable`].
+#[derive(Debug)]
+pub enum ItemValueTableError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    /// The item name is not a valid item kind.
+    UnknownItem(String),
+    /// The file extension is neither `.json` nor `.toml`.
+    UnknownFormat,
+}
+
+impl fmt::Display for ItemValueTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemValueTableError::Io(err) => write!(f, "failed to read item value table: {err}"),
+            ItemValueTableError::Json(err) => write!(f, "invalid item value table: {err}"),
+            ItemValueTableError::Toml(err) => write!(f, "invalid item value table: {err}"),
+            ItemValueTableError::UnknownItem(name) => write!(f, "unknown item `{name}`"),
+            ItemValueTableError::UnknownFormat => {
+                write!(f, "item value tables must be .json or .toml files")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ItemValueTableError {}
+
+/// The item value tables that can be used by the combat configs.
+///
+/// The tables of both combat systems are always available and can be replaced,
+/// additional tables can be added by name (e.g. a table per game mode).
+#[derive(Resource)]
+pub struct ItemValueTables {
+    /// The table used for [`CombatSystem::Old`].
+    pub old: ItemValueTable,
+    /// The table used for [`CombatSystem::New`].
+    pub new: ItemValueTable,
+    named: HashMap<String, ItemValueTable>,
+}
+
+impl Default for ItemValueTables {
+    fn default() -> Self {
+        Self {
+            old: ItemValueTable::from_combat_system(&CombatSystem::Old),
+            new: ItemValueTable::from_combat_system(&CombatSystem::New),
+            named: HashMap::new(),
+        }
+    }
+}
+
+impl ItemValueTables {
+    /// Add a named table, replacing the table with the same name.
+    pub fn insert(&mut self, name: impl Into<String>, table: ItemValueTable) {
+        self.named.insert(name.into(), table);
+    }
+
+    /// Remove a named table.
+    pub fn remove(&mut self, name: &str) -> Option<ItemValueTable> {
+        self.named.remove(name)
+    }
+
+    /// Get the table with the given name.
+    ///
+    /// If `name` is `None` or there is no table with the name, the table of the combat system is returned.
+    pub fn get(&self, name: Option<&str>, combat_system: &CombatSystem) -> &ItemValueTable {
+        name.and_then(|name| self.named.get(name))
+            .unwrap_or(match combat_system {
+                CombatSystem::Old => &self.old,
+                CombatSystem::New => &self.new,
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CombatSystem {
     Old,
     New,
 }
 
+/// The built-in item values, these are used to create the default [`ItemValueTable`]s.
 pub trait ItemKindExt {
     /// The armor points of the item.
     fn armor_points(&self) -> f32;
@@ -244,3 +626,81 @@ impl ItemKindExt for ItemKind {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_FILE: &str = include_str!("../data/item_values_old.toml");
+    const NEW_FILE: &str = include_str!("../data/item_values_new.toml");
+
+    fn assert_same_values(actual: &ItemValueTable, expected: &ItemValueTable) {
+        assert_eq!(actual.combat_system, expected.combat_system);
+        assert_eq!(actual.default, expected.default);
+
+        for item in ItemKind::ALL {
+            assert_eq!(actual.get(item), expected.get(item), "values of {item:?}");
+        }
+    }
+
+    #[test]
+    fn shipped_files_match_the_built_in_tables() {
+        for (file, combat_system) in [(OLD_FILE, CombatSystem::Old), (NEW_FILE, CombatSystem::New)]
+        {
+            let built_in = ItemValueTable::from_combat_system(&combat_system);
+
+            assert_same_values(&ItemValueTable::from_toml(file).unwrap(), &built_in);
+
+            // The files only contain the items that differ from the default values.
+            let file = toml::from_str::<ItemValueTableFile>(file).unwrap();
+            assert_eq!(file.items.len(), built_in.items.len());
+        }
+    }
+
+    #[test]
+    fn generated_files_keep_the_combat_system() {
+        let table = ItemValueTable::from_combat_system(&CombatSystem::Old);
+        let toml = table.to_toml();
+
+        assert!(toml.contains("combat_system = \"old\""));
+        assert_same_values(&ItemValueTable::from_toml(&toml).unwrap(), &table);
+        assert_same_values(
+            &ItemValueTable::from_json(&table.to_json()).unwrap(),
+            &table,
+        );
+    }
+
+    #[test]
+    fn generated_files_are_sorted() {
+        let toml = ItemValueTable::from_combat_system(&CombatSystem::New).to_toml();
+
+        let names: Vec<&str> = toml
+            .lines()
+            .filter_map(|line| line.strip_prefix("[items.")?.strip_suffix(']'))
+            .collect();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+
+        assert!(!names.is_empty());
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn missing_values_are_taken_from_the_built_in_table() {
+        let table = ItemValueTable::from_toml(
+            "combat_system = \"old\"\n[items.diamond_sword]\nattack_damage = 8.0",
+        )
+        .unwrap();
+        let built_in = ItemValueTable::from_combat_system(&CombatSystem::Old);
+
+        assert_eq!(table.get(ItemKind::DiamondSword).attack_damage, 8.0);
+        assert_eq!(
+            table.get(ItemKind::DiamondSword).attack_speed,
+            built_in.get(ItemKind::DiamondSword).attack_speed
+        );
+        assert_eq!(
+            table.get(ItemKind::DiamondChestplate),
+            built_in.get(ItemKind::DiamondChestplate)
+        );
+    }
+}
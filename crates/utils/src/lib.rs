@@ -1,7 +1,13 @@
 pub mod aaab;
+pub mod attributes;
 pub mod damage;
+pub mod death_prevention;
+pub mod effects;
 pub mod enchantments;
 pub mod item_values;
+pub mod random;
+pub mod region;
+pub mod spawn_protection;
 
 pub use item_values::ItemKindExt;
 use valence::{math::Aabb, prelude::*};
@@ -0,0 +1,71 @@
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+use valence::prelude::*;
+
+/// The random number generator of the game mechanics (e.g. critical hits and durability).
+///
+/// All random rolls that change the outcome of the combat use this resource, so the combat can
+/// be reproduced with the same seed (e.g. a seed per match for replays or deterministic tests).
+#[derive(Resource)]
+pub struct GameRng {
+    rng: StdRng,
+    seed: u64,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            seed,
+        }
+    }
+
+    /// A generator with a random seed, the seed can be logged to reproduce the rolls later.
+    pub fn from_entropy() -> Self {
+        Self::new(rand::random())
+    }
+
+    /// The seed the generator was created or last reseeded with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts the generator with a new seed (e.g. at the start of a match).
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    /// A random number in `0.0..1.0`.
+    pub fn f32(&mut self) -> f32 {
+        self.rng.gen()
+    }
+
+    /// Returns true with the given chance (0.0 - 1.0).
+    pub fn chance(&mut self, chance: f32) -> bool {
+        self.f32() < chance
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+/// Allows using the generator with the methods of [`rand::Rng`] (e.g. `gen_range`).
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
@@ -0,0 +1,170 @@
+use valence::{math::Aabb, prelude::*};
+
+use crate::{aaab::AabbExt, damage::DamageType};
+
+/// An area of a layer with its own rules (e.g. a lobby without PvP).
+#[derive(Debug, Clone)]
+pub struct Region {
+    /// Identifies the region in [`Regions`].
+    pub name: String,
+    pub area: Aabb,
+    /// The flags of regions with a higher priority override the flags of overlapping regions.
+    pub priority: i32,
+    pub flags: RegionFlags,
+}
+
+impl Region {
+    pub fn new(name: impl Into<String>, area: Aabb, flags: RegionFlags) -> Self {
+        Self {
+            name: name.into(),
+            area,
+            priority: 0,
+            flags,
+        }
+    }
+
+    /// A region that contains all blocks between the two corners (including the corners).
+    pub fn from_blocks(
+        name: impl Into<String>,
+        corner: BlockPos,
+        other_corner: BlockPos,
+        flags: RegionFlags,
+    ) -> Self {
+        let min = DVec3::new(
+            corner.x.min(other_corner.x).into(),
+            corner.y.min(other_corner.y).into(),
+            corner.z.min(other_corner.z).into(),
+        );
+        let max = DVec3::new(
+            corner.x.max(other_corner.x).into(),
+            corner.y.max(other_corner.y).into(),
+            corner.z.max(other_corner.z).into(),
+        ) + DVec3::ONE;
+
+        Self::new(name, Aabb::new(min, max), flags)
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn contains(&self, position: DVec3) -> bool {
+        self.area.contains_position(position)
+    }
+}
+
+/// The rules of a [`Region`], `None` keeps the rule of the overlapping regions with a lower
+/// priority (everything is allowed outside of regions).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegionFlags {
+    /// If entities can attack each other (this includes mobs and projectiles).
+    pub pvp: Option<bool>,
+    pub fall_damage: Option<bool>,
+    /// If entities take damage from fire, lava and magma blocks (see [`DamageType::is_fire`]).
+    pub fire: Option<bool>,
+    /// If players get hungry, there is no hunger system in this crate, it is for the server.
+    pub hunger: Option<bool>,
+    /// The id of the combat config that is used in the region instead of the config of the
+    /// players (the config override of a player is still used).
+    pub combat_config: Option<u64>,
+}
+
+impl RegionFlags {
+    /// Flags that disable all damage and hunger, e.g. for a lobby.
+    pub fn safe_zone() -> Self {
+        Self {
+            pvp: Some(false),
+            fall_damage: Some(false),
+            fire: Some(false),
+            hunger: Some(false),
+            combat_config: None,
+        }
+    }
+
+    /// Sets the flags that are not set with the flags of `other`.
+    fn merge(&mut self, other: &RegionFlags) {
+        self.pvp = self.pvp.or(other.pvp);
+        self.fall_damage = self.fall_damage.or(other.fall_damage);
+        self.fire = self.fire.or(other.fire);
+        self.hunger = self.hunger.or(other.hunger);
+        self.combat_config = self.combat_config.or(other.combat_config);
+    }
+
+    pub fn pvp(&self) -> bool {
+        self.pvp.unwrap_or(true)
+    }
+
+    pub fn fall_damage(&self) -> bool {
+        self.fall_damage.unwrap_or(true)
+    }
+
+    pub fn fire(&self) -> bool {
+        self.fire.unwrap_or(true)
+    }
+
+    pub fn hunger(&self) -> bool {
+        self.hunger.unwrap_or(true)
+    }
+
+    /// If the damage is allowed by the flags, `attacked` is true if another entity caused it.
+    pub fn allows_damage(&self, damage_type: DamageType, attacked: bool) -> bool {
+        match damage_type {
+            DamageType::Fall => self.fall_damage(),
+            DamageType::EntityAttack | DamageType::Projectile | DamageType::Thorns if attacked => {
+                self.pvp()
+            }
+            damage_type if damage_type.is_fire() => self.fire(),
+            _ => true,
+        }
+    }
+}
+
+/// The regions of a layer, add it to the layer entity.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Regions {
+    regions: Vec<Region>,
+}
+
+impl Regions {
+    /// Add a region or replace the region with the same name.
+    pub fn insert(&mut self, region: Region) {
+        self.remove(&region.name);
+        self.regions.push(region);
+        // The highest priority first, so merging the flags keeps the most important ones.
+        self.regions
+            .sort_by_key(|region| std::cmp::Reverse(region.priority));
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Region> {
+        let index = self.regions.iter().position(|region| region.name == name)?;
+
+        Some(self.regions.remove(index))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Region> {
+        self.regions.iter().find(|region| region.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter()
+    }
+
+    /// The regions that contain the position, the highest priority first.
+    pub fn at(&self, position: DVec3) -> impl Iterator<Item = &Region> {
+        self.regions
+            .iter()
+            .filter(move |region| region.contains(position))
+    }
+
+    /// The flags at the position, merged from all regions that contain it.
+    pub fn flags_at(&self, position: DVec3) -> RegionFlags {
+        let mut flags = RegionFlags::default();
+
+        for region in self.at(position) {
+            flags.merge(&region.flags);
+        }
+
+        flags
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use bevy_time::{Time, Timer, TimerMode};
+use valence::{entity::entity::Flags, prelude::*, title::SetTitle};
+
+/// Makes an entity immune to all damage except the void for a while, e.g. after it respawned.
+///
+/// The protection is removed when it ended, see [`SpawnProtection::cancel`] to end it early.
+#[derive(Component, Debug)]
+pub struct SpawnProtection {
+    timer: Timer,
+    /// What happens when the protected entity attacks another entity.
+    pub attacks: ProtectedAttacks,
+    /// Let the entity glow while it is protected.
+    pub glowing: bool,
+    /// If the protection turned the glowing on, the glowing of other sources is kept.
+    glowing_shown: bool,
+    /// Show the remaining time in the action bar (only for clients).
+    pub action_bar: bool,
+    /// The seconds that are shown in the action bar.
+    shown_seconds: u64,
+}
+
+/// What happens when an entity with [`SpawnProtection`] attacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtectedAttacks {
+    /// The attack ends the protection.
+    Cancel,
+    /// The entity can not attack while it is protected.
+    Deny,
+    /// The entity can attack and keeps the protection.
+    Allow,
+}
+
+impl SpawnProtection {
+    /// The first attack of the entity ends the protection.
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            timer: Timer::new(duration, TimerMode::Once),
+            attacks: ProtectedAttacks::Cancel,
+            glowing: true,
+            glowing_shown: false,
+            action_bar: true,
+            shown_seconds: 0,
+        }
+    }
+
+    pub fn with_attacks(mut self, attacks: ProtectedAttacks) -> Self {
+        self.attacks = attacks;
+        self
+    }
+
+    /// If the entity is still protected.
+    pub fn is_active(&self) -> bool {
+        !self.timer.remaining().is_zero()
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.timer.remaining()
+    }
+
+    /// Protects the entity for `duration` from now on, e.g. when it respawned while it was still
+    /// protected.
+    pub fn restart(&mut self, duration: Duration) {
+        self.timer = Timer::new(duration, TimerMode::Once);
+    }
+
+    /// Ends the protection.
+    pub fn cancel(&mut self) {
+        let duration = self.timer.duration();
+        self.timer.set_elapsed(duration);
+    }
+}
+
+pub(crate) fn update_spawn_protection(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut SpawnProtection,
+        Option<&mut Flags>,
+        Option<&mut Client>,
+    )>,
+    time: Res<Time>,
+) {
+    for (entity, mut protection, flags, client) in query.iter_mut() {
+        protection.timer.tick(time.delta());
+
+        let active = protection.is_active();
+
+        if let Some(mut flags) = flags {
+            let glowing = active && protection.glowing;
+
+            if glowing && !protection.glowing_shown && !flags.glowing() {
+                flags.set_glowing(true);
+                protection.glowing_shown = true;
+            } else if !glowing && protection.glowing_shown {
+                flags.set_glowing(false);
+                protection.glowing_shown = false;
+            }
+        }
+
+        if let Some(mut client) = client.filter(|_| protection.action_bar) {
+            let seconds = protection.remaining().as_secs_f32().ceil() as u64;
+
+            if !active {
+                client.set_action_bar("");
+            } else if seconds != protection.shown_seconds {
+                protection.shown_seconds = seconds;
+                client.set_action_bar(format!("Spawn protection: {seconds}s").color(Color::GREEN));
+            }
+        }
+
+        if !active {
+            commands.entity(entity).remove::<SpawnProtection>();
+        }
+    }
+}
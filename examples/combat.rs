@@ -1,9 +1,17 @@
+use std::time::Duration;
+
 use bevy_time::TimePlugin;
 // #![cfg(feature = "chat")]
-use combat::{CombatPlugin, CombatState};
+use combat::{
+    config_file::CombatConfigReloader, feedback::CombatFeedbackPlugin, CombatPlugin, CombatState,
+};
+use consumables::ConsumablesPlugin;
 use fall_damage::{FallDamagePlugin, FallingState};
 use physics::{Acceleration, BlockCollisionConfig, PhysicsPlugin, StopOnBlockCollision};
-use utils::damage::{DamageEvent, DamagePlugin, TakesDamage};
+use utils::{
+    damage::{DamageEvent, DamagePlugin, TakesDamage},
+    region::{Region, RegionFlags, Regions},
+};
 use valence::{
     entity::{zombie::ZombieEntityBundle, EntityStatuses},
     equipment::EquipmentInventorySync,
@@ -20,6 +28,13 @@ pub fn main() {
         .add_plugins(PhysicsPlugin)
         .add_plugins(DamagePlugin)
         .add_plugins(CombatPlugin)
+        .add_plugins(CombatFeedbackPlugin)
+        .add_plugins(ConsumablesPlugin)
+        // Edit combat.toml while the server runs to change the combat rules.
+        .insert_resource(CombatConfigReloader::new(
+            "combat.toml",
+            Duration::from_secs(1),
+        ))
         .add_systems(
             Update,
             (init_clients, despawn_disconnected_clients, on_damage),
@@ -49,7 +64,16 @@ fn setup(
         }
     }
 
-    let id = commands.spawn(layer).id();
+    // A small safe zone at the spawn, players can walk out to fight.
+    let mut regions = Regions::default();
+    regions.insert(Region::from_blocks(
+        "spawn",
+        BlockPos::new(-3, SPAWN_Y, -3),
+        BlockPos::new(3, SPAWN_Y + 10, 3),
+        RegionFlags::safe_zone(),
+    ));
+
+    let id = commands.spawn((layer, regions)).id();
 
     commands
         .spawn(ZombieEntityBundle {
@@ -116,6 +140,7 @@ fn init_clients(
         inventory.set_slot(36, ItemStack::new(ItemKind::DiamondSword, 1, None));
         inventory.set_slot(37, ItemStack::new(ItemKind::DiamondPickaxe, 1, None));
         inventory.set_slot(38, ItemStack::new(ItemKind::DiamondAxe, 1, None));
+        inventory.set_slot(39, ItemStack::new(ItemKind::GoldenApple, 8, None));
     }
 }
 
@@ -0,0 +1,130 @@
+use bevy_time::TimePlugin;
+use combat::{CombatPlugin, CombatState};
+use fall_damage::{FallDamagePlugin, FallingState};
+use mob_ai::{MobAi, MobAiBundle, MobAiPlugin};
+use physics::PhysicsPlugin;
+use utils::damage::{DamagePlugin, TakesDamage};
+use valence::{
+    entity::{skeleton::SkeletonEntityBundle, zombie::ZombieEntityBundle},
+    equipment::EquipmentInventorySync,
+    prelude::*,
+};
+
+const SPAWN_Y: i32 = 64;
+
+pub fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(FallDamagePlugin)
+        .add_plugins(TimePlugin)
+        .add_systems(Startup, setup)
+        .add_plugins(PhysicsPlugin)
+        .add_plugins(DamagePlugin)
+        .add_plugins(CombatPlugin)
+        .add_plugins(MobAiPlugin)
+        .add_systems(Update, (init_clients, despawn_disconnected_clients))
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    server: Res<Server>,
+    dimensions: Res<DimensionTypeRegistry>,
+    biomes: Res<BiomeRegistry>,
+) {
+    let mut layer = LayerBundle::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+    for z in -5..5 {
+        for x in -5..5 {
+            layer.chunk.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    for z in -25..25 {
+        for x in -25..25 {
+            layer
+                .chunk
+                .set_block([x, SPAWN_Y, z], BlockState::GRASS_BLOCK);
+        }
+    }
+
+    // A wall with a step, so the zombie has to walk around or jump.
+    for x in -10..10 {
+        layer
+            .chunk
+            .set_block([x, SPAWN_Y + 1, 8], BlockState::STONE);
+        layer
+            .chunk
+            .set_block([x, SPAWN_Y + 2, 8], BlockState::STONE);
+    }
+    layer
+        .chunk
+        .set_block([10, SPAWN_Y + 1, 8], BlockState::STONE);
+
+    let id = commands.spawn(layer).id();
+
+    commands
+        .spawn(ZombieEntityBundle {
+            position: Position([0.5, f64::from(SPAWN_Y) + 1.0, 15.5].into()),
+            layer: EntityLayerId(id),
+
+            ..Default::default()
+        })
+        .insert(MobAiBundle::new(MobAi::melee()))
+        .insert(TakesDamage::default());
+
+    commands
+        .spawn(SkeletonEntityBundle {
+            position: Position([-10.5, f64::from(SPAWN_Y) + 1.0, -10.5].into()),
+            layer: EntityLayerId(id),
+
+            ..Default::default()
+        })
+        .insert(MobAiBundle::new(MobAi::ranged()))
+        .insert(TakesDamage::default());
+}
+
+#[allow(clippy::type_complexity)]
+fn init_clients(
+    mut commands: Commands,
+    mut clients: Query<
+        (
+            Entity,
+            &mut Position,
+            &mut EntityLayerId,
+            &mut VisibleChunkLayer,
+            &mut VisibleEntityLayers,
+            &mut GameMode,
+            &mut Inventory,
+        ),
+        Added<Client>,
+    >,
+    layers: Query<Entity, (With<ChunkLayer>, With<EntityLayer>)>,
+) {
+    for (
+        player_ent,
+        mut pos,
+        mut layer_id,
+        mut visible_chunk_layer,
+        mut visible_entity_layers,
+        mut game_mode,
+        mut inventory,
+    ) in &mut clients
+    {
+        let layer = layers.single();
+
+        pos.0 = [0.0, f64::from(SPAWN_Y) + 1.0, 0.0].into();
+        layer_id.0 = layer;
+        visible_chunk_layer.0 = layer;
+        visible_entity_layers.0.insert(layer);
+        *game_mode = GameMode::Survival;
+
+        commands
+            .entity(player_ent)
+            .insert(CombatState::default())
+            .insert(FallingState::new(pos.0))
+            .insert(EquipmentInventorySync);
+
+        inventory.set_slot(36, ItemStack::new(ItemKind::DiamondSword, 1, None));
+    }
+}
@@ -1,5 +1,8 @@
 use bevy_time::TimePlugin;
-use physics::{Acceleration, BlockCollisionConfig, Drag, PhysicsPlugin, SpeedLimit};
+use physics::{
+    Acceleration, BlockCollisionConfig, Drag, EntityCollisionConfig, EntityEntityCollisionEvent,
+    PhysicsPlugin, SpeedLimit,
+};
 use valence::entity::chicken::ChickenEntityBundle;
 use valence::entity::entity::NoGravity;
 use valence::entity::Velocity;
@@ -19,6 +22,7 @@ pub fn main() {
                 init_clients,
                 despawn_disconnected_clients,
                 on_player_sneak_click,
+                on_entity_collision,
             ),
         )
         .run();
@@ -126,6 +130,33 @@ fn on_player_sneak_click(
             .insert(Acceleration(Vec3::new(0.0, -20.0, 0.0)))
             .insert(Drag(Vec3::new(0.99 / 20.0, 0.99 / 20.0, 0.99 / 20.0)))
             .insert(SpeedLimit(100.0))
-            .insert(BlockCollisionConfig::default());
+            .insert(BlockCollisionConfig::default())
+            .insert(EntityCollisionConfig::solid(1.0));
+    }
+}
+
+/// Shows the collisions with crit particles at the entity that was moved into the other one.
+fn on_entity_collision(
+    mut events: EventReader<EntityEntityCollisionEvent>,
+    entities: Query<(&Position, &EntityLayerId)>,
+    mut layers: Query<&mut ChunkLayer>,
+) {
+    for event in events.read() {
+        let Ok((position, layer_id)) = entities.get(event.entity1) else {
+            continue;
+        };
+
+        let Ok(mut layer) = layers.get_mut(layer_id.0) else {
+            continue;
+        };
+
+        layer.play_particle(
+            &Particle::Crit,
+            false,
+            position.0 + DVec3::new(0.0, 0.5, 0.0),
+            [0.2, 0.2, 0.2],
+            0.1,
+            5,
+        );
     }
 }
@@ -1,4 +1,16 @@
+#[cfg(feature = "anti_cheat")]
+pub use anti_cheat;
 #[cfg(feature = "chat")]
 pub use chat;
+#[cfg(feature = "consumables")]
+pub use consumables;
+#[cfg(feature = "environment_damage")]
+pub use environment_damage;
+#[cfg(feature = "mob_ai")]
+pub use mob_ai;
 #[cfg(feature = "physics")]
 pub use physics;
+#[cfg(feature = "replay")]
+pub use replay;
+#[cfg(feature = "respawn")]
+pub use respawn;